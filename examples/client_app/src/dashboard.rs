@@ -3,6 +3,7 @@ use axum::{
     response::{Html, IntoResponse},
 };
 use chrono::{DateTime, Utc};
+use chrono_tz::Tz;
 use reqwest::Client;
 use serde::Deserialize;
 use std::sync::Arc;
@@ -30,6 +31,11 @@ struct SensorWarning {
     status: String,
 }
 
+/// Format an instant in the display timezone, with the zone abbreviation
+fn format_timestamp(timestamp: DateTime<Utc>, tz: Tz) -> String {
+    timestamp.with_timezone(&tz).format("%Y-%m-%d %H:%M:%S %Z").to_string()
+}
+
 /// Dashboard view with visualizations
 pub async fn dashboard(State(state): State<Arc<AppState>>) -> impl IntoResponse {
     // Fetch all sensors
@@ -105,7 +111,7 @@ pub async fn dashboard(State(state): State<Arc<AppState>>) -> impl IntoResponse
                 _ => "N/A".to_string(),
             };
             
-            let time_str = reading.timestamp.format("%Y-%m-%d %H:%M:%S").to_string();
+            let time_str = format_timestamp(reading.timestamp, state.display_tz);
             
             table.push_str(&format!(
                 r#"
@@ -280,4 +286,21 @@ pub async fn dashboard(State(state): State<Arc<AppState>>) -> impl IntoResponse
         power_sensors_str,
         readings_table
     ))
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_timestamp_in_display_zone() {
+        let winter = Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap();
+        let summer = Utc.with_ymd_and_hms(2024, 7, 15, 12, 0, 0).unwrap();
+        
+        assert_eq!(format_timestamp(winter, Tz::UTC), "2024-01-15 12:00:00 UTC");
+        assert_eq!(format_timestamp(winter, chrono_tz::America::New_York), "2024-01-15 07:00:00 EST");
+        assert_eq!(format_timestamp(summer, chrono_tz::America::New_York), "2024-07-15 08:00:00 EDT");
+        assert_eq!(format_timestamp(summer, chrono_tz::Europe::Berlin), "2024-07-15 14:00:00 CEST");
+    }
+}
@@ -8,6 +8,7 @@ use axum::{
     routing::{get, post},
     Router,
 };
+use chrono_tz::Tz;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
@@ -22,6 +23,7 @@ use crate::models::*;
 pub struct AppState {
     api_base_url: String,
     http_client: Client,
+    display_tz: Tz,
     active_sensors: RwLock<HashMap<i64, String>>,
 }
 
@@ -643,10 +645,22 @@ async fn main() {
     
     info!("Using API endpoint: {}", api_base_url);
     
+    // Timezone used to display reading times, e.g. DISPLAY_TZ=Europe/Berlin
+    let display_tz = match std::env::var("DISPLAY_TZ") {
+        Ok(name) => name.parse::<Tz>().unwrap_or_else(|_| {
+            tracing::warn!("Unknown DISPLAY_TZ '{}', showing times in UTC", name);
+            Tz::UTC
+        }),
+        Err(_) => Tz::UTC,
+    };
+    
+    info!("Displaying times in {}", display_tz);
+    
     // Create app state with HTTP client
     let app_state = Arc::new(AppState {
         api_base_url,
         http_client: Client::new(),
+        display_tz,
         active_sensors: RwLock::new(HashMap::new()),
     });
     
@@ -38,6 +38,10 @@ pub struct ReadingResponse {
     pub value: Option<f64>,
     pub state: Option<i64>,
     pub change_type: Option<String>,
+    pub annotation: Option<String>,
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub out_of_range: bool,
 }
 
 /// Form model for logging a reading
@@ -83,8 +87,10 @@ pub struct TimeSeriesDataset {
     pub sensor_id: i64,
     pub sensor_name: String,
     pub unit: String,
-    pub data: Vec<f64>,
-    pub moving_average: Option<Vec<f64>>,
+    pub data: Vec<Option<f64>>,  // null for buckets without readings
+    pub moving_average: Option<Vec<Option<f64>>>,  // null where the whole window is empty
+    #[serde(default)]
+    pub interpolated: Option<Vec<Option<f64>>>,  // Only with resample=true
 }
 
 #[derive(Debug, Serialize, Deserialize)]
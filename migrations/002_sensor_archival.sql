@@ -0,0 +1,2 @@
+-- Archived sensors stay readable but reject new readings, sessions and edits
+ALTER TABLE sensors ADD COLUMN archived INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,3 @@
+-- Dashboard display metadata
+ALTER TABLE sensors ADD COLUMN display_color TEXT;     -- '#RRGGBB' or '#RGB'
+ALTER TABLE sensors ADD COLUMN display_order INTEGER;
@@ -0,0 +1,2 @@
+-- Minimum change in value worth storing; NULL stores every reading
+ALTER TABLE sensors ADD COLUMN deadband REAL;
@@ -0,0 +1,9 @@
+-- Tokens that may only write readings for a single sensor
+CREATE TABLE api_tokens (
+    token TEXT PRIMARY KEY,
+    sensor_id INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,  -- Unix timestamp
+    FOREIGN KEY (sensor_id) REFERENCES sensors(sensor_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_api_tokens_sensor ON api_tokens(sensor_id);
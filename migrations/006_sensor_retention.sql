@@ -0,0 +1,2 @@
+-- Per-sensor retention override for maintenance pruning, in days; NULL uses the request's default
+ALTER TABLE sensors ADD COLUMN retention_days INTEGER;
@@ -0,0 +1,2 @@
+-- Optional human note on a reading, e.g. 'calibration run' or 'door opened'
+ALTER TABLE readings ADD COLUMN annotation TEXT;
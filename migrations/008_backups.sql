@@ -0,0 +1,7 @@
+-- One row per completed backup, so health checks can report the latest
+CREATE TABLE backups (
+    backup_id INTEGER PRIMARY KEY AUTOINCREMENT,
+    path TEXT NOT NULL,
+    size_bytes INTEGER NOT NULL,
+    created_at INTEGER NOT NULL  -- Unix timestamp
+);
@@ -0,0 +1,2 @@
+-- When a sensor was soft-deleted; NULL while active. Disabled sensors keep their readings
+ALTER TABLE sensors ADD COLUMN disabled_at INTEGER;
@@ -0,0 +1,9 @@
+-- Responses to requests sent with an Idempotency-Key header, replayed when a client retries
+CREATE TABLE idempotency_keys (
+    idempotency_key TEXT PRIMARY KEY,
+    status_code INTEGER,  -- NULL while the first request is still being handled
+    response TEXT,  -- JSON body sent to the first request
+    created_at INTEGER NOT NULL  -- Unix timestamp
+);
+
+CREATE INDEX idx_idempotency_keys_created ON idempotency_keys(created_at);
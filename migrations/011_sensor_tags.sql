@@ -0,0 +1,9 @@
+-- Free-form labels grouping sensors beyond their location, stored lowercase
+CREATE TABLE sensor_tags (
+    sensor_id INTEGER NOT NULL,
+    tag TEXT NOT NULL,
+    PRIMARY KEY (sensor_id, tag),
+    FOREIGN KEY (sensor_id) REFERENCES sensors(sensor_id) ON DELETE CASCADE
+);
+
+CREATE INDEX idx_sensor_tags_tag ON sensor_tags(tag);
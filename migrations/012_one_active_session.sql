@@ -0,0 +1,10 @@
+-- At most one open logging session per sensor. Older duplicates left by
+-- concurrent starts are closed at their own start time first
+UPDATE logging_sessions
+SET end_time = start_time
+WHERE end_time IS NULL
+  AND session_id NOT IN (
+      SELECT MAX(session_id) FROM logging_sessions WHERE end_time IS NULL GROUP BY sensor_id
+  );
+
+CREATE UNIQUE INDEX idx_sessions_one_active ON logging_sessions(sensor_id) WHERE end_time IS NULL;
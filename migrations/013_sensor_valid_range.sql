@@ -0,0 +1,5 @@
+-- Plausible value range per sensor; NULL bounds accept any value
+ALTER TABLE sensors ADD COLUMN valid_min REAL;
+ALTER TABLE sensors ADD COLUMN valid_max REAL;
+-- Set on readings stored despite falling outside their sensor's valid range
+ALTER TABLE readings ADD COLUMN out_of_range INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,9 @@
+-- One reading per sensor per instant. Earlier duplicates are dropped,
+-- keeping the most recently stored reading for each instant
+DELETE FROM readings
+WHERE reading_id NOT IN (
+    SELECT MAX(reading_id) FROM readings GROUP BY sensor_id, timestamp
+);
+
+DROP INDEX idx_readings_sensor_time;
+CREATE UNIQUE INDEX idx_readings_sensor_time ON readings(sensor_id, timestamp);
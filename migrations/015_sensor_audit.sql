@@ -0,0 +1,12 @@
+-- Every change to a sensor's configuration, as JSON snapshots of the sensor
+-- No foreign key, so the trail outlives a hard-deleted sensor
+CREATE TABLE sensor_audit (
+    audit_id INTEGER PRIMARY KEY,
+    sensor_id INTEGER NOT NULL,
+    action TEXT NOT NULL,  -- 'create', 'update' or 'delete'
+    old_value TEXT,  -- Sensor before the change; NULL on create
+    new_value TEXT,  -- Sensor after the change; NULL once hard-deleted
+    changed_at INTEGER NOT NULL  -- Unix timestamp
+);
+
+CREATE INDEX idx_sensor_audit_sensor ON sensor_audit(sensor_id, audit_id);
@@ -0,0 +1,144 @@
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        Query, State,
+    },
+    response::Response,
+};
+use serde::Deserialize;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+
+use crate::api::AppState;
+use crate::models::ReadingResponse;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct LiveReadingsQuery {
+    pub sensor_id: Option<i64>,  // Only stream this sensor's readings
+}
+
+/// Stream newly stored readings over a WebSocket as JSON text messages
+pub async fn readings_socket(
+    ws: WebSocketUpgrade,
+    State(state): State<AppState>,
+    Query(query): Query<LiveReadingsQuery>,
+) -> Response {
+    // Subscribe before upgrading so nothing stored after the handshake is missed
+    let receiver = state.readings.subscribe();
+    ws.on_upgrade(move |socket| stream_readings(socket, receiver, query.sensor_id))
+}
+
+/// Forward readings to one client until it disconnects
+async fn stream_readings(mut socket: WebSocket, mut receiver: Receiver<ReadingResponse>, sensor_id: Option<i64>) {
+    loop {
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(reading) => {
+                    if sensor_id.is_some_and(|id| id != reading.sensor_id) {
+                        continue;
+                    }
+                    
+                    let Ok(text) = serde_json::to_string(&reading) else {
+                        continue;
+                    };
+                    if socket.send(Message::Text(text)).await.is_err() {
+                        break;
+                    }
+                }
+                // A slow client skips what it missed rather than stalling everyone else
+                Err(RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Live readings client fell behind; skipped {} readings", skipped);
+                }
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Clients only listen; anything else they send is ignored
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use futures::StreamExt;
+    use std::time::Duration;
+    use tokio::net::TcpStream;
+    use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+    use crate::{
+        api::{create_router, AppState},
+        models::ReadingResponse,
+        utils::test_utils::{setup_test_db, create_test_sensor, send_request_with},
+    };
+    
+    type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+    
+    async fn next_reading(socket: &mut Socket) -> Result<ReadingResponse> {
+        match tokio::time::timeout(Duration::from_secs(5), socket.next()).await? {
+            Some(Ok(Message::Text(text))) => Ok(serde_json::from_str(&text)?),
+            other => Err(anyhow::anyhow!("expected a reading, got {:?}", other)),
+        }
+    }
+    
+    #[tokio::test]
+    async fn test_live_readings_are_broadcast() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first = create_test_sensor(&conn)?;
+        let second = create_test_sensor(&conn)?;
+        
+        let state = AppState::default();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let addr = listener.local_addr()?;
+        let app = create_router(state.clone());
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        
+        let (mut all, _) = connect_async(format!("ws://{}/api/ws/readings", addr)).await?;
+        let (mut filtered, _) = connect_async(format!("ws://{}/api/ws/readings?sensor_id={}", addr, second)).await?;
+        
+        let request = Request::post("/api/readings")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(r#"{{"sensor_id": {}, "timestamp": 100, "value": 21.5}}"#, first)))?;
+        let (status, _, _) = send_request_with(state.clone(), request).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        
+        let body = format!(
+            r#"{{"readings": [{{"sensor_id": {}, "timestamp": 200, "value": 1.0}}, {{"sensor_id": {}, "timestamp": 200, "value": 2.0}}]}}"#,
+            second, first
+        );
+        let request = Request::post("/api/readings/bulk")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let (status, _, _) = send_request_with(state.clone(), request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let mut received = Vec::new();
+        for _ in 0..3 {
+            let reading = next_reading(&mut all).await?;
+            received.push((reading.sensor_id, reading.value));
+        }
+        assert_eq!(received, vec![(first, Some(21.5)), (second, Some(1.0)), (first, Some(2.0))]);
+        
+        // The filtered client only sees its own sensor
+        let reading = next_reading(&mut filtered).await?;
+        assert_eq!((reading.sensor_id, reading.value), (second, Some(1.0)));
+        
+        // Disconnecting drops the subscription
+        all.close(None).await?;
+        filtered.close(None).await?;
+        for _ in 0..100 {
+            if state.readings.receiver_count() == 0 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(state.readings.receiver_count(), 0);
+        
+        Ok(())
+    }
+}
@@ -1,28 +1,108 @@
+pub mod live;
 pub mod sensors;
 pub mod readings;
 pub mod sessions;
+pub mod status;
 pub mod system;
+pub mod visualizations;
+pub mod response;
 
 use axum::{
+    extract::{DefaultBodyLimit, Request},
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode, Uri},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post, put, delete},
     Router,
 };
 
-pub fn create_router() -> Router {
-    Router::new()
+use tokio::sync::broadcast;
+use tower_http::{
+    compression::CompressionLayer,
+    cors::{AllowOrigin, CorsLayer},
+};
+
+use crate::config;
+use crate::models::{ApiToken, Reading, ReadingResponse};
+use crate::utils::error::AppError;
+
+/// Readings a slow live subscriber may fall behind by before it skips ahead
+const READING_FEED_CAPACITY: usize = 1024;
+
+/// State shared by every handler
+#[derive(Clone)]
+pub struct AppState {
+    /// Newly stored readings, fanned out to live subscribers
+    pub readings: broadcast::Sender<ReadingResponse>,
+}
+
+impl Default for AppState {
+    fn default() -> Self {
+        let (readings, _) = broadcast::channel(READING_FEED_CAPACITY);
+        Self { readings }
+    }
+}
+
+impl AppState {
+    /// Send newly stored readings to live subscribers
+    ///
+    /// Skips the lookup entirely when nobody is listening.
+    pub fn publish_readings(&self, reading_ids: &[i64]) -> anyhow::Result<()> {
+        if self.readings.receiver_count() == 0 {
+            return Ok(());
+        }
+        
+        for reading in Reading::get_by_ids(reading_ids)? {
+            // Subscribers may all have gone since the check; nothing to do then
+            let _ = self.readings.send(reading);
+        }
+        
+        Ok(())
+    }
+}
+
+/// Sensor a request's scoped token is bound to, for handlers that accept scoped tokens
+#[derive(Debug, Clone, Copy)]
+pub struct SensorScope(pub i64);
+
+pub fn create_router(state: AppState) -> Router {
+    let router = Router::new()
         // Sensor routes
         .route("/api/sensors", post(sensors::create_sensor))
         .route("/api/sensors", get(sensors::get_all_sensors))
+        .route("/api/sensors/export", get(sensors::export_sensors_csv))
+        .route("/api/sensors/config", get(sensors::export_sensor_config))
         .route("/api/sensors/:id", get(sensors::get_sensor_by_id))
         .route("/api/sensors/:id", put(sensors::update_sensor))
         .route("/api/sensors/:id", delete(sensors::delete_sensor))
+        .route("/api/sensors/:id/stats", get(sensors::get_sensor_stats))
+        .route("/api/sensors/:id/history", get(sensors::get_sensor_history))
+        .route("/api/sensors/:id/readings", get(sensors::get_sensor_readings))
+        .route("/api/sensors/:id/summary", get(sensors::get_sensor_summary))
+        .route("/api/sensors/:id/archive", post(sensors::archive_sensor))
+        .route("/api/sensors/:id/tokens", post(sensors::create_sensor_token))
+        .route("/api/sensors/:id/tags/:tag", post(sensors::add_sensor_tag))
+        .route("/api/sensors/:id/tags/:tag", delete(sensors::remove_sensor_tag))
         
         // Reading routes
         .route("/api/readings", post(readings::create_reading))
-        .route("/api/readings/bulk", post(readings::bulk_import_readings))
-        .route("/api/readings", get(readings::get_readings))
+        .route("/api/readings/import/ndjson", post(readings::bulk_import_ndjson))
+        .route("/api/readings/bulk-ndjson", post(readings::bulk_import_ndjson_deprecated))
+        .route("/api/readings", get(readings::get_readings).layer(compression()))
+        .route("/api/readings/span", get(readings::get_reading_spans))
+        .route("/api/readings/stats", get(readings::get_reading_stats))
+        .route("/api/readings/histogram", get(readings::get_reading_histogram))
+        .route("/api/readings/aggregate", get(readings::get_reading_aggregate))
+        .route("/api/readings/aggregate/by-type/:sensor_type", get(readings::get_type_aggregate))
+        .route("/api/readings/aggregate/grouped", get(readings::get_grouped_aggregate))
+        .route("/api/readings/stepped/:sensor_id", get(readings::get_stepped_readings))
+        .route("/api/readings/integral/:sensor_id", get(readings::get_reading_integral))
+        .route("/api/readings/current", get(readings::get_all_current_readings))
         .route("/api/readings/current/:sensor_id", get(readings::get_current_reading))
+        .route("/api/readings/previous/:sensor_id", get(readings::get_previous_reading))
+        .route("/api/readings/annotated/:sensor_id", get(readings::get_annotated_readings))
         .route("/api/readings", delete(readings::delete_readings))
+        .route("/api/readings/:id", delete(readings::delete_reading))
         
         // Logging session routes
         .route("/api/sessions", post(sessions::start_logging))
@@ -30,9 +110,397 @@ pub fn create_router() -> Router {
         .route("/api/sessions/sensor/:sensor_id", get(sessions::get_sessions_by_sensor))
         .route("/api/sessions/active/:sensor_id", get(sessions::get_active_session))
         .route("/api/sessions/active", get(sessions::get_all_active_sessions))
+        .route("/api/sessions/:session_id/gaps", get(sessions::get_session_gaps))
+        
+        // Status routes
+        .route("/api/status/current", get(status::get_current_status))
+        
+        // Visualization routes
+        .route("/api/visualizations/time-series", get(visualizations::get_time_series))
+        
+        // Live update routes
+        .route("/api/ws/readings", get(live::readings_socket))
         
         // System management routes
         .route("/api/system/health", get(system::get_database_health))
+        .route("/api/system/schema-version", get(system::get_schema_version))
+        .route("/api/system/index-advice", get(system::get_index_advice))
+        .route("/api/system/storage-report", get(system::get_storage_report))
         .route("/api/system/maintenance", post(system::run_maintenance))
-        .route("/api/system/export", get(system::export_data))
-}
\ No newline at end of file
+        .route("/api/system/backup", post(system::create_backup))
+        .route("/api/system/export", get(system::export_data).layer(compression()))
+        .route("/api/system/dump", get(system::dump_database))
+        
+        .merge(bulk_routes())
+        
+        // Unmatched paths get the same JSON error envelope as everything else
+        .fallback(route_not_found)
+        .layer(middleware::from_fn(enforce_token_scope))
+        .with_state(state);
+    
+    let config = config::get();
+    let router = if config.read_only {
+        router.layer(middleware::from_fn(reject_writes))
+    } else {
+        router
+    };
+    
+    // Outermost, so preflight requests are answered before anything else runs
+    match &config.cors_allowed_origins {
+        Some(origins) => router.layer(cors(origins)),
+        None => router,
+    }
+}
+
+/// Routes that buffer a whole JSON body, capped at `MAX_BULK_BODY_BYTES`
+///
+/// The streaming NDJSON import isn't capped as a whole: it keeps at most a
+/// batch of parsed readings plus the line being read, and caps that line.
+fn bulk_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/sensors/config", post(sensors::import_sensor_config))
+        .route("/api/sensors/bulk", post(sensors::bulk_create_sensors))
+        .route("/api/sensors/bulk-delete", post(sensors::bulk_delete_sensors))
+        .route("/api/sensors/delete-batch", post(sensors::delete_sensor_batch))
+        .route("/api/readings/bulk", post(readings::bulk_import_readings))
+        .layer(DefaultBodyLimit::max(config::get().max_bulk_body_bytes))
+        .layer(middleware::from_fn(payload_too_large))
+}
+
+/// Give bodies rejected for their size the usual JSON error envelope
+async fn payload_too_large(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::PAYLOAD_TOO_LARGE {
+        return response;
+    }
+    
+    AppError::PayloadTooLarge(format!(
+        "Request body exceeds the {} byte limit",
+        config::get().max_bulk_body_bytes
+    ))
+    .into_response()
+}
+
+/// Cross-origin access for browser clients on the configured origins
+///
+/// `*` allows any origin. Credentials aren't allowed either way; clients
+/// authenticate with an explicit `Authorization` header.
+fn cors(origins: &[String]) -> CorsLayer {
+    let allow_origin = if origins.iter().any(|origin| origin == "*") {
+        AllowOrigin::any()
+    } else {
+        AllowOrigin::list(origins.iter().filter_map(|origin| HeaderValue::from_str(origin).ok()))
+    };
+    
+    CorsLayer::new()
+        .allow_origin(allow_origin)
+        .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
+        .allow_headers([
+            header::ACCEPT,
+            header::AUTHORIZATION,
+            header::CONTENT_TYPE,
+            header::IF_NONE_MATCH,
+            HeaderName::from_static("idempotency-key"),
+        ])
+        .expose_headers([
+            header::CONTENT_DISPOSITION,
+            header::ETAG,
+            HeaderName::from_static(readings::LIMIT_CLAMPED_HEADER),
+        ])
+}
+
+/// Gzip for routes whose bodies can run to megabytes
+///
+/// The body is compressed as it streams, and only for clients sending
+/// `Accept-Encoding: gzip`; everyone else gets it uncompressed.
+fn compression() -> CompressionLayer {
+    CompressionLayer::new()
+}
+
+/// Refuse mutating requests when the server runs in read-only mode
+async fn reject_writes(request: Request, next: Next) -> Response {
+    if matches!(*request.method(), Method::GET | Method::HEAD | Method::OPTIONS) {
+        return next.run(request).await;
+    }
+    
+    AppError::Forbidden(format!(
+        "Server is in read-only mode; {} requests are not allowed",
+        request.method()
+    ))
+    .into_response()
+}
+
+/// Confine requests carrying a sensor-scoped token to writing that sensor's readings
+///
+/// Requests without a token are let through unchanged.
+async fn enforce_token_scope(mut request: Request, next: Next) -> Response {
+    let Some(token) = bearer_token(request.headers()) else {
+        return next.run(request).await;
+    };
+    
+    let sensor_id = match ApiToken::sensor_for(&token) {
+        Ok(Some(sensor_id)) => sensor_id,
+        Ok(None) => return AppError::Unauthorized("Unknown API token".to_string()).into_response(),
+        Err(err) => return AppError::from(err).into_response(),
+    };
+    
+    if request.method() != Method::POST || request.uri().path() != "/api/readings" {
+        return AppError::Forbidden(format!(
+            "Token for sensor {} may only POST /api/readings",
+            sensor_id
+        ))
+        .into_response();
+    }
+    
+    // The handler checks the submitted sensor_id against the scope
+    request.extensions_mut().insert(SensorScope(sensor_id));
+    next.run(request).await
+}
+
+/// Token from an `Authorization: Bearer` header, if any
+fn bearer_token(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get(header::AUTHORIZATION)?
+        .to_str()
+        .ok()?
+        .strip_prefix("Bearer ")
+        .map(|token| token.trim().to_string())
+}
+
+/// Fallback for requests that match no route
+async fn route_not_found(uri: Uri) -> AppError {
+    AppError::NotFound(format!("No route for {}", uri.path()))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+    use crate::{
+        config::{self, Config},
+        models::{ApiToken, Reading},
+        utils::test_utils::{setup_test_db, create_test_sensor, send_request},
+    };
+
+    #[tokio::test]
+    async fn test_unknown_route_returns_json_error() -> Result<()> {
+        let request = Request::get("/api/no-such-thing").body(Body::empty())?;
+        let (status, headers, body) = send_request(request).await?;
+        
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        
+        let error: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(error["error"], "No route for /api/no-such-thing");
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_read_only_mode_rejects_writes() -> Result<()> {
+        let _pool = setup_test_db()?;
+        config::set_test_config(Config {
+            read_only: true,
+            ..Config::default()
+        });
+        
+        let request = Request::post("/api/sensors")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"sensor_name": "Meter", "sensor_type": "power"}"#))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        
+        let error: serde_json::Value = serde_json::from_slice(&body)?;
+        assert!(error["error"].as_str().unwrap_or_default().contains("read-only"));
+        
+        let request = Request::get("/api/sensors").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let sensors: Vec<serde_json::Value> = serde_json::from_slice(&body)?;
+        assert!(sensors.is_empty(), "the rejected POST must not have created a sensor");
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_scoped_token_only_writes_its_sensor() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        
+        let request = Request::post(format!("/api/sensors/{}/tokens", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let token: ApiToken = serde_json::from_slice(&body)?;
+        assert_eq!(token.sensor_id, sensor_id);
+        
+        let authorization = format!("Bearer {}", token.token);
+        let submit = |sensor_id: i64| {
+            Request::post("/api/readings")
+                .header(header::AUTHORIZATION, &authorization)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!(r#"{{"sensor_id": {}, "value": 21.5}}"#, sensor_id)))
+        };
+        
+        let (status, _, _) = send_request(submit(sensor_id)?).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        
+        let (status, _, _) = send_request(submit(other_sensor)?).await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        
+        let request = Request::get("/api/sensors")
+            .header(header::AUTHORIZATION, &authorization)
+            .body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        
+        let request = Request::get(format!("/api/readings/current/{}", sensor_id))
+            .header(header::AUTHORIZATION, &authorization)
+            .body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+        
+        let counts: Vec<i64> = [sensor_id, other_sensor]
+            .iter()
+            .map(|id| conn.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [id], |row| row.get(0)))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(counts, vec![1, 0]);
+        
+        // Unknown tokens are rejected outright
+        let request = Request::get("/api/sensors")
+            .header(header::AUTHORIZATION, "Bearer not-a-token")
+            .body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::UNAUTHORIZED);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_large_responses_are_gzipped_on_request() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let readings: Vec<Reading> = (0..500)
+            .map(|i| Reading {
+                timestamp: Some(10_000 + i),
+                sensor_id,
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        for uri in [
+            format!("/api/readings?sensor_id={}", sensor_id),
+            format!("/api/system/export?format=csv&sensor_ids={}", sensor_id),
+        ] {
+            let (status, headers, plain) = send_request(Request::get(&uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::OK);
+            assert!(headers.get(header::CONTENT_ENCODING).is_none(), "{}", uri);
+            
+            let request = Request::get(&uri)
+                .header(header::ACCEPT_ENCODING, "gzip")
+                .body(Body::empty())?;
+            let (status, headers, compressed) = send_request(request).await?;
+            assert_eq!(status, StatusCode::OK);
+            assert_eq!(headers[header::CONTENT_ENCODING], "gzip", "{}", uri);
+            assert!(compressed.len() < plain.len());
+            
+            let mut decompressed = Vec::new();
+            GzDecoder::new(&compressed[..]).read_to_end(&mut decompressed)?;
+            assert_eq!(decompressed, plain.to_vec(), "{}", uri);
+        }
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_cors_preflight_reflects_configured_origins() -> Result<()> {
+        let _pool = setup_test_db()?;
+        let preflight = |origin: &str| {
+            Request::options("/api/readings")
+                .header(header::ORIGIN, origin)
+                .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+                .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "content-type")
+                .body(Body::empty())
+        };
+        
+        // Without configuration browsers get no cross-origin access
+        let (_, headers, _) = send_request(preflight("https://dashboard.example.com")?).await?;
+        assert!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        
+        config::set_test_config(Config {
+            cors_allowed_origins: Some(vec!["https://dashboard.example.com".to_string()]),
+            ..Config::default()
+        });
+        let (status, headers, _) = send_request(preflight("https://dashboard.example.com")?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "https://dashboard.example.com");
+        let methods = headers[header::ACCESS_CONTROL_ALLOW_METHODS].to_str()?;
+        assert!(methods.contains("POST") && methods.contains("DELETE"));
+        assert!(headers[header::ACCESS_CONTROL_ALLOW_HEADERS].to_str()?.contains("content-type"));
+        
+        let (_, headers, _) = send_request(preflight("https://elsewhere.example.com")?).await?;
+        assert!(headers.get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+        
+        config::set_test_config(Config {
+            cors_allowed_origins: Some(vec!["*".to_string()]),
+            ..Config::default()
+        });
+        let (_, headers, _) = send_request(preflight("https://elsewhere.example.com")?).await?;
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        
+        // Simple requests carry the header too
+        let request = Request::get("/api/sensors")
+            .header(header::ORIGIN, "https://elsewhere.example.com")
+            .body(Body::empty())?;
+        let (status, headers, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_oversized_bulk_body_is_rejected() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        config::set_test_config(Config {
+            max_bulk_body_bytes: 1024,
+            ..Config::default()
+        });
+        
+        let bulk = |count: i64| {
+            let readings: Vec<String> = (0..count)
+                .map(|i| format!(r#"{{"sensor_id": {}, "timestamp": {}, "value": 1.0}}"#, sensor_id, 1000 + i))
+                .collect();
+            Request::post("/api/readings/bulk")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!(r#"{{"readings": [{}]}}"#, readings.join(","))))
+        };
+        
+        let (status, headers, body) = send_request(bulk(100)?).await?;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        let error: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(error["error"], "Request body exceeds the 1024 byte limit");
+        
+        let readings: i64 = conn.query_row("SELECT COUNT(*) FROM readings", [], |row| row.get(0))?;
+        assert_eq!(readings, 0);
+        
+        // Bodies within the limit go through
+        let (status, _, _) = send_request(bulk(5)?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        Ok(())
+    }
+}
@@ -1,69 +1,596 @@
 use axum::{
-    extract::{Path, Query},
-    http::StatusCode,
-    Json,
+    body::Body,
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Extension, Json,
 };
+use futures::StreamExt;
 use serde_json::{json, Value};
+use std::sync::Arc;
 
-use crate::models::{Reading, ReadingBulkInsert, ReadingBulkResponse, ReadingQuery, ReadingResponse};
-use crate::utils::error::AppError;
+use crate::api::{response::ResponseFormat, AppState, SensorScope};
+use crate::db::get_connection;
+use crate::models::{
+    BatchSettings, CurrentReadingQuery, IdempotentResponse, Reading, ReadingAggregateQuery, ReadingBulkInsert, ReadingBulkQuery,
+    ReadingBulkResponse, ReadingHistogramQuery, ReadingDeleteQuery, ReadingLineError, ReadingNdjsonResponse, ReadingQuery, ReadingSpanQuery,
+    ReadingGroupedAggregateQuery, ReadingIntegralQuery, ReadingSteppedQuery, ReadingSubmission,
+    ReadingTypeAggregateQuery,
+};
+use crate::utils::{cache, error::AppError, stats};
+
+/// Number of parsed NDJSON readings inserted per transaction
+const NDJSON_BATCH_SIZE: usize = 1000;
+
+/// Longest accepted NDJSON line; a reading is a few hundred bytes at most
+const NDJSON_MAX_LINE_BYTES: usize = 64 * 1024;
+
+/// Set on readings responses whose requested limit or tail was cut down to `READINGS_MAX_LIMIT`
+pub const LIMIT_CLAMPED_HEADER: &str = "x-limit-clamped";
+
+/// Header a client sets to make retries of a submission safe
+const IDEMPOTENCY_KEY_HEADER: &str = "idempotency-key";
+
+/// Longest accepted `Idempotency-Key`
+const MAX_IDEMPOTENCY_KEY_LENGTH: usize = 255;
 
 /// Log a single sensor reading
+///
+/// With an `Idempotency-Key` header, a retry of a request that already
+/// succeeded gets the original response back instead of storing the
+/// reading again. Failed requests aren't remembered, so they can be retried.
+///
+/// A sensor keeps one reading per second: a second reading in the same
+/// second, including an untimestamped one, is rejected with 409. Bulk
+/// imports choose how to resolve such clashes with `on_conflict`.
 pub async fn create_reading(
-    Json(reading): Json<Reading>,
+    State(state): State<AppState>,
+    scope: Option<Extension<SensorScope>>,
+    headers: HeaderMap,
+    Json(submission): Json<ReadingSubmission>,
 ) -> Result<(StatusCode, Json<Value>), AppError> {
-    let reading_id = reading.create()?;
+    if let Some(Extension(SensorScope(sensor_id))) = scope {
+        if submission.sensor_id != Some(sensor_id) {
+            return Err(AppError::Forbidden(format!("Token may only write readings for sensor {}", sensor_id)));
+        }
+    }
+    
+    let Some(key) = idempotency_key(&headers)? else {
+        let (status, response) = store_reading(&state, submission)?;
+        return Ok((status, Json(response)));
+    };
+    
+    if let Some(stored) = IdempotentResponse::claim(&key)? {
+        let status = StatusCode::from_u16(stored.status_code).map_err(|err| AppError::Internal(err.into()))?;
+        return Ok((status, Json(stored.body)));
+    }
+    
+    match store_reading(&state, submission) {
+        Ok((status, response)) => {
+            let stored = IdempotentResponse { status_code: status.as_u16(), body: response };
+            stored.save(&key)?;
+            Ok((status, Json(stored.body)))
+        }
+        Err(err) => {
+            IdempotentResponse::release(&key)?;
+            Err(err)
+        }
+    }
+}
+
+/// The request's `Idempotency-Key`, if it sent one
+fn idempotency_key(headers: &HeaderMap) -> Result<Option<String>, AppError> {
+    let Some(value) = headers.get(IDEMPOTENCY_KEY_HEADER) else {
+        return Ok(None);
+    };
+    
+    let key = value
+        .to_str()
+        .map(str::trim)
+        .map_err(|_| AppError::BadRequest("Idempotency-Key must be visible ASCII".to_string()))?;
+    if key.is_empty() || key.len() > MAX_IDEMPOTENCY_KEY_LENGTH {
+        return Err(AppError::BadRequest(format!(
+            "Idempotency-Key must be between 1 and {} characters",
+            MAX_IDEMPOTENCY_KEY_LENGTH
+        )));
+    }
+    
+    Ok(Some(key.to_string()))
+}
+
+/// Store a submitted reading, publish it, and build the response
+fn store_reading(state: &AppState, submission: ReadingSubmission) -> Result<(StatusCode, Value), AppError> {
+    let result = Reading::submit(submission)?;
+    if let Some(reading_id) = result.reading_id {
+        state.publish_readings(&[reading_id])?;
+    }
     
     let response = json!({
         "success": true,
-        "reading_id": reading_id
+        "stored": result.reading_id.is_some(),
+        "reading_id": result.reading_id,
+        "sensor_id": result.sensor_id,
+        "sensor_created": result.sensor_created,
+        "sensor_status": result.sensor_status
     });
     
-    Ok((StatusCode::CREATED, Json(response)))
+    // A reading suppressed by the sensor's deadband creates nothing
+    let status = if result.reading_id.is_some() { StatusCode::CREATED } else { StatusCode::OK };
+    Ok((status, response))
 }
 
 /// Bulk import readings
 pub async fn bulk_import_readings(
+    State(state): State<AppState>,
+    Query(query): Query<ReadingBulkQuery>,
     Json(payload): Json<ReadingBulkInsert>,
 ) -> Result<Json<ReadingBulkResponse>, AppError> {
-    let inserted_count = Reading::bulk_insert(&payload.readings)?;
+    let return_ids = query.return_ids.unwrap_or(false);
+    let on_conflict = query.on_conflict.unwrap_or_default();
+    
+    // Live subscribers need the ids to look the readings up
+    let live = state.readings.receiver_count() > 0;
+    let (inserted_count, ids) = Reading::bulk_upsert(&payload.readings, on_conflict, return_ids || live)?;
+    if live {
+        state.publish_readings(&ids)?;
+    }
+    let reading_ids = return_ids.then_some(ids);
     
     let response = ReadingBulkResponse {
         inserted_count,
+        reading_ids,
         success: true,
     };
     
     Ok(Json(response))
 }
 
+/// Bulk import readings from newline-delimited JSON, one reading per line
+///
+/// The body is streamed and inserted in batches of `NDJSON_BATCH_SIZE`, so
+/// memory stays flat however large the upload. Lines that are malformed or
+/// that the database rejects, e.g. for an unknown sensor, are skipped and
+/// reported by line number; every other line is still inserted.
+///
+/// A failure that isn't down to one line, such as an unreadable body, a line
+/// longer than `NDJSON_MAX_LINE_BYTES` (413) or a database error, stops the import with that error's status. The body
+/// still reports what was inserted, since batches committed before it stay
+/// committed, and the line it stopped at.
+pub async fn bulk_import_ndjson(body: Body) -> (StatusCode, Json<ReadingNdjsonResponse>) {
+    let mut importer = NdjsonImporter::default();
+    let status = match importer.import(body).await {
+        Ok(()) => StatusCode::OK,
+        Err(err) => {
+            let (status, message) = err.status_and_message();
+            importer.errors.push(ReadingLineError {
+                line: importer.failed_line.unwrap_or(importer.line_number + 1),
+                error: message,
+            });
+            status
+        }
+    };
+    
+    importer.errors.sort_by_key(|error| error.line);
+    let response = ReadingNdjsonResponse {
+        inserted_count: importer.inserted_count,
+        first_error_line: importer.errors.first().map(|error| error.line),
+        success: importer.errors.is_empty(),
+        errors: importer.errors,
+    };
+    
+    (status, Json(response))
+}
+
+/// Deprecated alias of `POST /api/readings/import/ndjson`
+///
+/// Answers exactly as the new path does, with a `Deprecation` header and a
+/// `Link` pointing clients at it.
+pub async fn bulk_import_ndjson_deprecated(body: Body) -> Response {
+    let mut response = bulk_import_ndjson(body).await.into_response();
+    let headers = response.headers_mut();
+    headers.insert("deprecation", HeaderValue::from_static("true"));
+    headers.insert(
+        header::LINK,
+        HeaderValue::from_static("</api/readings/import/ndjson>; rel=\"successor-version\""),
+    );
+    response
+}
+
+/// Parses NDJSON lines and inserts them in fixed-size batches
+#[derive(Default)]
+struct NdjsonImporter {
+    line_number: usize,
+    batch: Vec<Reading>,
+    batch_lines: Vec<usize>,  // Line number of each reading in `batch`
+    inserted_count: usize,
+    errors: Vec<ReadingLineError>,
+    failed_line: Option<usize>,  // Line whose insert stopped the import
+}
+
+impl NdjsonImporter {
+    async fn import(&mut self, body: Body) -> Result<(), AppError> {
+        let mut buffer: Vec<u8> = Vec::new();
+        let mut stream = body.into_data_stream();
+        
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk.map_err(|err| AppError::BadRequest(format!("Failed to read body: {}", err)))?;
+            // The leftover partial line has no newline, so only the new bytes need searching
+            let mut search_from = buffer.len();
+            buffer.extend_from_slice(&chunk);
+            
+            // Hand over every complete line, then drop them all at once and keep the
+            // trailing partial one for the next chunk
+            let mut start = 0;
+            while let Some(pos) = buffer[search_from..].iter().position(|&b| b == b'\n') {
+                let end = search_from + pos + 1;
+                self.push_line(&buffer[start..end]).await?;
+                start = end;
+                search_from = end;
+            }
+            buffer.drain(..start);
+            
+            // What's left is a single line still being read, so it must not grow without bound
+            if buffer.len() > NDJSON_MAX_LINE_BYTES {
+                return Err(AppError::PayloadTooLarge(format!(
+                    "NDJSON lines must not exceed {} bytes",
+                    NDJSON_MAX_LINE_BYTES
+                )));
+            }
+        }
+        
+        self.push_line(&buffer).await?;
+        self.flush().await
+    }
+    
+    async fn push_line(&mut self, line: &[u8]) -> Result<(), AppError> {
+        self.line_number += 1;
+        
+        if line.iter().all(|b| b.is_ascii_whitespace()) {
+            return Ok(());
+        }
+        
+        match serde_json::from_slice::<Reading>(line) {
+            Ok(reading) => {
+                self.batch.push(reading);
+                self.batch_lines.push(self.line_number);
+            },
+            Err(err) => self.errors.push(ReadingLineError {
+                line: self.line_number,
+                error: err.to_string(),
+            }),
+        }
+        
+        if self.batch.len() >= NDJSON_BATCH_SIZE {
+            self.flush().await?;
+        }
+        
+        Ok(())
+    }
+    
+    async fn flush(&mut self) -> Result<(), AppError> {
+        let batch: Arc<[Reading]> = std::mem::take(&mut self.batch).into();
+        let lines = std::mem::take(&mut self.batch_lines);
+        if batch.is_empty() {
+            return Ok(());
+        }
+        
+        if let Ok(count) = Self::insert(batch.clone()).await {
+            self.inserted_count += count;
+            return Ok(());
+        }
+        
+        // The batch was rolled back; insert its readings one by one to find the lines at fault
+        for (reading, line) in batch.iter().zip(lines) {
+            match Self::insert(Arc::new([reading.clone()])).await {
+                Ok(count) => self.inserted_count += count,
+                Err(
+                    err @ (AppError::NotFound(_) | AppError::BadRequest(_) | AppError::Conflict(_) | AppError::Forbidden(_)),
+                ) => {
+                    self.errors.push(ReadingLineError { line, error: err.status_and_message().1 });
+                },
+                Err(err) => {
+                    self.failed_line = Some(line);
+                    return Err(err);
+                },
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Insert readings on a blocking thread, so a large import doesn't stall the runtime
+    async fn insert(readings: Arc<[Reading]>) -> Result<usize, AppError> {
+        // Check out the connection here so a busy pool is still an error status
+        let mut conn = get_connection()?;
+        let settings = BatchSettings::current();
+        let written = tokio::task::spawn_blocking(move || Reading::bulk_insert_with(&mut conn, &readings, settings))
+            .await
+            .map_err(|err| AppError::Internal(err.into()))??;
+        
+        Ok(written.finish().0)
+    }
+}
+
 /// Get readings with filtering
 pub async fn get_readings(
+    format: ResponseFormat,
     Query(query): Query<ReadingQuery>,
-) -> Result<Json<Vec<ReadingResponse>>, AppError> {
-    let readings = Reading::get(&query)?;
-    Ok(Json(readings))
+) -> Result<Response, AppError> {
+    query.validate_time_range()?;
+    
+    let mut response = readings_response(format, &query)?;
+    if query.limit_clamped() {
+        response
+            .headers_mut()
+            .insert(LIMIT_CLAMPED_HEADER, HeaderValue::from_static("true"));
+    }
+    
+    Ok(response)
+}
+
+/// Serve the raw, downsampled, paginated or rate view the query asks for
+fn readings_response(format: ResponseFormat, query: &ReadingQuery) -> Result<Response, AppError> {
+    // Rates and bucket sums don't convert the way single values do
+    if query.convert_to.is_some() && (query.bucket.is_some() || query.rate.unwrap_or(false)) {
+        return Err(AppError::BadRequest("convert_to can't be combined with bucket or rate".to_string()));
+    }
+    
+    if query.rate.unwrap_or(false) {
+        if query.bucket.is_some()
+            || query.agg.is_some()
+            || query.tail.is_some()
+            || query.paginated.unwrap_or(false)
+            || query.after.is_some()
+        {
+            return Err(AppError::BadRequest(
+                "rate can't be combined with bucket, agg, tail, paginated or after".to_string(),
+            ));
+        }
+        let rates = Reading::get_rate_of_change(query)?;
+        return format.render(&rates);
+    }
+    
+    if query.bucket.is_some() {
+        if query.tail.is_some() || query.after.is_some() {
+            return Err(AppError::BadRequest("tail and after can't be combined with bucket".to_string()));
+        }
+        let readings = Reading::get_downsampled(query)?;
+        return format.render(&readings);
+    }
+    
+    if query.agg.is_some() {
+        return Err(AppError::BadRequest("agg requires bucket".to_string()));
+    }
+    
+    // Cursors only come from paginated responses, so following one stays paginated
+    let paginated = query.paginated.unwrap_or(false) || query.after.is_some();
+    
+    if query.tail.is_some() && paginated {
+        return Err(AppError::BadRequest("tail can't be combined with paginated or after".to_string()));
+    }
+    
+    // The bare list stays the default so existing clients keep working
+    if paginated {
+        let mut page = Reading::get_page(query)?;
+        if let Some(ref unit) = query.convert_to {
+            Reading::convert_units(&mut page.readings, unit)?;
+        }
+        return format.render(&page);
+    }
+    
+    let mut readings = Reading::get(query)?;
+    if let Some(ref unit) = query.convert_to {
+        Reading::convert_units(&mut readings, unit)?;
+    }
+    format.render(&readings)
+}
+
+/// Get the latest reading of every sensor; sensors without readings are left out
+pub async fn get_all_current_readings(format: ResponseFormat) -> Result<Response, AppError> {
+    let readings = Reading::get_current_for_all()?;
+    format.render(&readings)
 }
 
 /// Get current reading for a sensor
 pub async fn get_current_reading(
+    format: ResponseFormat,
     Path(sensor_id): Path<i64>,
-) -> Result<Json<ReadingResponse>, AppError> {
-    let reading = Reading::get_current(sensor_id)?;
-    Ok(Json(reading))
+    Query(query): Query<CurrentReadingQuery>,
+) -> Result<Response, AppError> {
+    let reading = Reading::get_current_or_default(sensor_id, query.default)?;
+    format.render(&reading)
 }
 
-/// Delete readings in a time range
-pub async fn delete_readings(
+/// Get the first and last reading timestamps for each sensor
+pub async fn get_reading_spans(
+    format: ResponseFormat,
+    Query(query): Query<ReadingSpanQuery>,
+) -> Result<Response, AppError> {
+    let include_empty = query.include_empty.unwrap_or(false);
+    let spans = cache::cached(&format!("spans:{}", include_empty), None, || {
+        Reading::spans(include_empty)
+    })?;
+    format.render(&*spans)
+}
+
+/// Get min, max and average of a sensor's values, optionally clipping outliers
+pub async fn get_reading_aggregate(
+    format: ResponseFormat,
+    Query(query): Query<ReadingAggregateQuery>,
+) -> Result<Response, AppError> {
+    let sensor_id = query.sensor_id
+        .ok_or_else(|| AppError::BadRequest("sensor_id is required".to_string()))?;
+    
+    let clip = match query.clip {
+        Some(ref raw) => Some(stats::parse_clip(raw).ok_or_else(|| {
+            AppError::BadRequest(format!("Invalid clip '{}'; expected a range like p1,p99", raw))
+        })?),
+        None => None,
+    };
+    
+    let key = format!("aggregate:{}:{:?}:{:?}:{:?}", sensor_id, query.start_time, query.end_time, clip);
+    let aggregate = cache::cached(&key, Some(sensor_id), || {
+        Reading::aggregate(sensor_id, query.start_time, query.end_time, clip)
+    })?;
+    format.render(&*aggregate)
+}
+
+/// Get the distribution of a sensor's values as equal-width bins
+pub async fn get_reading_histogram(
+    format: ResponseFormat,
+    Query(query): Query<ReadingHistogramQuery>,
+) -> Result<Response, AppError> {
+    let sensor_id = query.sensor_id
+        .ok_or_else(|| AppError::BadRequest("sensor_id is required".to_string()))?;
+    
+    let key = format!("histogram:{}:{:?}:{:?}:{:?}", sensor_id, query.start_time, query.end_time, query.bins);
+    let histogram = cache::cached(&key, Some(sensor_id), || {
+        Reading::histogram(sensor_id, query.start_time, query.end_time, query.bins)
+    })?;
+    format.render(&*histogram)
+}
+
+/// Get count, mean, spread and percentiles of the filtered readings' values
+pub async fn get_reading_stats(
+    format: ResponseFormat,
     Query(query): Query<ReadingQuery>,
-) -> Result<(StatusCode, Json<Value>), AppError> {
-    // Require start_time and end_time for deletion
+) -> Result<Response, AppError> {
+    query.validate_time_range()?;
+    
+    let key = format!(
+        "reading-stats:{:?}:{:?}:{:?}:{:?}:{:?}",
+        query.sensor_id, query.start_time, query.end_time, query.exclude_change_type, query.has_annotation
+    );
+    let stats = cache::cached(&key, query.sensor_id, || Reading::stats(&query))?;
+    format.render(&*stats)
+}
+
+/// Get the total delivered quantity for a sensor, integrating its rate over time
+pub async fn get_reading_integral(
+    format: ResponseFormat,
+    Path(sensor_id): Path<i64>,
+    Query(query): Query<ReadingIntegralQuery>,
+) -> Result<Response, AppError> {
+    let key = format!("integral:{}:{:?}:{:?}", sensor_id, query.start, query.end);
+    let integral = cache::cached(&key, Some(sensor_id), || {
+        Reading::integral(sensor_id, query.start, query.end)
+    })?;
+    format.render(&*integral)
+}
+
+/// Get one bucketed series combining every sensor of a type
+pub async fn get_type_aggregate(
+    format: ResponseFormat,
+    Path(sensor_type): Path<String>,
+    Query(query): Query<ReadingTypeAggregateQuery>,
+) -> Result<Response, AppError> {
+    let interval = query.interval.as_deref().unwrap_or("hour");
+    let bucket_seconds = stats::parse_interval(interval).ok_or_else(|| {
+        AppError::BadRequest(format!("Unknown interval '{}'; expected minute, hour or day", interval))
+    })?;
+    let agg = query.agg.as_deref().unwrap_or("sum");
+    
+    let key = format!(
+        "type-aggregate:{}:{}:{}:{:?}:{:?}",
+        sensor_type, bucket_seconds, agg, query.start_time, query.end_time
+    );
+    let buckets = cache::cached(&key, None, || {
+        Reading::aggregate_by_type(&sensor_type, bucket_seconds, agg, query.start_time, query.end_time)
+    })?;
+    format.render(&*buckets)
+}
+
+/// Get per-sensor aggregates for every sensor of a type, bucketed by interval
+pub async fn get_grouped_aggregate(
+    format: ResponseFormat,
+    Query(query): Query<ReadingGroupedAggregateQuery>,
+) -> Result<Response, AppError> {
+    let sensor_type = query.sensor_type
+        .ok_or_else(|| AppError::BadRequest("sensor_type is required".to_string()))?;
+    
+    let interval = query.interval.as_deref().unwrap_or("hour");
+    let bucket_seconds = stats::parse_interval(interval).ok_or_else(|| {
+        AppError::BadRequest(format!("Unknown interval '{}'; expected minute, hour or day", interval))
+    })?;
+    let agg = query.agg.as_deref().unwrap_or("avg");
+    
+    let key = format!(
+        "grouped-aggregate:{}:{}:{}:{:?}:{:?}",
+        sensor_type, bucket_seconds, agg, query.start_time, query.end_time
+    );
+    let buckets = cache::cached(&key, None, || {
+        Reading::aggregate_grouped(&sensor_type, bucket_seconds, agg, query.start_time, query.end_time)
+    })?;
+    format.render(&*buckets)
+}
+
+/// Get the last known value at each interval boundary for a sensor
+pub async fn get_stepped_readings(
+    format: ResponseFormat,
+    Path(sensor_id): Path<i64>,
+    Query(query): Query<ReadingSteppedQuery>,
+) -> Result<Response, AppError> {
+    let interval = query.interval
+        .ok_or_else(|| AppError::BadRequest("interval is required".to_string()))?;
+    
     let start_time = query.start_time
         .ok_or_else(|| AppError::BadRequest("start_time is required".to_string()))?;
     
     let end_time = query.end_time
         .ok_or_else(|| AppError::BadRequest("end_time is required".to_string()))?;
     
-    let deleted_count = Reading::delete_range(query.sensor_id, start_time, end_time)?;
+    let key = format!("stepped:{}:{}:{}:{}", sensor_id, interval, start_time, end_time);
+    let points = cache::cached(&key, Some(sensor_id), || {
+        Reading::stepped(sensor_id, interval, start_time, end_time)
+    })?;
+    format.render(&*points)
+}
+
+/// Get the reading before the current one for a sensor
+pub async fn get_previous_reading(
+    format: ResponseFormat,
+    Path(sensor_id): Path<i64>,
+) -> Result<Response, AppError> {
+    let reading = Reading::get_previous(sensor_id)?;
+    format.render(&reading)
+}
+
+/// Get the readings of a sensor that carry an annotation, with its text
+pub async fn get_annotated_readings(
+    format: ResponseFormat,
+    Path(sensor_id): Path<i64>,
+) -> Result<Response, AppError> {
+    let readings = Reading::get_annotated(sensor_id)?;
+    format.render(&readings)
+}
+
+/// Delete readings in a time range, or every reading for a sensor
+///
+/// Leaving out the time range wipes the sensor's whole history, so it
+/// only happens with an explicit `confirm=true`.
+pub async fn delete_readings(
+    Query(query): Query<ReadingDeleteQuery>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    let deleted_count = match (query.sensor_id, query.start_time, query.end_time) {
+        (Some(sensor_id), None, None) => {
+            if !query.confirm.unwrap_or(false) {
+                return Err(AppError::BadRequest(
+                    "Deleting every reading for a sensor requires confirm=true".to_string(),
+                ));
+            }
+            Reading::delete_all_for_sensor(sensor_id)?
+        }
+        (sensor_id, start_time, end_time) => {
+            // Otherwise require start_time and end_time for deletion
+            let start_time = start_time
+                .ok_or_else(|| AppError::BadRequest("start_time is required".to_string()))?;
+            
+            let end_time = end_time
+                .ok_or_else(|| AppError::BadRequest("end_time is required".to_string()))?;
+            
+            Reading::delete_range(sensor_id, start_time, end_time)?
+        }
+    };
     
     let response = json!({
         "success": true,
@@ -71,4 +598,1627 @@ pub async fn delete_readings(
     });
     
     Ok((StatusCode::OK, Json(response)))
-}
\ No newline at end of file
+}
+
+/// Delete a single reading by ID
+pub async fn delete_reading(
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    if !Reading::delete_by_id(id)? {
+        return Err(AppError::NotFound(format!("Reading {} not found", id)));
+    }
+    
+    let response = json!({
+        "success": true,
+        "reading_id": id
+    });
+    
+    Ok((StatusCode::OK, Json(response)))
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use super::LIMIT_CLAMPED_HEADER;
+    use crate::{
+        api::response::MSGPACK_CONTENT_TYPE,
+        config,
+        models::{reading::{CurrentReadingResponse, GroupedBucket, OnConflict, PaginatedReadings, ReadingAggregate, ReadingHistogram, ReadingIntegral, ReadingSpan, ReadingStats, SteppedPoint, TypeBucket}, Reading, ReadingBulkResponse, ReadingNdjsonResponse, ReadingQuery, ReadingResponse},
+        utils::{
+            cache,
+            test_utils::{setup_test_db, create_test_sensor, create_test_reading, send_request},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_get_readings_as_msgpack() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let sensor_id = create_test_sensor(&conn)?;
+        create_test_reading(&conn, sensor_id)?;
+        create_test_reading(&conn, sensor_id)?;
+        
+        let request = Request::get(format!("/api/readings?sensor_id={}", sensor_id))
+            .header(header::ACCEPT, MSGPACK_CONTENT_TYPE)
+            .body(Body::empty())?;
+        let (status, headers, body) = send_request(request).await?;
+        
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], MSGPACK_CONTENT_TYPE);
+        
+        let readings: Vec<ReadingResponse> = rmp_serde::from_slice(&body)?;
+        assert_eq!(readings.len(), 2);
+        assert!(readings.iter().all(|r| r.sensor_id == sensor_id && r.value == Some(21.5)));
+        
+        // Without an Accept header the same endpoint still answers with JSON
+        let request = Request::get(format!("/api/readings?sensor_id={}", sensor_id))
+            .body(Body::empty())?;
+        let (_, headers, body) = send_request(request).await?;
+        
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(readings.len(), 2);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_delete_reading_by_id() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let sensor_id = create_test_sensor(&conn)?;
+        let first = create_test_reading(&conn, sensor_id)?;
+        let second = create_test_reading(&conn, sensor_id)?;
+        let third = create_test_reading(&conn, sensor_id)?;
+        
+        let request = Request::delete(format!("/api/readings/{}", second)).body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            ..Default::default()
+        };
+        let mut remaining: Vec<i64> = Reading::get(&query)?.iter().map(|r| r.reading_id).collect();
+        remaining.sort();
+        assert_eq!(remaining, vec![first, third]);
+        
+        // Deleting it again reports that it no longer exists
+        let request = Request::delete(format!("/api/readings/{}", second)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        let error: serde_json::Value = serde_json::from_slice(&body)?;
+        assert!(error["error"].as_str().unwrap_or_default().contains("not found"));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_previous_reading() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(100, 1.0), reading(300, 3.0), reading(200, 2.0)])?;
+        
+        let request = Request::get(format!("/api/readings/previous/{}", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let previous: ReadingResponse = serde_json::from_slice(&body)?;
+        assert_eq!(previous.timestamp.timestamp(), 200);
+        assert_eq!(previous.value, Some(2.0));
+        
+        // A sensor with a single reading has no previous one
+        let other_sensor = create_test_sensor(&conn)?;
+        create_test_reading(&conn, other_sensor)?;
+        
+        let request = Request::get(format!("/api/readings/previous/{}", other_sensor)).body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_import_ndjson() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let body = format!(
+            "{{\"sensor_id\": {id}, \"timestamp\": 100, \"value\": 1.5}}\n\
+             {{\"sensor_id\": {id}, \"timestamp\": 200, \"value\": 2.5}}\n\
+             \n\
+             {{\"sensor_id\": {id}, \"timestamp\": \"oops\"}}\n\
+             {{\"sensor_id\": {id}, \"timestamp\": 300, \"state\": 1}}",
+            id = sensor_id
+        );
+        let request = Request::post("/api/readings/bulk-ndjson")
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(Body::from(body))?;
+        let (status, headers, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        // The old path still works, but points at the new one
+        assert_eq!(headers["deprecation"], "true");
+        assert!(headers[header::LINK].to_str()?.contains("/api/readings/import/ndjson"));
+        
+        let response: ReadingNdjsonResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.inserted_count, 3);
+        assert!(!response.success);
+        assert_eq!(response.errors.len(), 1);
+        assert_eq!(response.errors[0].line, 4);
+        assert_eq!(response.first_error_line, Some(4));
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            ..Default::default()
+        };
+        let mut timestamps: Vec<i64> = Reading::get(&query)?
+            .iter()
+            .map(|r| r.timestamp.timestamp())
+            .collect();
+        timestamps.sort();
+        assert_eq!(timestamps, vec![100, 200, 300]);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_reading_spans() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first_sensor = create_test_sensor(&conn)?;
+        let second_sensor = create_test_sensor(&conn)?;
+        let empty_sensor = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(1.0),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(first_sensor, 500),
+            reading(first_sensor, 100),
+            reading(first_sensor, 300),
+            reading(second_sensor, 2000),
+        ])?;
+        
+        let request = Request::get("/api/readings/span").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let spans: Vec<ReadingSpan> = serde_json::from_slice(&body)?;
+        let spans: Vec<_> = spans.iter().map(|s| (s.sensor_id, s.first_ts, s.last_ts, s.count)).collect();
+        assert_eq!(spans, vec![
+            (first_sensor, Some(100), Some(500), 3),
+            (second_sensor, Some(2000), Some(2000), 1),
+        ]);
+        
+        // Sensors without readings are listed with null timestamps on request
+        let request = Request::get("/api/readings/span?include_empty=true").body(Body::empty())?;
+        let (_, _, body) = send_request(request).await?;
+        
+        let spans: Vec<ReadingSpan> = serde_json::from_slice(&body)?;
+        assert_eq!(spans.len(), 3);
+        let empty = spans.iter().find(|s| s.sensor_id == empty_sensor).expect("empty sensor listed");
+        assert_eq!((empty.first_ts, empty.last_ts, empty.count), (None, None, 0));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_stepped_readings_carry_values_forward() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(150, 1.0),
+            reading(180, 2.0),
+            // Nothing between 200 and 400, then a reading exactly on a boundary
+            reading(400, 3.0),
+            reading(450, 4.0),
+        ])?;
+        
+        let request = Request::get(format!(
+            "/api/readings/stepped/{}?interval=100&start_time=0&end_time=500",
+            sensor_id
+        ))
+        .body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let points: Vec<SteppedPoint> = serde_json::from_slice(&body)?;
+        let series: Vec<(i64, Option<f64>)> = points.iter().map(|p| (p.timestamp, p.value)).collect();
+        assert_eq!(series, vec![
+            (0, None),
+            (100, None),
+            (200, Some(2.0)),
+            (300, Some(2.0)),
+            (400, Some(3.0)),
+            (500, Some(4.0)),
+        ]);
+        
+        // The interval is required and must be positive
+        let request = Request::get(format!(
+            "/api/readings/stepped/{}?interval=0&start_time=0&end_time=500",
+            sensor_id
+        ))
+        .body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_reading_auto_provisions_sensor() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let submit = |body: &'static str| {
+            Request::post("/api/readings")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        let body = r#"{"sensor_name": "Boiler Outlet", "sensor_type": "temperature", "value": 64.5}"#;
+        
+        // Off by default, so a missing sensor_id is a bad request
+        let (status, _, _) = send_request(submit(body)?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let sensors: i64 = conn.query_row("SELECT COUNT(*) FROM sensors", [], |row| row.get(0))?;
+        assert_eq!(sensors, 0);
+        
+        config::set_test_config(config::Config {
+            auto_provision_sensors: true,
+            ..config::Config::default()
+        });
+        
+        let (status, _, response) = send_request(submit(body)?).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["sensor_created"], true);
+        let sensor_id = response["sensor_id"].as_i64().expect("sensor_id returned");
+        
+        let sensor = crate::models::Sensor::get_by_id(sensor_id)?;
+        assert_eq!((sensor.sensor_name.as_str(), sensor.sensor_type.as_str()), ("Boiler Outlet", "temperature"));
+        let current = Reading::get_current(sensor_id)?;
+        assert_eq!(current.value, Some(64.5));
+        
+        // A second reading for the same name reuses the sensor
+        let body = r#"{"sensor_name": "Boiler Outlet", "sensor_type": "temperature", "value": 65.0, "timestamp": 100}"#;
+        let (_, _, response) = send_request(submit(body)?).await?;
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["sensor_created"], false);
+        assert_eq!(response["sensor_id"], sensor_id);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_stepped_readings_are_cached_until_insert() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(sensor_id, 100, 1.0)])?;
+        
+        let uri = format!("/api/readings/stepped/{}?interval=100&start_time=100&end_time=300", sensor_id);
+        let stepped = |uri: String| async move {
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::OK);
+            let points: Vec<SteppedPoint> = serde_json::from_slice(&body)?;
+            anyhow::Ok(points.iter().map(|p| p.value).collect::<Vec<_>>())
+        };
+        
+        let (_, misses_before) = cache::stats();
+        assert_eq!(stepped(uri.clone()).await?, vec![Some(1.0); 3]);
+        assert_eq!(stepped(uri.clone()).await?, vec![Some(1.0); 3]);
+        let (_, misses) = cache::stats();
+        assert_eq!(misses - misses_before, 1, "second request should be served from the cache");
+        
+        // Readings for another sensor leave this sensor's result cached
+        Reading::bulk_insert(&[reading(other_sensor, 250, 9.0)])?;
+        stepped(uri.clone()).await?;
+        assert_eq!(cache::stats().1 - misses_before, 1);
+        
+        // A new reading for the sensor invalidates it
+        Reading::bulk_insert(&[reading(sensor_id, 250, 2.0)])?;
+        assert_eq!(stepped(uri.clone()).await?, vec![Some(1.0), Some(1.0), Some(2.0)]);
+        assert_eq!(cache::stats().1 - misses_before, 2);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_aggregate_clips_outliers() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // A hundred steady readings and one garbage spike
+        let mut readings: Vec<Reading> = (0..100)
+            .map(|i| Reading {
+                timestamp: Some(1000 + i),
+                sensor_id,
+                value: Some(20.0),
+                ..Default::default()
+            })
+            .collect();
+        readings.push(Reading {
+            timestamp: Some(2000),
+            sensor_id,
+            value: Some(10_000.0),
+            ..Default::default()
+        });
+        Reading::bulk_insert(&readings)?;
+        
+        let aggregate = |query: String| async move {
+            let request = Request::get(format!("/api/readings/aggregate?sensor_id={}{}", sensor_id, query))
+                .body(Body::empty())?;
+            let (status, _, body) = send_request(request).await?;
+            assert_eq!(status, StatusCode::OK);
+            anyhow::Ok(serde_json::from_slice::<ReadingAggregate>(&body)?)
+        };
+        
+        let raw = aggregate(String::new()).await?;
+        assert_eq!((raw.count, raw.clipped_count, raw.max), (101, 0, Some(10_000.0)));
+        assert!(raw.avg.unwrap_or_default() > 100.0);
+        
+        let clipped = aggregate("&clip=p1,p99".to_string()).await?;
+        assert_eq!((clipped.count, clipped.clipped_count), (100, 1));
+        assert_eq!(clipped.avg, Some(20.0));
+        assert_eq!(clipped.max, Some(20.0));
+        
+        let request = Request::get(format!("/api/readings/aggregate?sensor_id={}&clip=p99,p1", sensor_id))
+            .body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_current_reading_with_default() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let empty_sensor = create_test_sensor(&conn)?;
+        let reading_id = create_test_reading(&conn, sensor_id)?;
+        
+        let current = |uri: String| async move {
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            anyhow::Ok((status, body))
+        };
+        
+        // A real reading wins over the default
+        let (status, body) = current(format!("/api/readings/current/{}?default=0", sensor_id)).await?;
+        assert_eq!(status, StatusCode::OK);
+        let reading: CurrentReadingResponse = serde_json::from_slice(&body)?;
+        assert_eq!((reading.reading_id, reading.value, reading.synthesized), (Some(reading_id), Some(21.5), false));
+        
+        // Without readings the default is returned and flagged
+        let (status, body) = current(format!("/api/readings/current/{}?default=0", empty_sensor)).await?;
+        assert_eq!(status, StatusCode::OK);
+        let reading: CurrentReadingResponse = serde_json::from_slice(&body)?;
+        assert_eq!((reading.reading_id, reading.value, reading.synthesized), (None, Some(0.0), true));
+        assert_eq!(reading.sensor_id, empty_sensor);
+        
+        // No default keeps the 404, and unknown sensors are never synthesized
+        let (status, _) = current(format!("/api/readings/current/{}", empty_sensor)).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = current("/api/readings/current/999999?default=0".to_string()).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_import_returns_ids() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let body = format!(
+            r#"{{"readings": [
+                {{"sensor_id": {id}, "timestamp": 300, "value": 3.0}},
+                {{"sensor_id": {id}, "timestamp": 100, "value": 1.0}},
+                {{"sensor_id": {id}, "timestamp": 200, "state": 1}}
+            ]}}"#,
+            id = sensor_id
+        );
+        let bulk = |uri: &str, body: String| {
+            Request::post(uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        
+        let (status, _, response) = send_request(bulk("/api/readings/bulk?return_ids=true", body.clone())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let response: ReadingBulkResponse = serde_json::from_slice(&response)?;
+        assert_eq!(response.inserted_count, 3);
+        let ids = response.reading_ids.expect("ids requested");
+        
+        // Ids come back in input order and point at the rows that were sent
+        let timestamps: Vec<i64> = ids
+            .iter()
+            .map(|id| conn.query_row("SELECT timestamp FROM readings WHERE reading_id = ?", [id], |row| row.get(0)))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(timestamps, vec![300, 100, 200]);
+        
+        // The default response carries only the count; sending the batch again replaces it
+        let (_, _, response) = send_request(bulk("/api/readings/bulk?on_conflict=replace", body)?).await?;
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["inserted_count"], 3);
+        assert!(response.get("reading_ids").is_none());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_aggregate_by_type_sums_across_sensors() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let power_sensor = |name: &str| -> Result<i64> {
+            conn.execute(
+                "INSERT INTO sensors (sensor_name, sensor_type, created_at, updated_at)
+                 VALUES (?, 'power', 0, 0)",
+                [name],
+            )?;
+            Ok(conn.last_insert_rowid())
+        };
+        let first = power_sensor("Feeder A")?;
+        let second = power_sensor("Feeder B")?;
+        let temperature = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            // Hour 0: the first feeder reports twice and is averaged before summing
+            reading(first, 100, 10.0),
+            reading(first, 200, 20.0),
+            reading(second, 300, 5.0),
+            // Hour 1: only the second feeder reports
+            reading(second, 3700, 7.0),
+            // Other sensor types are left out
+            reading(temperature, 150, 1000.0),
+        ])?;
+        
+        let request = Request::get("/api/readings/aggregate/by-type/power?interval=hour&agg=sum")
+            .body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let buckets: Vec<TypeBucket> = serde_json::from_slice(&body)?;
+        let summed: Vec<(i64, f64, i64)> = buckets
+            .iter()
+            .map(|b| (b.bucket_start, b.value, b.sensor_count))
+            .collect();
+        assert_eq!(summed, vec![(0, 20.0, 2), (3600, 7.0, 1)]);
+        
+        let request = Request::get("/api/readings/aggregate/by-type/power?agg=median")
+            .body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_reading_stats_on_known_dataset() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Inserted out of order, with a state-only row that has no value
+        let values = [16.0, 3.0, 8.0, 20.0, 6.0, 13.0, 7.0, 15.0, 8.0, 10.0];
+        let mut readings: Vec<Reading> = values
+            .iter()
+            .enumerate()
+            .map(|(i, &value)| Reading {
+                timestamp: Some(1000 + i as i64),
+                sensor_id,
+                value: Some(value),
+                ..Default::default()
+            })
+            .collect();
+        readings.push(Reading {
+            timestamp: Some(2000),
+            sensor_id,
+            state: Some(1),
+            ..Default::default()
+        });
+        Reading::bulk_insert(&readings)?;
+        
+        let uri = format!("/api/readings/stats?sensor_id={}&start_time=1000&end_time=2000", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // Sorted: 3 6 7 8 8 10 13 15 16 20
+        let stats: ReadingStats = serde_json::from_slice(&body)?;
+        assert_eq!(stats.count, 10);
+        assert_eq!(stats.min, Some(3.0));
+        assert_eq!(stats.max, Some(20.0));
+        assert_eq!(stats.mean, Some(10.6));
+        assert!((stats.std_dev.unwrap() - 24.84_f64.sqrt()).abs() < 1e-9);
+        assert_eq!(stats.p50, Some(8.0));
+        assert_eq!(stats.p90, Some(16.0));
+        assert_eq!(stats.p95, Some(20.0));
+        assert_eq!(stats.p99, Some(20.0));
+        
+        // A range holding only the state reading yields empty stats
+        let uri = format!("/api/readings/stats?sensor_id={}&start_time=1500", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let stats: ReadingStats = serde_json::from_slice(&body)?;
+        assert_eq!(stats.count, 0);
+        assert!(stats.mean.is_none() && stats.std_dev.is_none() && stats.p50.is_none());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_annotated_readings() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp, annotation: Option<&str>| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(timestamp as f64),
+            annotation: annotation.map(str::to_string),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(sensor_id, 100, None),
+            reading(sensor_id, 200, Some("valve stuck")),
+            reading(sensor_id, 300, None),
+            reading(sensor_id, 400, Some("")),
+            reading(sensor_id, 500, Some("recalibrated")),
+            reading(other_sensor, 200, Some("other sensor")),
+        ])?;
+        
+        let uri = format!("/api/readings/annotated/{}", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        let annotated: Vec<(i64, Option<&str>)> = readings
+            .iter()
+            .map(|r| (r.timestamp.timestamp(), r.annotation.as_deref()))
+            .collect();
+        assert_eq!(annotated, vec![(200, Some("valve stuck")), (500, Some("recalibrated"))]);
+        
+        let (status, _, _) = send_request(Request::get("/api/readings/annotated/999999").body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_reading_stats_cached_per_annotation_filter() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, value, annotation: Option<&str>| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            annotation: annotation.map(str::to_string),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(1000, 10.0, Some("door opened")),
+            reading(1001, 20.0, None),
+            reading(1002, 30.0, None),
+        ])?;
+        
+        // Both requests fall within the cache TTL, so they must not share an entry
+        let count = |filter: &'static str| async move {
+            let uri = format!("/api/readings/stats?sensor_id={}{}", sensor_id, filter);
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::OK);
+            let stats: ReadingStats = serde_json::from_slice(&body)?;
+            Ok::<_, anyhow::Error>(stats.count)
+        };
+        assert_eq!(count("").await?, 3);
+        assert_eq!(count("&has_annotation=true").await?, 1);
+        assert_eq!(count("&has_annotation=false").await?, 2);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_integral_of_constant_rate() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // 12 kW every minute for ten minutes, then a gap, then another five minutes
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value,
+            ..Default::default()
+        };
+        let mut readings: Vec<Reading> = (0..=10).map(|i| reading(i * 60, Some(12.0))).collect();
+        readings.push(reading(900, None));
+        readings.extend((0..=5).map(|i| reading(1200 + i * 60, Some(12.0))));
+        Reading::bulk_insert(&readings)?;
+        
+        let uri = format!("/api/readings/integral/{}?start=0&end=600", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let integral: ReadingIntegral = serde_json::from_slice(&body)?;
+        assert_eq!(integral.unit_seconds, 12.0 * 600.0);
+        assert_eq!(integral.unit_hours, 12.0 * 600.0 / 3600.0);
+        assert_eq!(integral.segments, 1);
+        
+        // The null reading splits the series; nothing is counted across the gap
+        let uri = format!("/api/readings/integral/{}", sensor_id);
+        let (_, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        let integral: ReadingIntegral = serde_json::from_slice(&body)?;
+        assert_eq!(integral.unit_seconds, 12.0 * (600.0 + 300.0));
+        assert_eq!(integral.segments, 2);
+        
+        let (status, _, _) = send_request(Request::get("/api/readings/integral/9999").body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_create_reports_deadband_suppression() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        conn.execute("UPDATE sensors SET deadband = 1.0 WHERE sensor_id = ?", [sensor_id])?;
+        
+        let submit = |value: f64| {
+            Request::post("/api/readings")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!(r#"{{"sensor_id": {}, "value": {}}}"#, sensor_id, value)))
+        };
+        
+        let (status, _, response) = send_request(submit(20.0)?).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["stored"], true);
+        
+        let (status, _, response) = send_request(submit(20.5)?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["stored"], false);
+        assert!(response["reading_id"].is_null());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_out_of_range_timestamp_is_a_server_error() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        conn.execute(
+            "INSERT INTO readings (timestamp, sensor_id, value) VALUES (?, ?, 1.0)",
+            rusqlite::params![i64::MAX, sensor_id],
+        )?;
+        
+        // A corrupt row fails the request cleanly instead of panicking the handler
+        let request = Request::get(format!("/api/readings?sensor_id={}", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert!(body["error"].is_string());
+        
+        conn.execute("UPDATE sensors SET calibration_date = ? WHERE sensor_id = ?", rusqlite::params![i64::MIN, sensor_id])?;
+        let request = Request::get(format!("/api/sensors/{}", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+        let body: serde_json::Value = serde_json::from_slice(&body)?;
+        assert!(body["error"].is_string());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_readings_downsamples_with_bucket() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(3600, 1.0), reading(3700, 3.0), reading(7300, 8.0)])?;
+        
+        let uri = format!("/api/readings?sensor_id={}&bucket=3600&agg=max", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let buckets: Vec<serde_json::Value> = serde_json::from_slice(&body)?;
+        let values: Vec<f64> = buckets.iter().filter_map(|b| b["value"].as_f64()).collect();
+        // Newest bucket first, as with raw readings
+        assert_eq!(values, vec![8.0, 3.0]);
+        
+        // Without a bucket the raw readings come back as before
+        let uri = format!("/api/readings?sensor_id={}", sensor_id);
+        let (_, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(readings.len(), 3);
+        
+        let uri = format!("/api/readings?sensor_id={}&agg=max", sensor_id);
+        let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_paginated_readings_report_total() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        
+        let mut readings: Vec<Reading> = (1..=5)
+            .map(|i| Reading { timestamp: Some(i * 60), sensor_id, value: Some(i as f64), ..Default::default() })
+            .collect();
+        readings.push(Reading { timestamp: Some(60), sensor_id: other_sensor, value: Some(1.0), ..Default::default() });
+        Reading::bulk_insert(&readings)?;
+        
+        let page = |offset: usize| async move {
+            let uri = format!("/api/readings?sensor_id={}&paginated=true&limit=2&offset={}", sensor_id, offset);
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::OK);
+            Ok::<_, anyhow::Error>(serde_json::from_slice::<PaginatedReadings>(&body)?)
+        };
+        
+        let first = page(0).await?;
+        assert_eq!((first.total, first.limit, first.offset), (5, 2, 0));
+        assert_eq!(first.readings.len(), 2);
+        assert!(first.has_more);
+        
+        let second = page(2).await?;
+        assert_eq!(second.readings.len(), 2);
+        assert!(second.has_more);
+        
+        let last = page(4).await?;
+        assert_eq!(last.total, 5);
+        assert_eq!(last.readings.len(), 1);
+        assert!(!last.has_more);
+        
+        // Without the flag the response is still a bare list
+        let uri = format!("/api/readings?sensor_id={}&limit=2", sensor_id);
+        let (_, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        let plain: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(plain.len(), 2);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_cursor_pages_cover_every_reading_once() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensors = [create_test_sensor(&conn)?, create_test_sensor(&conn)?];
+        
+        // The two sensors read at the same instants, so pages split ties
+        let readings: Vec<Reading> = (0..25)
+            .map(|i| Reading {
+                timestamp: Some(1_000 + i / 2 * 60),
+                sensor_id: sensors[i as usize % 2],
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        let (_, ids) = Reading::bulk_upsert(&readings, OnConflict::Error, true)?;
+        
+        let pages = |filters: String| async move {
+            let mut seen = Vec::new();
+            let mut cursor: Option<String> = None;
+            loop {
+                let mut uri = format!("/api/readings?limit=3&paginated=true{}", filters);
+                if let Some(ref cursor) = cursor {
+                    uri.push_str(&format!("&after={}", cursor));
+                }
+                let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+                assert_eq!(status, StatusCode::OK);
+                let page: PaginatedReadings = serde_json::from_slice(&body)?;
+                
+                seen.extend(page.readings.iter().map(|r| r.reading_id));
+                assert_eq!(page.has_more, page.next_cursor.is_some());
+                match page.next_cursor {
+                    Some(next) => cursor = Some(next),
+                    None => break,
+                }
+            }
+            Ok::<_, anyhow::Error>(seen)
+        };
+        
+        let newest_first: Vec<i64> = ids.iter().rev().copied().collect();
+        assert_eq!(pages(String::new()).await?, newest_first);
+        assert_eq!(pages("&order=asc".to_string()).await?, ids);
+        
+        // Time filters still apply on every page
+        let in_range: Vec<i64> = readings
+            .iter()
+            .zip(&ids)
+            .filter(|(reading, _)| (1_120..=1_480).contains(&reading.timestamp.unwrap()))
+            .map(|(_, &id)| id)
+            .collect();
+        assert_eq!(pages("&order=asc&start_time=1120&end_time=1480".to_string()).await?, in_range);
+        
+        for bad in ["after=nonsense", "after=1000-1&offset=4"] {
+            let uri = format!("/api/readings?{}", bad);
+            let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_grouped_aggregate_keeps_sensors_apart() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first = create_test_sensor(&conn)?;
+        let second = create_test_sensor(&conn)?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_name, sensor_type, created_at, updated_at) VALUES ('Meter', 'power', 0, 0)",
+            [],
+        )?;
+        let power = conn.last_insert_rowid();
+        
+        let reading = |sensor_id, timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(first, 100, 10.0),
+            reading(first, 200, 20.0),
+            reading(second, 300, 4.0),
+            // Hour 1: only the second sensor reports
+            reading(second, 3700, 6.0),
+            // Other sensor types are left out
+            reading(power, 150, 1000.0),
+        ])?;
+        
+        let uri = "/api/readings/aggregate/grouped?sensor_type=temperature&interval=hour&agg=avg";
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let buckets: Vec<GroupedBucket> = serde_json::from_slice(&body)?;
+        assert_eq!(buckets.len(), 2);
+        assert_eq!(buckets[0].bucket_start, 0);
+        assert_eq!(buckets[0].values.get(&first), Some(&Some(15.0)));
+        assert_eq!(buckets[0].values.get(&second), Some(&Some(4.0)));
+        assert_eq!(buckets[1].bucket_start, 3600);
+        assert_eq!(buckets[1].values.get(&first), Some(&None));
+        assert_eq!(buckets[1].values.get(&second), Some(&Some(6.0)));
+        assert!(buckets.iter().all(|b| !b.values.contains_key(&power)));
+        
+        let (status, _, _) = send_request(Request::get("/api/readings/aggregate/grouped").body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_create_reports_sensor_status() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        // The test sensor accepts values between 18 and 25
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let submit = |body: String| {
+            Request::post("/api/readings")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        
+        let (status, _, response) = send_request(submit(format!(r#"{{"sensor_id": {}, "timestamp": 100, "value": 31.0}}"#, sensor_id))?).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["sensor_status"], "critical");
+        
+        let (_, _, response) = send_request(submit(format!(r#"{{"sensor_id": {}, "timestamp": 200, "value": 21.0}}"#, sensor_id))?).await?;
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert_eq!(response["sensor_status"], "healthy");
+        
+        // State-only readings have nothing to compare
+        let (_, _, response) = send_request(submit(format!(r#"{{"sensor_id": {}, "timestamp": 300, "state": 1}}"#, sensor_id))?).await?;
+        let response: serde_json::Value = serde_json::from_slice(&response)?;
+        assert!(response["sensor_status"].is_null());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_ndjson_import_spans_batches() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Enough lines for three batches, with a malformed one in the middle
+        let body: String = (1..=2500)
+            .map(|line| {
+                if line == 1200 {
+                    "{\"sensor_id\": oops}\n".to_string()
+                } else {
+                    format!("{{\"sensor_id\": {}, \"timestamp\": {}, \"value\": 1.0}}\n", sensor_id, line)
+                }
+            })
+            .collect();
+        let request = Request::post("/api/readings/import/ndjson")
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(Body::from(body))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // The bad line is skipped; everything around it is inserted
+        let response: ReadingNdjsonResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.inserted_count, 2499);
+        assert_eq!(response.first_error_line, Some(1200));
+        assert!(!response.success);
+        
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM readings WHERE sensor_id = ? AND timestamp = 1200",
+            [sensor_id],
+            |row| row.get(0),
+        )?;
+        assert_eq!(count, 0);
+        let total: i64 = conn.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?;
+        assert_eq!(total, 2499);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_ndjson_import_joins_lines_split_across_chunks() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Small chunks cut most lines in two, and some hold several line ends
+        let body: String = (1..=50)
+            .map(|line| format!("{{\"sensor_id\": {}, \"timestamp\": {}, \"value\": {}}}\n", sensor_id, line, line))
+            .collect();
+        let chunks: Vec<std::io::Result<Vec<u8>>> = body.into_bytes().chunks(7).map(|chunk| Ok(chunk.to_vec())).collect();
+        let request = Request::post("/api/readings/import/ndjson")
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(Body::from_stream(futures::stream::iter(chunks)))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let response: ReadingNdjsonResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.inserted_count, 50);
+        assert!(response.success);
+        
+        let sum: f64 = conn.query_row(
+            "SELECT SUM(value) FROM readings WHERE sensor_id = ? AND value = timestamp",
+            [sensor_id],
+            |row| row.get(0),
+        )?;
+        assert_eq!(sum, 1275.0);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_ndjson_import_rejects_overlong_line() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // A good line, then one that never ends
+        let mut body = format!("{{\"sensor_id\": {}, \"timestamp\": 100, \"value\": 1.0}}\n", sensor_id).into_bytes();
+        body.extend(std::iter::repeat_n(b' ', super::NDJSON_MAX_LINE_BYTES + 1));
+        let request = Request::post("/api/readings/import/ndjson")
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(Body::from(body))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::PAYLOAD_TOO_LARGE);
+        
+        let response: ReadingNdjsonResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.first_error_line, Some(2));
+        assert!(!response.success);
+        // Only the pending batch is lost; committed ones would have stayed
+        assert_eq!(response.inserted_count, 0);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_ndjson_import_reports_rejected_lines() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        create_test_reading(&conn, sensor_id)?;
+        let stored: i64 = conn.query_row("SELECT timestamp FROM readings", [], |row| row.get(0))?;
+        
+        // Well-formed lines the database turns down: an unknown sensor and an instant already stored
+        let body = format!(
+            "{{\"sensor_id\": {id}, \"timestamp\": 100, \"value\": 1.0}}\n\
+             {{\"sensor_id\": 999999, \"timestamp\": 200, \"value\": 2.0}}\n\
+             {{\"sensor_id\": oops}}\n\
+             {{\"sensor_id\": {id}, \"timestamp\": {stored}, \"value\": 3.0}}\n\
+             {{\"sensor_id\": {id}, \"timestamp\": 300, \"value\": 4.0}}\n",
+            id = sensor_id,
+            stored = stored
+        );
+        let request = Request::post("/api/readings/import/ndjson")
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(Body::from(body))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // Only the lines at fault are dropped from the batch
+        let response: ReadingNdjsonResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.inserted_count, 2);
+        let lines: Vec<usize> = response.errors.iter().map(|error| error.line).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+        assert!(response.errors[0].error.contains("999999"));
+        assert_eq!(response.first_error_line, Some(2));
+        
+        let total: i64 = conn.query_row("SELECT COUNT(*) FROM readings", [], |row| row.get(0))?;
+        assert_eq!(total, 3);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_import_on_conflict_modes() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let bulk = |query: &'static str, readings: &'static str| async move {
+            let request = Request::post(format!("/api/readings/bulk{}", query))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(format!(r#"{{"readings": {}}}"#, readings.replace("SENSOR", &sensor_id.to_string()))))?;
+            let (status, _, body) = send_request(request).await?;
+            // Query rejections answer in plain text
+            Ok::<_, anyhow::Error>((status, serde_json::from_slice::<serde_json::Value>(&body).unwrap_or_default()))
+        };
+        let stored = || -> Result<Vec<(i64, i64, f64)>> {
+            let mut stmt = conn.prepare("SELECT reading_id, timestamp, value FROM readings ORDER BY timestamp")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+            Ok(rows.collect::<Result<_, _>>()?)
+        };
+        
+        let (status, _) = bulk("", r#"[{"sensor_id": SENSOR, "timestamp": 100, "value": 1.0}, {"sensor_id": SENSOR, "timestamp": 200, "value": 2.0}]"#).await?;
+        assert_eq!(status, StatusCode::OK);
+        let original = stored()?;
+        
+        // Errors by default, and the whole batch is rolled back
+        let overlapping = r#"[{"sensor_id": SENSOR, "timestamp": 300, "value": 3.0}, {"sensor_id": SENSOR, "timestamp": 200, "value": 20.0}]"#;
+        for query in ["", "?on_conflict=error"] {
+            let (status, body) = bulk(query, overlapping).await?;
+            assert_eq!(status, StatusCode::CONFLICT);
+            assert!(body["error"].as_str().unwrap_or_default().contains("Reading 1 "));
+            assert_eq!(stored()?, original);
+        }
+        
+        // Ignore keeps the stored reading and adds the new one
+        let (status, body) = bulk("?on_conflict=ignore&return_ids=true", overlapping).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["inserted_count"], 1);
+        assert_eq!(body["reading_ids"].as_array().map(Vec::len), Some(1));
+        let after_ignore = stored()?;
+        assert_eq!(after_ignore[..2], original[..]);
+        assert_eq!((after_ignore[2].1, after_ignore[2].2), (300, 3.0));
+        
+        // Replace overwrites the value in place
+        let (status, body) = bulk("?on_conflict=replace&return_ids=true", overlapping).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["inserted_count"], 2);
+        assert_eq!(body["reading_ids"], serde_json::json!([after_ignore[2].0, original[1].0]));
+        assert_eq!(stored()?, vec![original[0], (original[1].0, 200, 20.0), after_ignore[2]]);
+        
+        let (status, _) = bulk("?on_conflict=merge", overlapping).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        // Single submissions conflict the same way
+        let request = Request::post("/api/readings")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(r#"{{"sensor_id": {}, "timestamp": 100, "value": 5.0}}"#, sensor_id)))?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::CONFLICT);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_create_reading_for_missing_sensor() -> Result<()> {
+        let _pool = setup_test_db()?;
+        
+        let request = Request::post("/api/readings")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"sensor_id": 9999, "value": 1.0}"#))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(String::from_utf8_lossy(&body).contains("Sensor 9999 not found"));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_readings_with_annotation() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let body = format!(r#"{{"sensor_id": {}, "value": 19.0, "annotation": "door opened"}}"#, sensor_id);
+        let request = Request::post("/api/readings")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        create_test_reading(&conn, sensor_id)?;
+        
+        let uri = format!("/api/readings?sensor_id={}&has_annotation=true", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].annotation.as_deref(), Some("door opened"));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_readings_tail() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(timestamp as f64),
+            ..Default::default()
+        };
+        let mut readings: Vec<Reading> = (1..=10).map(|t| reading(sensor_id, t * 100)).collect();
+        readings.push(reading(other_sensor, 5000));
+        Reading::bulk_insert(&readings)?;
+        
+        let get = |uri: String| async move {
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            Ok::<_, anyhow::Error>((status, body))
+        };
+        
+        // The newest three, oldest first
+        let (status, body) = get(format!("/api/readings?sensor_id={}&tail=3", sensor_id)).await?;
+        assert_eq!(status, StatusCode::OK);
+        let tail: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        let timestamps: Vec<i64> = tail.iter().map(|r| r.timestamp.timestamp()).collect();
+        assert_eq!(timestamps, vec![800, 900, 1000]);
+        
+        // Asking for more than exist returns them all
+        let (_, body) = get(format!("/api/readings?sensor_id={}&tail=50", sensor_id)).await?;
+        let tail: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(tail.len(), 10);
+        assert_eq!(tail[0].timestamp.timestamp(), 100);
+        
+        for extra in ["offset=2", "limit=2", "paginated=true", "bucket=60"] {
+            let (status, _) = get(format!("/api/readings?sensor_id={}&tail=3&{}", sensor_id, extra)).await?;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "tail with {}", extra);
+        }
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_readings_rate_of_change() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // A power ramp climbing 0.5 kW every minute
+        let readings: Vec<Reading> = (0..5)
+            .map(|step| Reading {
+                timestamp: Some(60_000 + step * 60),
+                sensor_id,
+                value: Some(step as f64 * 0.5),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let uri = format!("/api/readings?sensor_id={}&rate=true&order=asc", sensor_id);
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let rates: Vec<serde_json::Value> = serde_json::from_slice(&body)?;
+        assert_eq!(rates.len(), 4);
+        assert!(rates.iter().all(|r| (r["rate"].as_f64().unwrap() - 0.5 / 60.0).abs() < 1e-12));
+        assert_eq!(rates[0]["timestamp"].as_str(), Some("1970-01-01T16:41:00Z"));
+        
+        for extra in ["bucket=60", "tail=3", "paginated=true"] {
+            let uri = format!("/api/readings?sensor_id={}&rate=true&{}", sensor_id, extra);
+            let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::BAD_REQUEST, "rate with {}", extra);
+        }
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_readings_limit_is_capped() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        config::set_test_config(config::Config {
+            readings_default_limit: 3,
+            readings_max_limit: 5,
+            ..config::Config::default()
+        });
+        
+        let readings: Vec<Reading> = (1..=8)
+            .map(|t| Reading {
+                timestamp: Some(t * 100),
+                sensor_id,
+                value: Some(t as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let get = |params: &str| {
+            let uri = format!("/api/readings?sensor_id={}{}", sensor_id, params);
+            async move {
+                let (status, headers, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+                assert_eq!(status, StatusCode::OK);
+                let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+                Ok::<_, anyhow::Error>((readings.len(), headers.get(LIMIT_CLAMPED_HEADER).cloned()))
+            }
+        };
+        
+        // No limit falls back to the default
+        assert_eq!(get("").await?, (3, None));
+        // Within the cap the limit is honoured
+        assert_eq!(get("&limit=4").await?, (4, None));
+        // Oversized limits and tails are cut down to the cap and flagged
+        let (count, clamped) = get("&limit=1000000").await?;
+        assert_eq!((count, clamped.as_ref().map(|v| v.to_str().unwrap())), (5, Some("true")));
+        let (count, clamped) = get("&tail=50").await?;
+        assert_eq!((count, clamped.is_some()), (5, true));
+        
+        // A clamped page reports the limit actually applied
+        let uri = format!("/api/readings?sensor_id={}&limit=50&paginated=true", sensor_id);
+        let (_, headers, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        let page: PaginatedReadings = serde_json::from_slice(&body)?;
+        assert_eq!((page.readings.len(), page.limit, page.total, page.has_more), (5, 5, 8, true));
+        assert!(headers.contains_key(LIMIT_CLAMPED_HEADER));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_get_readings_converts_units() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        // The test sensor reports Celsius
+        let thermometer = create_test_sensor(&conn)?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_name, sensor_type, unit, created_at, updated_at)
+             VALUES ('Main Feed', 'power', 'kW', 0, 0)",
+            [],
+        )?;
+        let meter = conn.last_insert_rowid();
+        
+        let reading = |sensor_id, value| Reading {
+            timestamp: Some(1_000),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(thermometer, 100.0), reading(meter, 2.5)])?;
+        
+        let get = |sensor_id: i64, unit: &str| {
+            let uri = format!("/api/readings?sensor_id={}&convert_to={}", sensor_id, unit);
+            async move {
+                let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+                Ok::<_, anyhow::Error>((status, body))
+            }
+        };
+        
+        let (status, body) = get(thermometer, "F").await?;
+        assert_eq!(status, StatusCode::OK);
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!((readings[0].value, readings[0].unit.as_deref()), (Some(212.0), Some("F")));
+        
+        let (_, body) = get(meter, "W").await?;
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!((readings[0].value, readings[0].unit.as_deref()), (Some(2500.0), Some("W")));
+        
+        // Unconverted readings don't report a unit at all
+        let uri = format!("/api/readings?sensor_id={}", meter);
+        let (_, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        let readings: Vec<serde_json::Value> = serde_json::from_slice(&body)?;
+        assert!(readings[0].get("unit").is_none());
+        
+        // Power can't become a temperature, and unknown units are refused
+        let (status, _) = get(meter, "C").await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = get(thermometer, "furlongs").await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_delete_all_readings_for_sensor() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        for _ in 0..3 {
+            create_test_reading(&conn, sensor_id)?;
+        }
+        create_test_reading(&conn, other_sensor)?;
+        
+        let delete = |uri: String| async move {
+            let (status, _, body) = send_request(Request::delete(uri).body(Body::empty())?).await?;
+            Ok::<_, anyhow::Error>((status, body))
+        };
+        let count = |sensor_id: i64| -> Result<i64> {
+            Ok(conn.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?)
+        };
+        
+        // Without confirm nothing is touched
+        let (status, _) = delete(format!("/api/readings?sensor_id={}", sensor_id)).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _) = delete(format!("/api/readings?sensor_id={}&confirm=false", sensor_id)).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(count(sensor_id)?, 3);
+        
+        // A range is still required when no sensor is named
+        let (status, _) = delete("/api/readings?confirm=true".to_string()).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        let (status, body) = delete(format!("/api/readings?sensor_id={}&confirm=true", sensor_id)).await?;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(response["deleted_count"], 3);
+        assert_eq!((count(sensor_id)?, count(other_sensor)?), (0, 1));
+        
+        let (status, _) = delete("/api/readings?sensor_id=9999&confirm=true".to_string()).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_current_readings_for_all_sensors() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first = create_test_sensor(&conn)?;
+        let second = create_test_sensor(&conn)?;
+        // No readings, so it is left out
+        create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        // Inserted out of order
+        Reading::bulk_insert(&[
+            reading(first, 300, 3.0),
+            reading(first, 100, 1.0),
+            reading(second, 200, 20.0),
+            reading(first, 200, 2.0),
+            reading(second, 450, 50.0),
+            reading(second, 500, 51.0),
+            reading(second, 400, 40.0),
+        ])?;
+        
+        let (status, _, body) = send_request(Request::get("/api/readings/current").body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let current: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        let latest: Vec<(i64, i64, Option<f64>)> = current
+            .iter()
+            .map(|r| (r.sensor_id, r.timestamp.timestamp(), r.value))
+            .collect();
+        assert_eq!(latest, vec![(first, 300, Some(3.0)), (second, 500, Some(51.0))]);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_readings_reject_invalid_time_ranges() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let reading = |timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(20.0),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(1000), reading(2000), reading(3000)])?;
+        
+        let get = |path: &str, range: &str| {
+            let uri = format!("{}?sensor_id={}&{}", path, sensor_id, range);
+            async move {
+                let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+                Ok::<_, anyhow::Error>((status, body))
+            }
+        };
+        
+        for path in ["/api/readings", "/api/readings/stats"] {
+            for range in ["start_time=3000&end_time=1000", "start_time=-5", "end_time=-1"] {
+                let (status, body) = get(path, range).await?;
+                assert_eq!(status, StatusCode::BAD_REQUEST, "{} with {}", path, range);
+                let error: serde_json::Value = serde_json::from_slice(&body)?;
+                assert!(error.to_string().contains("_time must not be"), "{}", error);
+            }
+        }
+        let (status, _) = get("/api/readings", "start_time=3000&end_time=1000&bucket=60").await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        // One-sided and single-instant ranges stay valid
+        let (status, body) = get("/api/readings", "start_time=2000").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_slice::<Vec<ReadingResponse>>(&body)?.len(), 2);
+        let (status, body) = get("/api/readings", "end_time=2000").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_slice::<Vec<ReadingResponse>>(&body)?.len(), 2);
+        let (status, body) = get("/api/readings", "start_time=2000&end_time=2000").await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(serde_json::from_slice::<Vec<ReadingResponse>>(&body)?.len(), 1);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_idempotency_key_prevents_duplicate_readings() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let post = |key: &'static str, timestamp: i64| async move {
+            let request = Request::post("/api/readings")
+                .header(header::CONTENT_TYPE, "application/json")
+                .header("Idempotency-Key", key)
+                .body(Body::from(format!(r#"{{"sensor_id": {}, "timestamp": {}, "value": 21.5}}"#, sensor_id, timestamp)))?;
+            let (status, _, body) = send_request(request).await?;
+            Ok::<_, anyhow::Error>((status, serde_json::from_slice::<serde_json::Value>(&body)?))
+        };
+        let count = || -> Result<i64> {
+            Ok(conn.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?)
+        };
+        
+        let (status, first) = post("reading-1", 100).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        
+        // The retry gets the same response and stores nothing new
+        let (status, retried) = post("reading-1", 100).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(retried["reading_id"], first["reading_id"]);
+        assert_eq!(count()?, 1);
+        
+        let (_, other) = post("reading-2", 200).await?;
+        assert_ne!(other["reading_id"], first["reading_id"]);
+        assert_eq!(count()?, 2);
+        
+        // A failed request isn't remembered, so its key stays usable
+        let request = Request::post("/api/readings")
+            .header(header::CONTENT_TYPE, "application/json")
+            .header("Idempotency-Key", "reading-3")
+            .body(Body::from(r#"{"sensor_id": 9999, "value": 1.0}"#))?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _) = post("reading-3", 300).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(count()?, 3);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_histogram_bins_known_values() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let flat_sensor = create_test_sensor(&conn)?;
+        
+        // 0..=10 inside the range, plus readings the filters must leave out
+        let mut readings: Vec<Reading> = (0..=10)
+            .map(|i| Reading { timestamp: Some(1_000 + i), sensor_id, value: Some(i as f64), ..Default::default() })
+            .collect();
+        readings.push(Reading { timestamp: Some(5_000), sensor_id, value: Some(100.0), ..Default::default() });
+        readings.push(Reading { timestamp: Some(1_011), sensor_id, state: Some(1), ..Default::default() });
+        readings.extend((0..4).map(|i| Reading {
+            timestamp: Some(1_000 + i),
+            sensor_id: flat_sensor,
+            value: Some(5.0),
+            ..Default::default()
+        }));
+        Reading::bulk_insert(&readings)?;
+        
+        let histogram = |params: String| async move {
+            let uri = format!("/api/readings/histogram?{}", params);
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::OK);
+            Ok::<_, anyhow::Error>(serde_json::from_slice::<ReadingHistogram>(&body)?)
+        };
+        let edges = |histogram: &ReadingHistogram| -> Vec<(f64, f64, usize)> {
+            histogram.bins.iter().map(|bin| (bin.lower, bin.upper, bin.count)).collect()
+        };
+        
+        let five = histogram(format!("sensor_id={}&start_time=1000&end_time=2000&bins=5", sensor_id)).await?;
+        assert_eq!((five.count, five.min, five.max), (11, Some(0.0), Some(10.0)));
+        // The maximum is counted in the last bin
+        assert_eq!(
+            edges(&five),
+            vec![(0.0, 2.0, 2), (2.0, 4.0, 2), (4.0, 6.0, 2), (6.0, 8.0, 2), (8.0, 10.0, 3)]
+        );
+        
+        let default = histogram(format!("sensor_id={}&end_time=2000", sensor_id)).await?;
+        assert_eq!(default.bins.len(), 10);
+        assert_eq!(default.bins.iter().map(|bin| bin.count).sum::<usize>(), 11);
+        
+        // Identical values share one zero-width bin
+        let flat = histogram(format!("sensor_id={}&bins=5", flat_sensor)).await?;
+        assert_eq!(edges(&flat), vec![(5.0, 5.0, 4)]);
+        
+        let empty = histogram(format!("sensor_id={}&start_time=9000", sensor_id)).await?;
+        assert_eq!((empty.count, empty.min), (0, None));
+        assert!(empty.bins.is_empty());
+        
+        for bad in ["bins=0".to_string(), format!("sensor_id={}&bins=0", sensor_id)] {
+            let uri = format!("/api/readings/histogram?{}", bad);
+            let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+        
+        Ok(())
+    }
+}
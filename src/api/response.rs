@@ -0,0 +1,109 @@
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, HeaderValue, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+
+use crate::utils::error::AppError;
+
+/// Content type used for MessagePack responses
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// Response encoding negotiated from the request's `Accept` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseFormat {
+    Json,
+    MsgPack,
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ResponseFormat
+where
+    S: Send + Sync,
+{
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let wants_msgpack = parts
+            .headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .map(|accept| {
+                accept.split(',').any(|media_type| {
+                    let media_type = media_type.split(';').next().unwrap_or_default().trim();
+                    media_type.eq_ignore_ascii_case(MSGPACK_CONTENT_TYPE)
+                        || media_type.eq_ignore_ascii_case("application/x-msgpack")
+                })
+            })
+            .unwrap_or(false);
+
+        // JSON stays the default for anything that doesn't explicitly ask for MessagePack
+        if wants_msgpack {
+            Ok(ResponseFormat::MsgPack)
+        } else {
+            Ok(ResponseFormat::Json)
+        }
+    }
+}
+
+impl ResponseFormat {
+    /// Serialize a response body in the negotiated format
+    pub fn render<T: Serialize>(self, value: &T) -> Result<Response, AppError> {
+        match self {
+            ResponseFormat::Json => Ok(Json(value).into_response()),
+            ResponseFormat::MsgPack => {
+                let body = rmp_serde::to_vec_named(value)
+                    .map_err(|err| AppError::Internal(err.into()))?;
+
+                Ok(([(header::CONTENT_TYPE, MSGPACK_CONTENT_TYPE)], body).into_response())
+            }
+        }
+    }
+
+    /// Render with a weak ETag, or answer 304 when `If-None-Match` already has it
+    ///
+    /// The tag is a hash of the value's fields, so any change to them,
+    /// `updated_at` included, produces a new one.
+    pub fn render_with_etag<T: Serialize>(self, headers: &HeaderMap, value: &T) -> Result<Response, AppError> {
+        let etag = self.etag(value)?;
+        let etag_value = HeaderValue::from_str(&etag).map_err(|err| AppError::Internal(err.into()))?;
+
+        if if_none_match(headers, &etag) {
+            return Ok((StatusCode::NOT_MODIFIED, [(header::ETAG, etag_value)]).into_response());
+        }
+
+        let mut response = self.render(value)?;
+        response.headers_mut().insert(header::ETAG, etag_value);
+        Ok(response)
+    }
+
+    /// Weak validator for a value in this format
+    fn etag<T: Serialize>(self, value: &T) -> Result<String, AppError> {
+        let bytes = serde_json::to_vec(value).map_err(|err| AppError::Internal(err.into()))?;
+
+        // JSON and MessagePack bodies differ, so their tags must too
+        let mut hasher = DefaultHasher::new();
+        bytes.hash(&mut hasher);
+        (self == ResponseFormat::MsgPack).hash(&mut hasher);
+
+        Ok(format!("W/\"{:016x}\"", hasher.finish()))
+    }
+}
+
+/// Whether the request's `If-None-Match` lists `etag`, compared weakly
+fn if_none_match(headers: &HeaderMap, etag: &str) -> bool {
+    let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+
+    headers
+        .get_all(header::IF_NONE_MATCH)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .any(|tag| tag.trim() == "*" || opaque(tag) == opaque(etag))
+}
@@ -1,12 +1,34 @@
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Body,
+    extract::{Path, Query},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
+use std::sync::Arc;
 
-use crate::models::{Sensor, SensorQuery, SensorResponse};
-use crate::utils::error::AppError;
+use crate::api::response::ResponseFormat;
+use crate::db::get_connection;
+use crate::models::{
+    ApiToken, LoggingSession, LoggingSessionResponse, Reading, ReadingQuery, ReadingResponse, Sensor,
+    SensorBatchDelete, SensorBulkCreate, SensorBulkDelete, SensorBulkResponse, SensorConfigDocument, SensorConfigImport, SensorDeleteResult, SensorPatch,
+    SensorAuditEntry, SensorQuery, SensorResponse, SensorStats,
+};
+use crate::utils::{cache, csv, error::AppError};
+
+/// Sensors encoded per chunk of a CSV export
+const CSV_EXPORT_CHUNK: usize = 500;
+
+/// Everything a dashboard tile shows for one sensor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorSummary {
+    pub sensor: SensorResponse,
+    pub current_reading: Option<ReadingResponse>,
+    pub active_session: Option<LoggingSessionResponse>,
+    pub stats: SensorStats,
+}
 
 /// Create a new sensor
 pub async fn create_sensor(
@@ -22,28 +44,119 @@ pub async fn create_sensor(
     Ok((StatusCode::CREATED, Json(response)))
 }
 
+/// Create several sensors at once
+pub async fn bulk_create_sensors(
+    Json(payload): Json<SensorBulkCreate>,
+) -> Result<(StatusCode, Json<SensorBulkResponse>), AppError> {
+    let sensor_ids = Sensor::bulk_create(&payload.sensors)?;
+    
+    let response = SensorBulkResponse {
+        created_count: sensor_ids.len(),
+        sensor_ids,
+        success: true,
+    };
+    
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
 /// Get all sensors with optional filtering
 pub async fn get_all_sensors(
+    format: ResponseFormat,
+    headers: HeaderMap,
     Query(query): Query<SensorQuery>,
-) -> Result<Json<Vec<SensorResponse>>, AppError> {
+) -> Result<Response, AppError> {
     let sensors = Sensor::get_all(&query)?;
-    Ok(Json(sensors))
+    format.render_with_etag(&headers, &sensors)
+}
+
+/// Download sensors as CSV, filtered like `/api/sensors`
+///
+/// Rows are encoded chunk by chunk as the body is sent, with the header
+/// row at the top even when no sensors match.
+pub async fn export_sensors_csv(
+    Query(query): Query<SensorQuery>,
+) -> Result<Response, AppError> {
+    let mut sensors = Sensor::get_all(&query)?.into_iter().peekable();
+    let mut include_headers = true;
+    
+    let chunks = std::iter::from_fn(move || {
+        if !include_headers && sensors.peek().is_none() {
+            return None;
+        }
+        
+        let chunk: Vec<SensorResponse> = sensors.by_ref().take(CSV_EXPORT_CHUNK).collect();
+        let mut buffer = Vec::new();
+        let encoded = csv::export_sensors_to_csv(&mut buffer, &chunk, include_headers)
+            .map(|_| buffer)
+            .map_err(|err| std::io::Error::other(err.to_string()));
+        include_headers = false;
+        Some(encoded)
+    });
+    
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv"),
+            (header::CONTENT_DISPOSITION, "attachment; filename=\"sensors.csv\""),
+        ],
+        Body::from_stream(futures::stream::iter(chunks)),
+    )
+        .into_response())
+}
+
+/// Export the configuration of all sensors as one document
+pub async fn export_sensor_config() -> Result<Json<SensorConfigDocument>, AppError> {
+    let document = Sensor::export_config()?;
+    Ok(Json(document))
+}
+
+/// Import a sensor configuration document, upserting sensors by name
+pub async fn import_sensor_config(
+    Json(document): Json<SensorConfigDocument>,
+) -> Result<Json<SensorConfigImport>, AppError> {
+    let import = Sensor::import_config(&document)?;
+    Ok(Json(import))
 }
 
 /// Get a sensor by ID
 pub async fn get_sensor_by_id(
+    format: ResponseFormat,
+    headers: HeaderMap,
     Path(id): Path<i64>,
-) -> Result<Json<SensorResponse>, AppError> {
+) -> Result<Response, AppError> {
     let sensor = Sensor::get_by_id(id)?;
-    Ok(Json(sensor))
+    format.render_with_etag(&headers, &sensor)
+}
+
+/// Get a sensor's readings, filtered like `/api/readings`
+pub async fn get_sensor_readings(
+    format: ResponseFormat,
+    Path(id): Path<i64>,
+    Query(mut query): Query<ReadingQuery>,
+) -> Result<Response, AppError> {
+    // 404 for an unknown sensor rather than an empty list
+    Sensor::get_by_id(id)?;
+    
+    query.sensor_id = Some(id);
+    let readings = Reading::get(&query)?;
+    format.render(&readings)
+}
+
+/// List every recorded change to a sensor, oldest first
+///
+/// The history of a hard-deleted sensor is still served.
+pub async fn get_sensor_history(
+    Path(id): Path<i64>,
+) -> Result<Json<Vec<SensorAuditEntry>>, AppError> {
+    let history = SensorAuditEntry::for_sensor(id)?;
+    Ok(Json(history))
 }
 
 /// Update a sensor
 pub async fn update_sensor(
     Path(id): Path<i64>,
-    Json(sensor): Json<Sensor>,
+    Json(patch): Json<SensorPatch>,
 ) -> Result<(StatusCode, Json<Value>), AppError> {
-    sensor.update(id)?;
+    Sensor::patch(id, &patch)?;
     
     let response = json!({
         "success": true,
@@ -53,16 +166,797 @@ pub async fn update_sensor(
     Ok((StatusCode::OK, Json(response)))
 }
 
+/// Archive a sensor so it stays readable but rejects further writes
+pub async fn archive_sensor(
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, Json<Value>), AppError> {
+    Sensor::archive(id)?;
+    
+    let response = json!({
+        "success": true,
+        "sensor_id": id,
+        "archived": true
+    });
+    
+    Ok((StatusCode::OK, Json(response)))
+}
+
+/// Tag a sensor; tags are stored lowercase
+pub async fn add_sensor_tag(
+    Path((id, tag)): Path<(i64, String)>,
+) -> Result<Json<Value>, AppError> {
+    let tags = Sensor::add_tag(id, &tag)?;
+    
+    let response = json!({
+        "success": true,
+        "sensor_id": id,
+        "tags": tags
+    });
+    
+    Ok(Json(response))
+}
+
+/// Remove a tag from a sensor
+pub async fn remove_sensor_tag(
+    Path((id, tag)): Path<(i64, String)>,
+) -> Result<Json<Value>, AppError> {
+    let tags = Sensor::remove_tag(id, &tag)?;
+    
+    let response = json!({
+        "success": true,
+        "sensor_id": id,
+        "tags": tags
+    });
+    
+    Ok(Json(response))
+}
+
+/// Issue a token that may only write readings for this sensor
+pub async fn create_sensor_token(
+    Path(id): Path<i64>,
+) -> Result<(StatusCode, Json<ApiToken>), AppError> {
+    let token = ApiToken::create(id)?;
+    Ok((StatusCode::CREATED, Json(token)))
+}
+
+/// Delete several sensors and their readings for good; requires `confirm: true`
+///
+/// With `atomic: true` an unknown id is a 404 and no sensor is deleted.
+pub async fn bulk_delete_sensors(
+    Json(request): Json<SensorBulkDelete>,
+) -> Result<Json<Vec<SensorDeleteResult>>, AppError> {
+    if !request.confirm {
+        return Err(AppError::BadRequest("Bulk delete requires \"confirm\": true".to_string()));
+    }
+    
+    let results = Sensor::delete_many(&request.ids, true, request.atomic)?;
+    Ok(Json(results))
+}
+
+/// Delete several sensors in one transaction, reporting the outcome per id
+///
+/// Like `DELETE /api/sensors/:id`, sensors are only disabled unless
+/// `hard: true` is given. With `atomic: true` an unknown id is a 404 and
+/// no sensor is touched.
+pub async fn delete_sensor_batch(
+    Json(request): Json<SensorBatchDelete>,
+) -> Result<Json<Vec<SensorDeleteResult>>, AppError> {
+    let results = Sensor::delete_many(&request.sensor_ids, request.hard, request.atomic)?;
+    Ok(Json(results))
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SensorDeleteQuery {
+    pub hard: Option<bool>,  // Remove the sensor and its readings for good
+}
+
 /// Delete a sensor
+///
+/// By default the sensor is only disabled: it drops out of listings and
+/// refuses writes, but it and its readings stay. `hard=true` removes both.
 pub async fn delete_sensor(
     Path(id): Path<i64>,
+    Query(query): Query<SensorDeleteQuery>,
 ) -> Result<(StatusCode, Json<Value>), AppError> {
-    Sensor::delete(id)?;
+    let hard = query.hard.unwrap_or(false);
+    if hard {
+        Sensor::delete(id)?;
+    } else {
+        Sensor::disable(id)?;
+    }
     
     let response = json!({
         "success": true,
-        "sensor_id": id
+        "sensor_id": id,
+        "hard_deleted": hard
     });
     
     Ok((StatusCode::OK, Json(response)))
-}
\ No newline at end of file
+}
+
+/// Get ingest statistics for a sensor
+pub async fn get_sensor_stats(
+    Path(id): Path<i64>,
+) -> Result<Response, AppError> {
+    let stats: Arc<SensorStats> = cache::cached(&format!("stats:{}", id), Some(id), || Sensor::stats(id))?;
+    Ok(Json(&*stats).into_response())
+}
+
+/// Get a sensor with its current reading, active session and stats
+pub async fn get_sensor_summary(
+    format: ResponseFormat,
+    Path(id): Path<i64>,
+) -> Result<Response, AppError> {
+    // One checkout serves every query below
+    let conn = get_connection()?;
+    
+    let summary = SensorSummary {
+        sensor: Sensor::get_by_id_with(&conn, id)?,
+        current_reading: Reading::get_current_with(&conn, id)?,
+        active_session: LoggingSession::get_active_with(&conn, id)?,
+        stats: Sensor::stats_with(&conn, id)?,
+    };
+    
+    format.render(&summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use super::SensorSummary;
+    use crate::{
+        db::test_checkout_count,
+        models::{
+            sensor::SensorConfig, Reading, ReadingResponse, Sensor, SensorBulkResponse, SensorConfigDocument,
+            SensorAuditEntry, SensorConfigImport, SensorDeleteResult, SensorResponse, SensorStats,
+        },
+        utils::{
+            current_timestamp,
+            test_utils::{setup_test_db, create_test_sensor, create_test_reading, create_test_session, send_request},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_sensor_stats() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let now = current_timestamp();
+        let reading = |timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(1.0),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(now - 30 * 86400),
+            reading(now - 2 * 86400),
+            reading(now - 1),
+            reading(now),
+        ])?;
+        
+        let request = Request::get(format!("/api/sensors/{}/stats", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let stats: SensorStats = serde_json::from_slice(&body)?;
+        assert_eq!(stats.ingest_count, 4);
+        assert_eq!(stats.last_ingest.map(|ts| ts.timestamp()), Some(now));
+        
+        // Seven days, oldest first, with the month-old reading outside the window
+        assert_eq!(stats.readings_per_day.len(), 7);
+        let week_total: i64 = stats.readings_per_day.iter().map(|day| day.count).sum();
+        assert_eq!(week_total, 3);
+        assert_eq!(stats.readings_per_day[4].count, 1);
+        assert_eq!(stats.readings_per_day[6].count + stats.readings_per_day[5].count, 2);
+        
+        // Unknown sensors are reported as not found
+        let request = Request::get("/api/sensors/999999/stats").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_archived_sensor_is_read_only() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        create_test_reading(&conn, sensor_id)?;
+        
+        let request = Request::post(format!("/api/sensors/{}/archive", sensor_id)).body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // The sensor and its readings are still readable
+        let request = Request::get(format!("/api/sensors/{}", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        let sensor: SensorResponse = serde_json::from_slice(&body)?;
+        assert!(sensor.archived);
+        
+        let request = Request::get(format!("/api/readings/current/{}", sensor_id)).body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // New readings, sessions and edits are rejected
+        let writes = [
+            ("POST", "/api/readings".to_string(), format!(r#"{{"sensor_id": {}, "value": 1.0}}"#, sensor_id)),
+            ("POST", "/api/readings/bulk".to_string(), format!(r#"{{"readings": [{{"sensor_id": {}, "value": 1.0}}]}}"#, sensor_id)),
+            ("POST", "/api/sessions".to_string(), format!(r#"{{"sensor_id": {}}}"#, sensor_id)),
+            (
+                "PUT",
+                format!("/api/sensors/{}", sensor_id),
+                r#"{"sensor_name": "Renamed", "sensor_type": "temperature"}"#.to_string(),
+            ),
+        ];
+        for (method, uri, body) in writes {
+            let request = Request::builder()
+                .method(method)
+                .uri(&uri)
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))?;
+            let (status, _, _) = send_request(request).await?;
+            assert_eq!(status, StatusCode::CONFLICT, "{} {}", method, uri);
+        }
+        
+        let readings: i64 = conn.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?;
+        assert_eq!(readings, 1);
+        assert_eq!(crate::models::Sensor::get_by_id(sensor_id)?.sensor_name, "Test Sensor");
+        
+        // Archiving an unknown sensor is reported as not found
+        let request = Request::post("/api/sensors/999999/archive").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_update_keeps_omitted_fields() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let request = Request::put(format!("/api/sensors/{}", sensor_id))
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"threshold_max": 28.5}"#))?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let sensor = crate::models::Sensor::get_by_id(sensor_id)?;
+        assert_eq!(sensor.threshold_max, Some(28.5));
+        assert_eq!(sensor.sensor_name, "Test Sensor");
+        assert!(sensor.location.is_some());
+        assert!(sensor.unit.is_some());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_config_round_trip() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        create_test_sensor(&conn)?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_name, sensor_type, location, unit, threshold_max, created_at, updated_at)
+             VALUES ('Main Feed', 'power', 'Plant Room', 'kW', 120.0, 0, 0)",
+            [],
+        )?;
+        
+        let request = Request::get("/api/sensors/config").body(Body::empty())?;
+        let (status, _, exported) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        let original: SensorConfigDocument = serde_json::from_slice(&exported)?;
+        assert_eq!(original.sensors.len(), 2);
+        
+        conn.execute("DELETE FROM sensors", [])?;
+        
+        let import = |body: Vec<u8>| {
+            Request::post("/api/sensors/config")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        let (status, _, body) = send_request(import(exported.to_vec())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let result: SensorConfigImport = serde_json::from_slice(&body)?;
+        assert_eq!((result.created, result.updated), (2, 0));
+        
+        let request = Request::get("/api/sensors/config").body(Body::empty())?;
+        let (_, _, body) = send_request(request).await?;
+        let restored: SensorConfigDocument = serde_json::from_slice(&body)?;
+        assert_eq!(restored.sensors, original.sensors);
+        
+        // Importing again updates by name instead of duplicating
+        let (_, _, body) = send_request(import(exported.to_vec())?).await?;
+        let result: SensorConfigImport = serde_json::from_slice(&body)?;
+        assert_eq!((result.created, result.updated), (0, 2));
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM sensors", [], |row| row.get(0))?;
+        assert_eq!(count, 2);
+        
+        // Malformed documents and duplicate names are rejected
+        let (status, _, _) = send_request(import(br#"{"version": 1}"#.to_vec())?).await?;
+        assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+        
+        let duplicate = SensorConfig {
+            sensor_name: "Twin".to_string(),
+            sensor_type: "flow".to_string(),
+            location: None,
+            unit: None,
+            threshold_min: None,
+            threshold_max: None,
+            calibration_date: None,
+            notes: None,
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
+        };
+        let document = SensorConfigDocument {
+            version: 1,
+            sensors: vec![duplicate.clone(), duplicate],
+        };
+        let (status, _, _) = send_request(import(serde_json::to_vec(&document)?)?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_summary_uses_one_checkout() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let reading_id = create_test_reading(&conn, sensor_id)?;
+        let session_id = create_test_session(&conn, sensor_id, true)?;
+        
+        let checkouts_before = test_checkout_count();
+        let request = Request::get(format!("/api/sensors/{}/summary", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(test_checkout_count() - checkouts_before, 1);
+        
+        let summary: SensorSummary = serde_json::from_slice(&body)?;
+        assert_eq!(summary.sensor.sensor_id, sensor_id);
+        assert_eq!(summary.current_reading.map(|r| r.reading_id), Some(reading_id));
+        assert_eq!(summary.active_session.map(|s| s.session_id), Some(session_id));
+        assert_eq!(summary.stats.ingest_count, 1);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_summary_without_readings_or_sessions() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let request = Request::get(format!("/api/sensors/{}/summary", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // Missing parts come back as null rather than failing the request
+        let summary: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(summary["sensor"]["sensor_id"], sensor_id);
+        assert!(summary["current_reading"].is_null());
+        assert!(summary["active_session"].is_null());
+        assert_eq!(summary["stats"]["ingest_count"], 0);
+        assert!(summary["stats"]["last_ingest"].is_null());
+        
+        let request = Request::get("/api/sensors/9999/summary").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_readings_route() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_id = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(20.0),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(sensor_id, 100),
+            reading(sensor_id, 200),
+            reading(sensor_id, 300),
+            reading(other_id, 150),
+        ])?;
+        
+        let uri = format!("/api/sensors/{}/readings?start_time=150&order=asc&limit=5", sensor_id);
+        let request = Request::get(uri).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let readings: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert!(readings.iter().all(|r| r.sensor_id == sensor_id));
+        let timestamps: Vec<i64> = readings.iter().map(|r| r.timestamp.timestamp()).collect();
+        assert_eq!(timestamps, vec![200, 300]);
+        
+        let request = Request::get("/api/sensors/9999/readings").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        let uri = format!("/api/sensors/{}/readings?order=sideways", sensor_id);
+        let request = Request::get(uri).body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_delete_requires_confirmation() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first = create_test_sensor(&conn)?;
+        let second = create_test_sensor(&conn)?;
+        let kept = create_test_sensor(&conn)?;
+        create_test_reading(&conn, first)?;
+        
+        let bulk_delete = |body: String| {
+            Request::post("/api/sensors/bulk-delete")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        
+        // Without confirm nothing is deleted
+        let (status, _, _) = send_request(bulk_delete(format!(r#"{{"ids": [{}, {}]}}"#, first, second))?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let (status, _, _) = send_request(bulk_delete(format!(r#"{{"ids": [{}], "confirm": false}}"#, first))?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM sensors", [], |row| row.get(0))?;
+        assert_eq!(count, 3);
+        
+        let body = format!(r#"{{"ids": [{}, {}, 999999], "confirm": true}}"#, first, second);
+        let (status, _, body) = send_request(bulk_delete(body)?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let results: Vec<SensorDeleteResult> = serde_json::from_slice(&body)?;
+        let outcomes: Vec<(i64, bool)> = results.iter().map(|r| (r.sensor_id, r.deleted)).collect();
+        assert_eq!(outcomes, vec![(first, true), (second, true), (999999, false)]);
+        assert!(results[2].error.is_some());
+        
+        let remaining: Vec<i64> = conn
+            .prepare("SELECT sensor_id FROM sensors")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(remaining, vec![kept]);
+        let readings: i64 = conn.query_row("SELECT COUNT(*) FROM readings", [], |row| row.get(0))?;
+        assert_eq!(readings, 0);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_delete_batch_atomic_and_partial() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first = create_test_sensor(&conn)?;
+        let second = create_test_sensor(&conn)?;
+        
+        create_test_reading(&conn, first)?;
+        
+        let delete_batch = |extra: &str| {
+            let body = format!(r#"{{"sensor_ids": [{}, 999999, {}]{}}}"#, first, second, extra);
+            Request::post("/api/sensors/delete-batch")
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(body))
+        };
+        let count = |sql: &str| -> Result<i64> { Ok(conn.query_row(sql, [], |row| row.get(0))?) };
+        let outcomes = |body: &[u8]| -> Result<Vec<(i64, bool)>> {
+            let results: Vec<SensorDeleteResult> = serde_json::from_slice(body)?;
+            Ok(results.iter().map(|r| (r.sensor_id, r.deleted)).collect())
+        };
+        
+        // Atomic: the unknown id aborts the batch before anything is touched
+        let (status, _, body) = send_request(delete_batch(r#", "atomic": true"#)?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        assert!(String::from_utf8_lossy(&body).contains("999999"));
+        assert_eq!(count("SELECT COUNT(*) FROM sensors WHERE disabled_at IS NULL")?, 2);
+        
+        // Otherwise it is reported and the rest are disabled, keeping their readings
+        let (status, _, body) = send_request(delete_batch("")?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(outcomes(&body)?, vec![(first, true), (999999, false), (second, true)]);
+        assert_eq!(count("SELECT COUNT(*) FROM sensors WHERE disabled_at IS NOT NULL")?, 2);
+        assert_eq!(count("SELECT COUNT(*) FROM readings")?, 1);
+        
+        // Hard deletes remove both
+        let (status, _, body) = send_request(delete_batch(r#", "hard": true"#)?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(outcomes(&body)?, vec![(first, true), (999999, false), (second, true)]);
+        assert_eq!(count("SELECT COUNT(*) FROM sensors")?, 0);
+        assert_eq!(count("SELECT COUNT(*) FROM readings")?, 0);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_bulk_create_sensors() -> Result<()> {
+        let _pool = setup_test_db()?;
+        
+        let body = r#"{"sensors": [
+            {"sensor_name": "Boiler Temp", "sensor_type": "temperature", "unit": "C"},
+            {"sensor_name": "Main Meter", "sensor_type": "power", "unit": "kW"},
+            {"sensor_name": "Return Flow", "sensor_type": "flow"}
+        ]}"#;
+        let request = Request::post("/api/sensors/bulk")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        
+        let response: SensorBulkResponse = serde_json::from_slice(&body)?;
+        assert_eq!(response.created_count, 3);
+        let names: Vec<String> = response
+            .sensor_ids
+            .iter()
+            .map(|&id| Sensor::get_by_id(id).map(|sensor| sensor.sensor_name))
+            .collect::<Result<_, _>>()?;
+        assert_eq!(names, vec!["Boiler Temp", "Main Meter", "Return Flow"]);
+        
+        // One bad sensor rejects the whole batch and is named in the error
+        let body = r#"{"sensors": [
+            {"sensor_name": "Lobby Light", "sensor_type": "light"},
+            {"sensor_name": "Roof Gauge", "sensor_type": "barometer"}
+        ]}"#;
+        let request = Request::post("/api/sensors/bulk")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(body))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        let error: serde_json::Value = serde_json::from_slice(&body)?;
+        assert!(error["error"].as_str().unwrap_or_default().contains("Sensor 1 ('Roof Gauge')"));
+        
+        let request = Request::get("/api/sensors").body(Body::empty())?;
+        let (_, _, body) = send_request(request).await?;
+        let sensors: Vec<SensorResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(sensors.len(), 3);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_etag_revalidation() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let get = |uri: String, etag: Option<String>| async move {
+            let mut request = Request::get(uri);
+            if let Some(etag) = etag {
+                request = request.header(header::IF_NONE_MATCH, etag);
+            }
+            let (status, headers, body) = send_request(request.body(Body::empty())?).await?;
+            let etag = headers.get(header::ETAG).map(|v| v.to_str().unwrap().to_string());
+            Ok::<_, anyhow::Error>((status, etag, body))
+        };
+        
+        for uri in [format!("/api/sensors/{}", sensor_id), "/api/sensors".to_string()] {
+            let (status, etag, body) = get(uri.clone(), None).await?;
+            assert_eq!(status, StatusCode::OK);
+            let etag = etag.expect("ETag header");
+            assert!(etag.starts_with("W/\""));
+            assert!(!body.is_empty());
+            
+            // An unchanged sensor revalidates without a body
+            let (status, same, body) = get(uri.clone(), Some(etag.clone())).await?;
+            assert_eq!(status, StatusCode::NOT_MODIFIED);
+            assert_eq!(same.as_ref(), Some(&etag));
+            assert!(body.is_empty());
+            let (status, _, _) = get(uri.clone(), Some(format!("\"stale\", {}", etag))).await?;
+            assert_eq!(status, StatusCode::NOT_MODIFIED);
+            
+            // Any change to the sensor produces a new tag
+            conn.execute(
+                "UPDATE sensors SET notes = ? WHERE sensor_id = ?",
+                rusqlite::params![format!("Checked via {}", uri), sensor_id],
+            )?;
+            let (status, changed, _) = get(uri.clone(), Some(etag.clone())).await?;
+            assert_eq!(status, StatusCode::OK);
+            assert_ne!(changed, Some(etag));
+        }
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_delete_disables_unless_hard() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let kept = create_test_sensor(&conn)?;
+        let sensor_id = create_test_sensor(&conn)?;
+        create_test_reading(&conn, sensor_id)?;
+        
+        let send = |request: Request<Body>| async move {
+            let (status, _, body) = send_request(request).await?;
+            Ok::<_, anyhow::Error>((status, body))
+        };
+        let listed = |uri: &'static str| async move {
+            let (_, body) = send(Request::get(uri).body(Body::empty())?).await?;
+            let sensors: Vec<SensorResponse> = serde_json::from_slice(&body)?;
+            Ok::<_, anyhow::Error>(sensors.iter().map(|s| s.sensor_id).collect::<Vec<_>>())
+        };
+        let readings = |sensor_id: i64| -> Result<i64> {
+            Ok(conn.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?)
+        };
+        
+        let (status, _) = send(Request::delete(format!("/api/sensors/{}", sensor_id)).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // Hidden from listings, but it and its readings are still there
+        assert_eq!(listed("/api/sensors").await?, vec![kept]);
+        assert_eq!(listed("/api/sensors?include_disabled=true").await?, vec![kept, sensor_id]);
+        let (status, body) = send(Request::get(format!("/api/sensors/{}", sensor_id)).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert!(serde_json::from_slice::<SensorResponse>(&body)?.disabled_at.is_some());
+        assert_eq!(readings(sensor_id)?, 1);
+        
+        // No new readings for a disabled sensor
+        let request = Request::post("/api/readings")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(format!(r#"{{"sensor_id": {}, "value": 20.0}}"#, sensor_id)))?;
+        let (status, _) = send(request).await?;
+        assert_eq!(status, StatusCode::CONFLICT);
+        
+        let uri = format!("/api/sensors/{}?hard=true", sensor_id);
+        let (status, _) = send(Request::delete(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(listed("/api/sensors?include_disabled=true").await?, vec![kept]);
+        assert_eq!(readings(sensor_id)?, 0);
+        
+        let (status, _) = send(Request::delete("/api/sensors/9999").body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_export_sensors_as_csv() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_name, sensor_type, location, unit, threshold_max, created_at, updated_at)
+             VALUES ('Main Feed, North', 'power', 'Plant Room', 'kW', 120.0, 1744374600, 1744374600)",
+            [],
+        )?;
+        let meter = conn.last_insert_rowid();
+        
+        let (status, headers, body) = send_request(Request::get("/api/sensors/export").body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(headers[header::CONTENT_DISPOSITION], "attachment; filename=\"sensors.csv\"");
+        
+        let csv = String::from_utf8(body.to_vec())?;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "sensor_id,sensor_name,sensor_type,location,unit,threshold_min,threshold_max,calibration_date,notes,created_at,updated_at"
+        );
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].starts_with(&format!("{},Test Sensor,temperature,Test Location,C,18,25,,Test Notes,", sensor_id)));
+        // Names with commas are quoted, and times use the CSV timestamp format
+        assert_eq!(
+            lines[2],
+            format!(r#"{},"Main Feed, North",power,Plant Room,kW,,120,,,2025-04-11 12:30:00,2025-04-11 12:30:00"#, meter)
+        );
+        
+        // Filters apply as on the listing
+        let (_, _, body) = send_request(Request::get("/api/sensors/export?sensor_type=power").body(Body::empty())?).await?;
+        let csv = String::from_utf8(body.to_vec())?;
+        assert_eq!(csv.lines().count(), 2);
+        assert!(csv.lines().nth(1).unwrap_or_default().starts_with(&format!("{},", meter)));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_tag_sensors_and_filter_by_tag() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let boiler = create_test_sensor(&conn)?;
+        let fan = create_test_sensor(&conn)?;
+        let lamp = create_test_sensor(&conn)?;
+        
+        let tag = |method: &str, sensor_id: i64, tag: &str| -> Result<Request<Body>> {
+            let uri = format!("/api/sensors/{}/tags/{}", sensor_id, tag);
+            Ok(Request::builder().method(method).uri(uri).body(Body::empty())?)
+        };
+        let tagged = |tag: &'static str| async move {
+            let uri = format!("/api/sensors?tag={}", tag);
+            let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+            assert_eq!(status, StatusCode::OK);
+            let sensors: Vec<serde_json::Value> = serde_json::from_slice(&body)?;
+            Ok::<_, anyhow::Error>(sensors.iter().map(|s| s["sensor_id"].as_i64().unwrap_or_default()).collect::<Vec<_>>())
+        };
+        
+        // Tags are lowercased, so HVAC and hvac are the same tag
+        let (status, _, body) = send_request(tag("POST", boiler, "HVAC")?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(response["tags"], serde_json::json!(["hvac"]));
+        send_request(tag("POST", boiler, "critical")?).await?;
+        send_request(tag("POST", boiler, "hvac")?).await?;
+        send_request(tag("POST", fan, "hvac")?).await?;
+        send_request(tag("POST", lamp, "floor-2")?).await?;
+        
+        assert_eq!(Sensor::tags_with(&conn, boiler)?, vec!["critical", "hvac"]);
+        assert_eq!(tagged("hvac").await?, vec![boiler, fan]);
+        assert_eq!(tagged("Critical").await?, vec![boiler]);
+        assert_eq!(tagged("floor-2").await?, vec![lamp]);
+        assert!(tagged("outdoor").await?.is_empty());
+        
+        let (status, _, body) = send_request(tag("DELETE", boiler, "hvac")?).await?;
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(response["tags"], serde_json::json!(["critical"]));
+        assert_eq!(tagged("hvac").await?, vec![fan]);
+        
+        let (status, _, _) = send_request(tag("DELETE", boiler, "hvac")?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _, _) = send_request(tag("POST", 9999, "hvac")?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        let (status, _, _) = send_request(tag("POST", fan, "%20")?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_sensor_history_route() -> Result<()> {
+        let _pool = setup_test_db()?;
+        
+        let request = Request::post("/api/sensors")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"sensor_name": "Boiler", "sensor_type": "temperature"}"#))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let sensor_id = serde_json::from_slice::<serde_json::Value>(&body)?["sensor_id"].as_i64().unwrap();
+        
+        for patch in [r#"{"location": "Basement"}"#, r#"{"unit": "C"}"#] {
+            let request = Request::put(format!("/api/sensors/{}", sensor_id))
+                .header(header::CONTENT_TYPE, "application/json")
+                .body(Body::from(patch))?;
+            let (status, _, _) = send_request(request).await?;
+            assert_eq!(status, StatusCode::OK);
+        }
+        let request = Request::delete(format!("/api/sensors/{}?hard=true", sensor_id)).body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // The trail is still there after the sensor is gone
+        let request = Request::get(format!("/api/sensors/{}/history", sensor_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        let history: Vec<SensorAuditEntry> = serde_json::from_slice(&body)?;
+        let actions: Vec<&str> = history.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, vec!["create", "update", "update", "delete"]);
+        assert_eq!(history[1].new_value.as_ref().unwrap()["location"], "Basement");
+        assert_eq!(history[2].old_value.as_ref().unwrap()["unit"], serde_json::Value::Null);
+        assert_eq!(history[2].new_value.as_ref().unwrap()["unit"], "C");
+        assert!(history[3].new_value.is_none());
+        
+        let request = Request::get("/api/sensors/999999/history").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+}
@@ -1,11 +1,13 @@
 use axum::{
     extract::Path,
     http::StatusCode,
+    response::Response,
     Json,
 };
 use serde_json::{json, Value};
 
-use crate::models::{LoggingSession, LoggingSessionResponse};
+use crate::api::response::ResponseFormat;
+use crate::models::LoggingSession;
 use crate::utils::error::AppError;
 
 /// Start a new logging session
@@ -38,22 +40,94 @@ pub async fn end_logging(
 
 /// Get all sessions for a sensor
 pub async fn get_sessions_by_sensor(
+    format: ResponseFormat,
     Path(sensor_id): Path<i64>,
-) -> Result<Json<Vec<LoggingSessionResponse>>, AppError> {
+) -> Result<Response, AppError> {
     let sessions = LoggingSession::get_by_sensor(sensor_id)?;
-    Ok(Json(sessions))
+    format.render(&sessions)
 }
 
 /// Get active session for a sensor (if any)
 pub async fn get_active_session(
+    format: ResponseFormat,
     Path(sensor_id): Path<i64>,
-) -> Result<Json<Option<LoggingSessionResponse>>, AppError> {
+) -> Result<Response, AppError> {
     let session = LoggingSession::get_active(sensor_id)?;
-    Ok(Json(session))
+    format.render(&session)
 }
 
 /// Get all active sessions
-pub async fn get_all_active_sessions() -> Result<Json<Vec<LoggingSessionResponse>>, AppError> {
+pub async fn get_all_active_sessions(
+    format: ResponseFormat,
+) -> Result<Response, AppError> {
     let sessions = LoggingSession::get_all_active()?;
-    Ok(Json(sessions))
-}
\ No newline at end of file
+    format.render(&sessions)
+}
+
+/// Get the stretches of a session where readings were missing
+pub async fn get_session_gaps(
+    format: ResponseFormat,
+    Path(session_id): Path<i64>,
+) -> Result<Response, AppError> {
+    let gaps = LoggingSession::gaps(session_id)?;
+    format.render(&gaps)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use crate::{
+        models::{session::SessionGap, LoggingSession, Reading},
+        utils::test_utils::{setup_test_db, create_test_sensor, send_request},
+    };
+
+    #[tokio::test]
+    async fn test_session_gaps_with_sparse_readings() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Ten-second samples over 1000..1200, first reading arriving late
+        let session_id = LoggingSession {
+            session_id: None,
+            sensor_id,
+            start_time: Some(1000),
+            end_time: Some(1200),
+            sample_rate: Some(10),
+            notes: None,
+        }
+        .start()?;
+        let readings: Vec<Reading> = [1030, 1040, 1055, 1060, 1100, 1190, 1250]
+            .iter()
+            .map(|&timestamp| Reading {
+                timestamp: Some(timestamp),
+                sensor_id,
+                value: Some(20.0),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let request = Request::get(format!("/api/sessions/{}/gaps", session_id)).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // 15 s between 1040 and 1055 is within twice the sample rate; the reading after the end is ignored
+        let gaps: Vec<SessionGap> = serde_json::from_slice(&body)?;
+        let spans: Vec<(i64, i64, i64)> = gaps
+            .iter()
+            .map(|gap| (gap.start.timestamp(), gap.end.timestamp(), gap.duration_seconds))
+            .collect();
+        assert_eq!(spans, vec![(1000, 1030, 30), (1060, 1100, 40), (1100, 1190, 90)]);
+        
+        let request = Request::get("/api/sessions/9999/gaps").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,135 @@
+use axum::response::Response;
+use serde::{Deserialize, Serialize};
+
+use crate::api::response::ResponseFormat;
+use crate::db::get_connection;
+use crate::models::{Reading, Sensor, SensorQuery};
+use crate::utils::{current_timestamp, error::AppError, stats::classify_value};
+
+/// Sensors classified by how their latest value sits against their thresholds
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CurrentStatus {
+    pub timestamp: i64,
+    pub healthy_count: i64,
+    pub warning_count: i64,
+    pub critical_count: i64,
+    pub sensors_warning: Vec<SensorWarning>,
+    pub sensors_critical: Vec<SensorWarning>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorWarning {
+    pub sensor_id: i64,
+    pub sensor_name: String,
+    pub current_value: f64,
+    pub threshold_min: Option<f64>,
+    pub threshold_max: Option<f64>,
+    pub status: String,  // 'warning' or 'critical'
+}
+
+/// Get the current health of every sensor with a numeric reading
+pub async fn get_current_status(format: ResponseFormat) -> Result<Response, AppError> {
+    let status = current_status()?;
+    format.render(&status)
+}
+
+fn current_status() -> anyhow::Result<CurrentStatus> {
+    let conn = get_connection()?;
+    let sensors = Sensor::get_all(&SensorQuery::default())?;
+    
+    let mut status = CurrentStatus {
+        timestamp: current_timestamp(),
+        healthy_count: 0,
+        warning_count: 0,
+        critical_count: 0,
+        sensors_warning: Vec::new(),
+        sensors_critical: Vec::new(),
+    };
+    
+    for sensor in sensors {
+        // Sensors without a numeric reading have nothing to classify
+        let Some(value) = Reading::get_current_with(&conn, sensor.sensor_id)?.and_then(|r| r.value) else {
+            continue;
+        };
+        
+        let level = classify_value(value, sensor.threshold_min, sensor.threshold_max);
+        let warning = |status: &str| SensorWarning {
+            sensor_id: sensor.sensor_id,
+            sensor_name: sensor.sensor_name.clone(),
+            current_value: value,
+            threshold_min: sensor.threshold_min,
+            threshold_max: sensor.threshold_max,
+            status: status.to_string(),
+        };
+        
+        match level {
+            "critical" => {
+                status.critical_count += 1;
+                status.sensors_critical.push(warning(level));
+            }
+            "warning" => {
+                status.warning_count += 1;
+                status.sensors_warning.push(warning(level));
+            }
+            _ => status.healthy_count += 1,
+        }
+    }
+    
+    Ok(status)
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use super::CurrentStatus;
+    use crate::{
+        models::Reading,
+        utils::test_utils::{setup_test_db, create_test_sensor, send_request},
+    };
+
+    #[tokio::test]
+    async fn test_current_status_reports_breaches() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        // Every test sensor accepts values between 18 and 25
+        let sensor_with = |value: f64| -> Result<i64> {
+            let sensor_id = create_test_sensor(&conn)?;
+            Reading {
+                sensor_id,
+                value: Some(value),
+                ..Default::default()
+            }
+            .create()?;
+            Ok(sensor_id)
+        };
+        let inside = sensor_with(21.0)?;
+        let near_max = sensor_with(24.8)?;
+        let above = sensor_with(30.0)?;
+        let below = sensor_with(10.0)?;
+        // No readings at all, so it is left out
+        create_test_sensor(&conn)?;
+        
+        let request = Request::get("/api/status/current").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let current: CurrentStatus = serde_json::from_slice(&body)?;
+        assert_eq!((current.healthy_count, current.warning_count, current.critical_count), (1, 1, 2));
+        
+        let warning_ids: Vec<i64> = current.sensors_warning.iter().map(|s| s.sensor_id).collect();
+        assert_eq!(warning_ids, vec![near_max]);
+        assert_eq!(current.sensors_warning[0].current_value, 24.8);
+        
+        let critical_ids: Vec<i64> = current.sensors_critical.iter().map(|s| s.sensor_id).collect();
+        assert_eq!(critical_ids, vec![above, below]);
+        assert!(current.sensors_critical.iter().all(|s| s.status == "critical"));
+        assert!(!critical_ids.contains(&inside));
+        
+        Ok(())
+    }
+}
@@ -1,15 +1,58 @@
 use axum::{
+    body::Body,
     extract::Query,
-    http::StatusCode,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
+use futures::StreamExt;
+use rusqlite::{params, types::ValueRef, Connection};
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
-use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
 
-use crate::db::get_connection;
-use crate::utils::error::AppError;
+use crate::config;
+use crate::db::{self, get_connection, migrations};
+use crate::models::{Reading, ReadingExportRange, ReadingResponse, Sensor, SensorQuery};
+use crate::utils::{
+    cache, csv, current_timestamp,
+    error::AppError,
+    ingest,
+    xlsx::{self, Cell, Sheet},
+};
+
+/// Window used to compute the recent insert rate, in seconds
+const RECENT_RATE_WINDOW_SECS: i64 = 60;
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Default tolerance for the dedup task, in seconds
+const DEFAULT_DEDUP_TOLERANCE_SECS: i64 = 60;
+
+/// Number of readings the dedup task scans per transaction
+const DEDUP_BATCH_SIZE: usize = 1000;
+
+/// WAL size above which a manual checkpoint is recommended, in MB
+const WAL_CHECKPOINT_THRESHOLD_MB: f64 = 64.0;
+
+/// Readings fetched per query while streaming an export
+const EXPORT_BATCH_SIZE: usize = 1000;
+
+const XLSX_CONTENT_TYPE: &str = "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet";
+
+/// Tables included in a replication dump, in restore order: (section, table, key)
+const DUMP_TABLES: &[(&str, &str, &str)] = &[
+    ("sensors", "sensors", "sensor_id"),
+    ("sessions", "logging_sessions", "session_id"),
+    ("readings", "readings", "reading_id"),
+];
+
+/// Bytes of dump lines gathered before they are handed to the response
+const DUMP_CHUNK_BYTES: usize = 64 * 1024;
+
+/// Dump chunks buffered ahead of a slow client
+const DUMP_CHANNEL_CAPACITY: usize = 4;
 
 #[derive(Debug, Serialize)]
 pub struct DatabaseHealth {
@@ -21,13 +64,96 @@ pub struct DatabaseHealth {
     pub oldest_reading: Option<i64>,
     pub newest_reading: Option<i64>,
     pub average_insert_rate: Option<f64>,
-    pub peak_insert_rate: Option<f64>,
+    pub peak_readings_per_minute: Option<i64>,  // Most readings timestamped within one clock minute
+    pub peak_readings_per_hour: Option<i64>,    // Most readings timestamped within one clock hour
+    pub recent_insert_rate: f64,
+    pub ingest_rate: f64,        // Readings/second this process inserted over the last minute
+    pub readings_ingested: u64,  // Readings this process inserted since it started
+    pub wal_size_mb: f64,
+    pub checkpoint_recommended: bool,
+    pub integrity_ok: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrity_errors: Option<Vec<String>>,  // Problems reported by PRAGMA quick_check
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SchemaVersionStatus {
+    pub applied_version: i32,
+    pub current_version: i32,
+    pub pending: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StorageReport {
+    pub page_size: i64,
+    pub page_count: i64,
+    pub freelist_count: i64,
+    pub database_size_bytes: i64,
+    pub free_bytes: i64,
+    pub readings_count: i64,
+    pub readings_raw_bytes: i64,      // Estimated size of the values as fixed-width fields
+    pub readings_on_disk_bytes: i64,  // Pages used by the readings table and its indexes
+    pub compression_ratio: Option<f64>,  // Raw over on-disk; None without readings
+    pub tables: Vec<TableStorage>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TableStorage {
+    pub name: String,
+    pub on_disk_bytes: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IndexAdvice {
+    pub query: String,
+    pub table: String,
+    pub sql: String,
+    pub plan: Vec<String>,
+    pub uses_index: bool,
+    pub suggestion: Option<String>,
+}
+
+/// Representative queries checked by the index advisor:
+/// (name, table, SQL, index that would serve it)
+const ADVISOR_QUERIES: &[(&str, &str, &str, &str)] = &[
+    (
+        "readings_by_sensor_and_time",
+        "readings",
+        "SELECT * FROM readings WHERE sensor_id = 1 AND timestamp >= 0 AND timestamp <= 1 ORDER BY timestamp DESC",
+        "CREATE INDEX idx_readings_sensor_time ON readings(sensor_id, timestamp)",
+    ),
+    (
+        "readings_by_time",
+        "readings",
+        "SELECT * FROM readings WHERE timestamp >= 0 AND timestamp <= 1 ORDER BY timestamp DESC",
+        "CREATE INDEX idx_readings_timestamp ON readings(timestamp)",
+    ),
+    (
+        "current_reading",
+        "readings",
+        "SELECT * FROM readings WHERE sensor_id = 1 ORDER BY timestamp DESC LIMIT 1",
+        "CREATE INDEX idx_readings_sensor_time ON readings(sensor_id, timestamp)",
+    ),
+    (
+        "readings_by_change_type",
+        "readings",
+        "SELECT * FROM readings WHERE change_type = 'manual'",
+        "CREATE INDEX idx_readings_change_type ON readings(change_type)",
+    ),
+    (
+        "active_sessions_by_sensor",
+        "logging_sessions",
+        "SELECT * FROM logging_sessions WHERE sensor_id = 1 AND end_time IS NULL",
+        "CREATE INDEX idx_sessions_active ON logging_sessions(sensor_id, end_time)",
+    ),
+];
+
 #[derive(Debug, Deserialize)]
 pub struct MaintenanceRequest {
     pub tasks: Vec<String>,
     pub archive_before: Option<i64>,
+    pub dedup_tolerance_secs: Option<i64>,
+    pub retention_days: Option<i64>,  // Prune older readings; sensors may override
 }
 
 /// Get the health status of the database
@@ -35,25 +161,27 @@ pub async fn get_database_health() -> Result<Json<DatabaseHealth>, AppError> {
     let conn = get_connection()?;
     
     // Get database size
-    let db_path = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "sensor_data.db".to_string());
-    let path = Path::new(&db_path);
+    let config = config::get();
+    let path = Path::new(&config.database_path);
     
     let db_size = match path.metadata() {
         Ok(metadata) => metadata.len() as f64 / (1024.0 * 1024.0), // Convert to MB
         Err(_) => -1.0, // Unable to get file size
     };
     
+    // Get the write-ahead log size (no -wal file means nothing is pending)
+    let wal_size = wal_path(path)
+        .metadata()
+        .map(|metadata| metadata.len() as f64 / (1024.0 * 1024.0)) // Convert to MB
+        .unwrap_or(0.0);
+    
     // Get free disk space (platform-specific)
     let free_space = if cfg!(unix) {
         #[cfg(unix)]
         {
-            use std::fs;
             let parent_dir = path.parent().unwrap_or_else(|| Path::new("/"));
-            match fs::statvfs(parent_dir) {
-                Ok(stat) => {
-                    let free_bytes = stat.blocks_free() * stat.block_size();
-                    free_bytes as f64 / (1024.0 * 1024.0) // Convert to MB
-                }
+            match fs2::available_space(parent_dir) {
+                Ok(free_bytes) => free_bytes as f64 / (1024.0 * 1024.0), // Convert to MB
                 Err(_) => -1.0,
             }
         }
@@ -94,34 +222,33 @@ pub async fn get_database_health() -> Result<Json<DatabaseHealth>, AppError> {
         None
     };
     
-    // Calculate insertion rates (if possible)
-    let (average_insert_rate, peak_insert_rate) = if let (Some(oldest), Some(newest)) = (oldest_reading, newest_reading) {
-        let duration_seconds = (newest - oldest) as f64;
-        
-        if duration_seconds > 0.0 {
-            let avg_rate = readings_count as f64 / duration_seconds;
-            
-            // Calculate peak rate (based on hourly windows)
-            let peak_rate: f64 = conn.query_row(
-                "SELECT MAX(count) FROM (
-                    SELECT COUNT(*) as count 
-                    FROM readings 
-                    GROUP BY timestamp / 3600
-                )",
-                [],
-                |row| row.get(0),
-            ).unwrap_or(0.0);
-            
-            (Some(avg_rate), Some(peak_rate / 3600.0)) // Convert hourly peak to per-second
-        } else {
-            (None, None)
-        }
-    } else {
-        (None, None)
+    // Calculate the average rate over the span of reading timestamps (if possible)
+    let average_insert_rate = match (oldest_reading, newest_reading) {
+        (Some(oldest), Some(newest)) if newest > oldest => Some(readings_count as f64 / (newest - oldest) as f64),
+        _ => None,
     };
     
+    // Find the busiest minute and hour by reading timestamp
+    let peak_readings_per_minute = peak_readings(&conn, 60)?;
+    let peak_readings_per_hour = peak_readings(&conn, 3600)?;
+    
+    // Calculate the live rate over the most recent window
+    let recent_count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM readings WHERE timestamp >= ?",
+        [current_timestamp() - RECENT_RATE_WINDOW_SECS],
+        |row| row.get(0),
+    )?;
+    let recent_insert_rate = recent_count as f64 / RECENT_RATE_WINDOW_SECS as f64;
+    
+    let last_backup: Option<i64> = conn.query_row("SELECT MAX(created_at) FROM backups", [], |row| row.get(0))?;
+    
+    // Check the database structure; quick_check skips the slow index cross-checks
+    let integrity_errors = quick_check(&conn)?;
+    
     // Determine status
-    let status = if readings_count > 0 && newest_reading.is_some() {
+    let status = if integrity_errors.is_some() {
+        "degraded"
+    } else if readings_count > 0 && newest_reading.is_some() {
         "healthy"
     } else {
         "empty"
@@ -131,33 +258,228 @@ pub async fn get_database_health() -> Result<Json<DatabaseHealth>, AppError> {
         status: status.to_string(),
         database_size_mb: db_size,
         free_space_mb: free_space,
-        last_backup: None, // Not implemented
+        last_backup,
         readings_count,
         oldest_reading,
         newest_reading,
         average_insert_rate,
-        peak_insert_rate,
+        peak_readings_per_minute,
+        peak_readings_per_hour,
+        recent_insert_rate,
+        ingest_rate: ingest::rate(),
+        readings_ingested: ingest::total(),
+        wal_size_mb: wal_size,
+        checkpoint_recommended: wal_size > WAL_CHECKPOINT_THRESHOLD_MB,
+        integrity_ok: integrity_errors.is_none(),
+        integrity_errors,
     };
     
     Ok(Json(health))
 }
 
+/// Most readings timestamped within a single `bucket_seconds` window; None without readings
+///
+/// Windows are aligned to the clock, so a burst straddling two minutes
+/// counts towards each separately.
+fn peak_readings(conn: &Connection, bucket_seconds: i64) -> Result<Option<i64>, AppError> {
+    let peak = conn.query_row(
+        "SELECT MAX(count) FROM (
+            SELECT COUNT(*) AS count
+            FROM readings
+            GROUP BY timestamp / ?
+        )",
+        [bucket_seconds],
+        |row| row.get(0),
+    )?;
+    
+    Ok(peak)
+}
+
+/// Run `PRAGMA quick_check`, returning the problems it found, if any
+///
+/// A sound database yields the single row "ok"; otherwise each row
+/// describes one problem.
+fn quick_check(conn: &Connection) -> Result<Option<Vec<String>>, AppError> {
+    let mut stmt = conn.prepare("PRAGMA quick_check")?;
+    let rows = stmt
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    if rows.len() == 1 && rows[0] == "ok" {
+        Ok(None)
+    } else {
+        Ok(Some(rows))
+    }
+}
+
+/// A completed database backup
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupInfo {
+    pub path: String,
+    pub size_bytes: i64,
+    pub created_at: i64,
+}
+
+/// Back up the live database to a timestamped file in the backup directory
+pub async fn create_backup() -> Result<(StatusCode, Json<BackupInfo>), AppError> {
+    let conn = get_connection()?;
+    
+    let dir = PathBuf::from(&config::get().backup_dir);
+    std::fs::create_dir_all(&dir).map_err(|err| {
+        AppError::Internal(anyhow::anyhow!("Failed to create backup directory {}: {}", dir.display(), err))
+    })?;
+    
+    // Millisecond precision keeps back-to-back backups from sharing a file
+    let created_at = current_timestamp();
+    let name = format!("sensor_data-{}.db", chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ"));
+    let path = dir.join(name);
+    
+    db::backup_to(&conn, &path)?;
+    
+    let size_bytes = path.metadata().map(|metadata| metadata.len() as i64).unwrap_or(0);
+    let path = path.to_string_lossy().into_owned();
+    conn.execute(
+        "INSERT INTO backups (path, size_bytes, created_at) VALUES (?, ?, ?)",
+        params![path, size_bytes, created_at],
+    )?;
+    
+    Ok((StatusCode::CREATED, Json(BackupInfo { path, size_bytes, created_at })))
+}
+
+/// Estimate how compactly readings are stored on disk
+///
+/// Archived sensors keep their readings in the readings table, so they are
+/// included in the readings totals rather than reported separately.
+pub async fn get_storage_report() -> Result<Json<StorageReport>, AppError> {
+    let conn = get_connection()?;
+    
+    let page_size: i64 = conn.query_row("PRAGMA page_size", [], |row| row.get(0))?;
+    let page_count: i64 = conn.query_row("PRAGMA page_count", [], |row| row.get(0))?;
+    let freelist_count: i64 = conn.query_row("PRAGMA freelist_count", [], |row| row.get(0))?;
+    
+    // 8 bytes per integer or real column, plus the change_type and annotation text
+    let (readings_count, readings_raw_bytes): (i64, i64) = conn.query_row(
+        "SELECT COUNT(*),
+                COALESCE(SUM(24 + (value IS NOT NULL) * 8 + (state IS NOT NULL) * 8
+                             + COALESCE(LENGTH(change_type), 0) + COALESCE(LENGTH(annotation), 0)), 0)
+         FROM readings",
+        [],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    
+    // Per-table and per-index page usage from the dbstat virtual table
+    let mut stmt = conn.prepare(
+        "SELECT name, SUM(pgsize) FROM dbstat GROUP BY name ORDER BY SUM(pgsize) DESC, name"
+    )?;
+    let tables: Vec<TableStorage> = stmt
+        .query_map([], |row| {
+            Ok(TableStorage {
+                name: row.get(0)?,
+                on_disk_bytes: row.get(1)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+    
+    let readings_on_disk_bytes: i64 = conn.query_row(
+        "SELECT COALESCE(SUM(pgsize), 0) FROM dbstat
+         WHERE name IN (
+             SELECT name FROM sqlite_master WHERE tbl_name = 'readings'
+         )",
+        [],
+        |row| row.get(0),
+    )?;
+    
+    let compression_ratio = (readings_count > 0 && readings_on_disk_bytes > 0)
+        .then(|| readings_raw_bytes as f64 / readings_on_disk_bytes as f64);
+    
+    Ok(Json(StorageReport {
+        page_size,
+        page_count,
+        freelist_count,
+        database_size_bytes: page_size * page_count,
+        free_bytes: page_size * freelist_count,
+        readings_count,
+        readings_raw_bytes,
+        readings_on_disk_bytes,
+        compression_ratio,
+        tables,
+    }))
+}
+
+/// Path of SQLite's write-ahead log for a database file
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut wal = db_path.as_os_str().to_owned();
+    wal.push("-wal");
+    PathBuf::from(wal)
+}
+
+/// Get the applied schema version and the version this build expects
+pub async fn get_schema_version() -> Result<Json<SchemaVersionStatus>, AppError> {
+    let conn = get_connection()?;
+    
+    let applied_version = migrations::get_applied_version(&conn)?;
+    let current_version = migrations::CURRENT_VERSION;
+    
+    let status = SchemaVersionStatus {
+        applied_version,
+        current_version,
+        pending: applied_version != current_version,
+    };
+    
+    Ok(Json(status))
+}
+
+/// Explain the representative read queries and suggest indices for full scans
+pub async fn get_index_advice() -> Result<Json<Vec<IndexAdvice>>, AppError> {
+    let conn = get_connection()?;
+    
+    let mut advice = Vec::new();
+    
+    for &(query, table, sql, index_sql) in ADVISOR_QUERIES {
+        let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+        let plan: Vec<String> = stmt
+            .query_map([], |row| row.get(3))?
+            .collect::<Result<_, _>>()?;
+        
+        // "SCAN table" without "USING ... INDEX" means every row is visited
+        let full_scan = plan.iter().any(|detail| {
+            detail.starts_with(&format!("SCAN {}", table)) && !detail.contains("INDEX")
+        });
+        
+        advice.push(IndexAdvice {
+            query: query.to_string(),
+            table: table.to_string(),
+            sql: sql.to_string(),
+            plan,
+            uses_index: !full_scan,
+            suggestion: full_scan.then(|| index_sql.to_string()),
+        });
+    }
+    
+    Ok(Json(advice))
+}
+
 /// Run database maintenance tasks
 pub async fn run_maintenance(
     Json(payload): Json<MaintenanceRequest>,
 ) -> Result<(StatusCode, Json<Value>), AppError> {
+    if payload.retention_days.is_some_and(|days| days < 1) {
+        return Err(AppError::BadRequest("retention_days must be at least 1".to_string()));
+    }
+    if payload.dedup_tolerance_secs.is_some_and(|secs| secs < 0) {
+        return Err(AppError::BadRequest("dedup_tolerance_secs must not be negative".to_string()));
+    }
+    
     let mut conn = get_connection()?;
-    let now = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_secs() as i64;
     
     let mut tasks_completed = Vec::new();
     let mut archive_count = 0;
+    let mut retention_removed: BTreeMap<i64, usize> = BTreeMap::new();
+    let mut dedup_count = 0;
     let start_time = std::time::Instant::now();
     
     // Begin transaction
-    let mut tx = conn.transaction()?;
+    let tx = conn.transaction()?;
     
     for task in &payload.tasks {
         match task.as_str() {
@@ -173,6 +495,14 @@ pub async fn run_maintenance(
                 // Note: VACUUM cannot be executed within a transaction
                 tasks_completed.push("vacuum");
             },
+            "dedup" => {
+                // Runs in its own batched transactions after this one commits
+                tasks_completed.push("dedup");
+            },
+            "reindex" => {
+                // Runs after this transaction commits, like VACUUM
+                tasks_completed.push("reindex");
+            },
             _ => {
                 // Skip unknown tasks
             }
@@ -189,17 +519,52 @@ pub async fn run_maintenance(
         archive_count = deleted;
     }
     
+    // Prune readings past each sensor's retention; archived sensors stay frozen
+    if let Some(retention_days) = payload.retention_days {
+        let now = current_timestamp();
+        let sensors: Vec<(i64, i64)> = tx
+            .prepare("SELECT sensor_id, COALESCE(retention_days, ?) FROM sensors WHERE archived = 0")?
+            .query_map([retention_days], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        
+        for (sensor_id, days) in sensors {
+            let removed = tx.execute(
+                "DELETE FROM readings WHERE sensor_id = ? AND timestamp < ?",
+                params![sensor_id, now - days * SECONDS_PER_DAY],
+            )?;
+            retention_removed.insert(sensor_id, removed);
+        }
+    }
+    
     // Commit transaction
     tx.commit()?;
     
+    if archive_count > 0 || retention_removed.values().any(|&removed| removed > 0) {
+        cache::clear();
+    }
+    
+    // Collapse duplicate readings in batches so writers aren't blocked for long
+    if payload.tasks.contains(&"dedup".to_string()) {
+        let tolerance = payload.dedup_tolerance_secs.unwrap_or(DEFAULT_DEDUP_TOLERANCE_SECS);
+        dedup_count = Reading::dedup(tolerance, DEDUP_BATCH_SIZE)?;
+    }
+    
+    // Rebuild the readings indexes outside the transaction if requested
+    let mut reindex_duration = None;
+    if payload.tasks.contains(&"reindex".to_string()) {
+        let reindex_start = std::time::Instant::now();
+        conn.execute("REINDEX readings", [])?;
+        reindex_duration = Some(reindex_start.elapsed().as_secs_f64());
+    }
+    
     // Run VACUUM outside the transaction if requested
     if payload.tasks.contains(&"vacuum".to_string()) {
         conn.execute("VACUUM", [])?;
     }
     
     // Calculate new database size
-    let db_path = std::env::var("DATABASE_PATH").unwrap_or_else(|_| "sensor_data.db".to_string());
-    let path = Path::new(&db_path);
+    let config = config::get();
+    let path = Path::new(&config.database_path);
     
     let new_db_size = match path.metadata() {
         Ok(metadata) => metadata.len() as f64 / (1024.0 * 1024.0), // Convert to MB
@@ -212,6 +577,9 @@ pub async fn run_maintenance(
         "success": true,
         "tasks_completed": tasks_completed,
         "archived_readings": archive_count,
+        "retention_removed": retention_removed,
+        "deduplicated_readings": dedup_count,
+        "reindex_duration_seconds": reindex_duration,
         "duration_seconds": elapsed,
         "new_database_size_mb": new_db_size
     });
@@ -219,27 +587,335 @@ pub async fn run_maintenance(
     Ok((StatusCode::OK, Json(response)))
 }
 
-/// Export sensor data
+/// Export sensor readings as JSON, CSV or JSON Lines
+///
+/// Rows come out in (timestamp, reading_id) order, so an interrupted export
+/// can be resumed by passing the last received reading_id as `from_reading_id`.
+/// Resumed CSV exports omit the header row so segments concatenate cleanly.
+/// The body is streamed in batches rather than built up in memory.
 pub async fn export_data(
     Query(query): Query<ExportQuery>,
-) -> Result<(StatusCode, Json<Value>), AppError> {
-    // This is a placeholder for a real implementation
-    // In a complete implementation, we would:
-    // 1. Query the readings based on the parameters
-    // 2. Convert to the requested format
-    // 3. Return a file download response
+) -> Result<Response, AppError> {
+    let sensor_ids = match query.sensor_ids {
+        Some(ref raw) => raw
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| {
+                id.parse::<i64>()
+                    .map_err(|_| AppError::BadRequest(format!("Invalid sensor id '{}'", id)))
+            })
+            .collect::<Result<Vec<_>, _>>()?,
+        None => Vec::new(),
+    };
     
-    let response = json!({
-        "message": "Export functionality not fully implemented in this example",
-        "params": {
-            "sensor_ids": query.sensor_ids,
-            "start_time": query.start_time,
-            "end_time": query.end_time,
-            "format": query.format
+    let resuming = query.from_reading_id.is_some() || query.from_timestamp.is_some();
+    let range = ReadingExportRange {
+        sensor_ids,
+        start_time: query.start_time,
+        end_time: query.end_time,
+        from_reading_id: query.from_reading_id,
+        from_timestamp: query.from_timestamp,
+        after: None,
+        limit: None,
+    };
+    
+    let (format, content_type) = match query.format.as_deref().unwrap_or("json") {
+        "json" => ("json", "application/json"),
+        "csv" => ("csv", "text/csv"),
+        "jsonl" => ("jsonl", "application/x-ndjson"),
+        "xlsx" | "excel" => ("xlsx", XLSX_CONTENT_TYPE),
+        other => {
+            return Err(AppError::BadRequest(format!(
+                "Unsupported export format '{}'; expected json, csv, jsonl or xlsx",
+                other
+            )));
+        }
+    };
+    
+    let filename = format!(
+        "readings_{}_{}.{}",
+        query.start_time.map_or_else(|| "start".to_string(), |time| time.to_string()),
+        query.end_time.map_or_else(|| "end".to_string(), |time| time.to_string()),
+        format
+    );
+    let headers = [
+        (header::CONTENT_TYPE, content_type.to_string()),
+        (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+    ];
+    
+    // A workbook can't be written out row by row, so it is built in memory
+    if format == "xlsx" {
+        let workbook = export_workbook(range, query.limit)?;
+        return Ok((headers, workbook).into_response());
+    }
+    
+    let mut export = ExportStream {
+        range,
+        remaining: query.limit,
+        format,
+        include_header: !resuming,
+        pending: None,
+        exhausted: false,
+        started: false,
+        wrote_rows: false,
+        finished: false,
+    };
+    
+    // Fetch the first batch up front so bad parameters still get an error status
+    export.pending = Some(export.fetch()?);
+    
+    let stream = futures::stream::unfold(export, |mut export| async move {
+        match export.next_chunk() {
+            Ok(Some(chunk)) => Some((Ok(chunk), export)),
+            Ok(None) => None,
+            Err(err) => {
+                tracing::error!("Export failed mid-stream: {:?}", err);
+                export.finished = true;
+                Some((Err(std::io::Error::other(err.to_string())), export))
+            }
+        }
+    })
+    // Compression polls once more after the end, which a bare unfold doesn't allow
+    .fuse();
+    
+    Ok((headers, Body::from_stream(stream)).into_response())
+}
+
+/// Build an xlsx export: the exported sensors on one sheet and the readings on another
+///
+/// Fails with a 400 once the readings outgrow a worksheet, so callers
+/// know to narrow the range or page through it with `limit`.
+fn export_workbook(mut range: ReadingExportRange, limit: Option<usize>) -> Result<Vec<u8>, AppError> {
+    let mut readings = Sheet::new(
+        "Readings",
+        &["reading_id", "timestamp", "sensor_id", "value", "state", "change_type", "annotation"],
+    );
+    
+    let mut remaining = limit;
+    loop {
+        let batch_size = remaining.map_or(EXPORT_BATCH_SIZE, |remaining| remaining.min(EXPORT_BATCH_SIZE));
+        if batch_size == 0 {
+            break;
+        }
+        
+        range.limit = Some(batch_size);
+        let batch = Reading::export(&range)?;
+        for reading in &batch {
+            let row = vec![
+                Cell::Number(reading.reading_id as f64),
+                Cell::DateTime(reading.timestamp),
+                Cell::Number(reading.sensor_id as f64),
+                reading.value.into(),
+                reading.state.into(),
+                reading.change_type.clone().into(),
+                reading.annotation.clone().into(),
+            ];
+            readings
+                .push(row)
+                .map_err(|err| AppError::BadRequest(format!("{}; narrow the export or pass a limit", err)))?;
+        }
+        
+        if batch.len() < batch_size {
+            break;
+        }
+        range.after = batch.last().map(|reading| (reading.timestamp.timestamp(), reading.reading_id));
+        if let Some(remaining) = remaining.as_mut() {
+            *remaining -= batch.len();
+        }
+    }
+    
+    let mut sensors = Sheet::new(
+        "Sensors",
+        &["sensor_id", "sensor_name", "sensor_type", "location", "unit", "threshold_min", "threshold_max", "created_at"],
+    );
+    let query = SensorQuery {
+        include_disabled: Some(true),
+        ..Default::default()
+    };
+    for sensor in Sensor::get_all(&query)? {
+        if !range.sensor_ids.is_empty() && !range.sensor_ids.contains(&sensor.sensor_id) {
+            continue;
+        }
+        sensors.push(vec![
+            Cell::Number(sensor.sensor_id as f64),
+            Cell::Text(sensor.sensor_name),
+            Cell::Text(sensor.sensor_type),
+            sensor.location.into(),
+            sensor.unit.into(),
+            sensor.threshold_min.into(),
+            sensor.threshold_max.into(),
+            Cell::DateTime(sensor.created_at),
+        ])?;
+    }
+    
+    Ok(xlsx::write_workbook(&[sensors, readings])?)
+}
+
+/// Batches of an export, encoded one chunk at a time
+struct ExportStream {
+    range: ReadingExportRange,
+    remaining: Option<usize>,  // Rows left under the caller's limit
+    format: &'static str,
+    include_header: bool,
+    pending: Option<Vec<ReadingResponse>>,
+    exhausted: bool,   // No more rows to fetch
+    started: bool,     // Opening bytes written
+    wrote_rows: bool,  // At least one JSON row written
+    finished: bool,    // Closing bytes written
+}
+
+impl ExportStream {
+    /// Fetch the next batch, continuing after the last row fetched
+    fn fetch(&mut self) -> anyhow::Result<Vec<ReadingResponse>> {
+        let batch_size = self.remaining.map_or(EXPORT_BATCH_SIZE, |remaining| remaining.min(EXPORT_BATCH_SIZE));
+        if batch_size == 0 {
+            self.exhausted = true;
+            return Ok(Vec::new());
+        }
+        
+        self.range.limit = Some(batch_size);
+        let batch = Reading::export(&self.range)?;
+        
+        if batch.len() < batch_size {
+            self.exhausted = true;
+        }
+        // Page on the position itself, so deleting that reading meanwhile doesn't matter
+        if let Some(last) = batch.last() {
+            self.range.after = Some((last.timestamp.timestamp(), last.reading_id));
+        }
+        if let Some(remaining) = self.remaining.as_mut() {
+            *remaining -= batch.len();
+        }
+        
+        Ok(batch)
+    }
+    
+    /// Encode the next batch, ending with the closing bytes; None once done
+    fn next_chunk(&mut self) -> anyhow::Result<Option<Vec<u8>>> {
+        if self.finished {
+            return Ok(None);
+        }
+        
+        let batch = match self.pending.take() {
+            Some(batch) => batch,
+            None if !self.exhausted => self.fetch()?,
+            None => Vec::new(),
+        };
+        
+        let mut chunk = Vec::new();
+        match self.format {
+            "csv" => {
+                if self.include_header || !batch.is_empty() {
+                    csv::export_readings_to_csv(&mut chunk, &batch, self.include_header)?;
+                    self.include_header = false;
+                }
+            },
+            "jsonl" => {
+                for reading in &batch {
+                    serde_json::to_writer(&mut chunk, reading)?;
+                    chunk.push(b'\n');
+                }
+            },
+            _ => {
+                if !self.started {
+                    chunk.push(b'[');
+                }
+                for reading in &batch {
+                    if self.wrote_rows {
+                        chunk.push(b',');
+                    }
+                    serde_json::to_writer(&mut chunk, reading)?;
+                    self.wrote_rows = true;
+                }
+            },
+        }
+        self.started = true;
+        
+        if self.exhausted {
+            if self.format == "json" {
+                chunk.push(b']');
+            }
+            self.finished = true;
+        }
+        
+        Ok(Some(chunk))
+    }
+}
+
+/// Dump sensors, sessions and readings as NDJSON for bootstrapping a replica
+///
+/// Each section starts with a `{"section": ..., "table": ...}` line followed by
+/// one line per row, holding the raw column values. All sections are read in
+/// one read transaction, so the dump is a consistent snapshot; under WAL this
+/// never blocks writers. A blocking task holds the transaction and streams
+/// the rows out in chunks as the client reads them, so memory stays flat
+/// however large the database.
+pub async fn dump_database() -> Result<Response, AppError> {
+    // Check out the connection here so a busy pool is still an error status
+    let conn = get_connection()?;
+    let (sender, mut receiver) = tokio::sync::mpsc::channel::<std::io::Result<Vec<u8>>>(DUMP_CHANNEL_CAPACITY);
+    
+    tokio::task::spawn_blocking(move || {
+        let mut conn = conn;
+        if let Err(err) = write_dump(&mut conn, &sender) {
+            tracing::error!("Dump failed mid-stream: {:?}", err);
+            let _ = sender.blocking_send(Err(std::io::Error::other(err.to_string())));
         }
     });
     
-    Ok((StatusCode::OK, Json(response)))
+    let stream = futures::stream::poll_fn(move |cx| receiver.poll_recv(cx));
+    Ok(([(header::CONTENT_TYPE, "application/x-ndjson")], Body::from_stream(stream)).into_response())
+}
+
+/// Write the dump into `sender` in chunks of about `DUMP_CHUNK_BYTES`
+///
+/// Stops early, without an error, once the client has gone away.
+fn write_dump(conn: &mut Connection, sender: &tokio::sync::mpsc::Sender<std::io::Result<Vec<u8>>>) -> anyhow::Result<()> {
+    let tx = conn.transaction()?;
+    let mut chunk = Vec::with_capacity(DUMP_CHUNK_BYTES);
+    let send = |chunk: &mut Vec<u8>| sender.blocking_send(Ok(std::mem::take(chunk))).is_ok();
+    
+    for &(section, table, key) in DUMP_TABLES {
+        serde_json::to_writer(&mut chunk, &json!({ "section": section, "table": table }))?;
+        chunk.push(b'\n');
+        
+        let mut stmt = tx.prepare(&format!("SELECT * FROM {} ORDER BY {}", table, key))?;
+        let columns: Vec<String> = stmt.column_names().iter().map(|name| name.to_string()).collect();
+        let mut rows = stmt.query([])?;
+        
+        while let Some(row) = rows.next()? {
+            let mut object = serde_json::Map::new();
+            for (i, column) in columns.iter().enumerate() {
+                object.insert(column.clone(), column_to_json(row.get_ref(i)?));
+            }
+            
+            serde_json::to_writer(&mut chunk, &object)?;
+            chunk.push(b'\n');
+            
+            if chunk.len() >= DUMP_CHUNK_BYTES && !send(&mut chunk) {
+                return Ok(());
+            }
+        }
+    }
+    
+    if !chunk.is_empty() {
+        send(&mut chunk);
+    }
+    
+    // Read-only, so ending the transaction without a commit is enough
+    Ok(())
+}
+
+/// Convert a raw SQLite value to JSON without reinterpreting it
+fn column_to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(i) => json!(i),
+        ValueRef::Real(f) => json!(f),
+        ValueRef::Text(text) => json!(String::from_utf8_lossy(text)),
+        ValueRef::Blob(bytes) => json!(bytes),
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -247,5 +923,710 @@ pub struct ExportQuery {
     pub sensor_ids: Option<String>, // Comma-separated list
     pub start_time: Option<i64>,
     pub end_time: Option<i64>,
-    pub format: Option<String>, // 'json', 'csv', 'excel'
-}
\ No newline at end of file
+    pub format: Option<String>, // 'json', 'csv', 'jsonl', 'xlsx'
+    pub from_reading_id: Option<i64>, // Resume after this reading
+    pub from_timestamp: Option<i64>,  // Resume at this timestamp, inclusive
+    pub limit: Option<usize>,
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{header, Request, StatusCode},
+    };
+    use calamine::{open_workbook_from_rs, Data, DataType, Reader, Xlsx};
+    use super::{get_database_health, BackupInfo, IndexAdvice, SchemaVersionStatus, StorageReport};
+    use crate::{
+        config,
+        db::migrations::CURRENT_VERSION,
+        models::{Reading, ReadingExportRange, ReadingQuery, ReadingResponse},
+        utils::{
+            current_timestamp,
+            test_utils::{setup_test_db, create_test_sensor, create_test_session, send_request},
+        },
+    };
+
+    #[tokio::test]
+    async fn test_schema_version_on_fresh_database() -> Result<()> {
+        let _pool = setup_test_db()?;
+        
+        let request = Request::get("/api/system/schema-version").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        
+        assert_eq!(status, StatusCode::OK);
+        
+        let version: SchemaVersionStatus = serde_json::from_slice(&body)?;
+        assert_eq!(version.applied_version, CURRENT_VERSION);
+        assert_eq!(version.current_version, CURRENT_VERSION);
+        assert!(!version.pending);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_recent_insert_rate_reflects_burst() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let now = current_timestamp();
+        let reading = |timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(1.0),
+            ..Default::default()
+        };
+        
+        // A couple of readings from three months ago, then a burst of 30 right now
+        let mut readings = vec![reading(now - 90 * 86400), reading(now - 89 * 86400)];
+        readings.extend((0..30).map(|i| reading(now - i)));
+        Reading::bulk_insert(&readings)?;
+        
+        let health = get_database_health().await?.0;
+        
+        assert_eq!(health.readings_count, 32);
+        assert!((health.recent_insert_rate - 0.5).abs() < 1e-9);
+        assert!(health.average_insert_rate.unwrap_or_default() < 0.001);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_health_reports_peak_and_ingest_rates() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let health = get_database_health().await?.0;
+        assert_eq!((health.peak_readings_per_minute, health.peak_readings_per_hour), (None, None));
+        assert_eq!(health.readings_ingested, 0);
+        
+        // Hour-aligned start: 5 readings in the first minute, 3 in the next,
+        // and 4 spread over a later hour
+        let start = 1_700_000_000 / 3600 * 3600;
+        let mut offsets = vec![0, 10, 20, 30, 59, 60, 90, 119];
+        offsets.extend([7200, 7260, 7320, 7380]);
+        let readings: Vec<Reading> = offsets
+            .iter()
+            .map(|offset| Reading {
+                timestamp: Some(start + offset),
+                sensor_id,
+                value: Some(1.0),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let health = get_database_health().await?.0;
+        assert_eq!(health.peak_readings_per_minute, Some(5));
+        assert_eq!(health.peak_readings_per_hour, Some(8));
+        
+        // Ingestion counts when readings arrive, however old their timestamps
+        assert_eq!(health.readings_ingested, 12);
+        assert!((health.ingest_rate - 12.0 / 60.0).abs() < 1e-9);
+        assert_eq!(health.recent_insert_rate, 0.0);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_dedup_collapses_consecutive_duplicates() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id, timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        
+        Reading::bulk_insert(&[
+            // Retried duplicates collapse into the first reading
+            reading(sensor_id, 1000, 20.0),
+            reading(sensor_id, 1001, 20.0),
+            reading(sensor_id, 1002, 20.0),
+            // A changed value survives, as does a repeat outside the tolerance
+            reading(sensor_id, 1003, 21.0),
+            reading(sensor_id, 1100, 21.0),
+            // Identical values on another sensor are not duplicates of the first sensor
+            reading(other_sensor_id, 1000, 20.0),
+            reading(other_sensor_id, 1004, 20.0),
+        ])?;
+        
+        let request = Request::post("/api/system/maintenance")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tasks": ["dedup"], "dedup_tolerance_secs": 5}"#))?;
+        let (status, _, body) = send_request(request).await?;
+        
+        assert_eq!(status, StatusCode::OK);
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(response["deduplicated_readings"], 3);
+        
+        let remaining = |sensor_id| -> Result<Vec<(i64, f64)>> {
+            let query = ReadingQuery {
+                sensor_id: Some(sensor_id),
+                ..Default::default()
+            };
+            let mut readings: Vec<(i64, f64)> = Reading::get(&query)?
+                .iter()
+                .map(|r| (r.timestamp.timestamp(), r.value.unwrap_or_default()))
+                .collect();
+            readings.sort_by_key(|(timestamp, _)| *timestamp);
+            Ok(readings)
+        };
+        
+        assert_eq!(remaining(sensor_id)?, vec![(1000, 20.0), (1003, 21.0), (1100, 21.0)]);
+        assert_eq!(remaining(other_sensor_id)?, vec![(1000, 20.0)]);
+        
+        // A bad tolerance is rejected before any other task touches the data
+        let request = Request::post("/api/system/maintenance")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tasks": ["dedup"], "dedup_tolerance_secs": -1, "archive_before": 2000}"#))?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(remaining(sensor_id)?.len(), 3);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_health_reports_wal_size() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        // Point the health check at the test database file
+        let db_path = conn.path().unwrap_or_default().to_string();
+        config::set_test_config(config::Config {
+            database_path: db_path,
+            ..config::Config::default()
+        });
+        
+        let sensor_id = create_test_sensor(&conn)?;
+        let readings: Vec<Reading> = (0..100)
+            .map(|i| Reading {
+                timestamp: Some(1000 + i),
+                sensor_id,
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let health = get_database_health().await?.0;
+        
+        assert!(health.wal_size_mb > 0.0);
+        assert!(!health.checkpoint_recommended);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_health_reports_clean_integrity_check() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        Reading {
+            sensor_id,
+            value: Some(21.0),
+            ..Default::default()
+        }
+        .create()?;
+        
+        let health = get_database_health().await?.0;
+        
+        assert!(health.integrity_ok);
+        assert_eq!(health.integrity_errors, None);
+        assert_eq!(health.status, "healthy");
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_export_resumes_from_reading_id() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensors = [create_test_sensor(&conn)?, create_test_sensor(&conn)?, create_test_sensor(&conn)?];
+        let sensor_ids = sensors.map(|id| id.to_string()).join(",");
+        
+        // Several sensors' readings share a timestamp so the reading_id tie-break matters
+        let readings: Vec<Reading> = [100, 100, 100, 200, 200, 300, 50]
+            .iter()
+            .enumerate()
+            .map(|(i, &timestamp)| Reading {
+                timestamp: Some(timestamp),
+                sensor_id: sensors[i % sensors.len()],
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let export = |query: String| async move {
+            let request = Request::get(format!("/api/system/export?{}", query)).body(Body::empty())?;
+            let (status, _, body) = send_request(request).await?;
+            assert_eq!(status, StatusCode::OK);
+            anyhow::Ok(String::from_utf8(body.to_vec())?)
+        };
+        
+        for format in ["jsonl", "csv"] {
+            let full = export(format!("format={}&sensor_ids={}", format, sensor_ids)).await?;
+            
+            // Pretend the connection dropped after three readings
+            let first = export(format!("format={}&sensor_ids={}&limit=3", format, sensor_ids)).await?;
+            let last_id: i64 = if format == "jsonl" {
+                let last_line = first.lines().last().unwrap_or_default();
+                serde_json::from_str::<serde_json::Value>(last_line)?["reading_id"].as_i64().unwrap_or_default()
+            } else {
+                let last_line = first.lines().last().unwrap_or_default();
+                last_line.split(',').next().unwrap_or_default().parse()?
+            };
+            
+            let rest = export(format!("format={}&sensor_ids={}&from_reading_id={}", format, sensor_ids, last_id)).await?;
+            
+            assert_eq!(full.lines().count(), if format == "csv" { 8 } else { 7 });
+            assert_eq!(format!("{}{}", first, rest), full);
+        }
+        
+        // Unknown resume positions are rejected rather than silently restarting
+        let request = Request::get("/api/system/export?format=csv&from_reading_id=999999").body(Body::empty())?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_export_pages_past_a_deleted_reading() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let readings: Vec<Reading> = (0..1500)
+            .map(|i| Reading { timestamp: Some(1000 + i), sensor_id, value: Some(i as f64), ..Default::default() })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let mut export = super::ExportStream {
+            range: ReadingExportRange {
+                sensor_ids: vec![sensor_id],
+                start_time: None,
+                end_time: None,
+                from_reading_id: None,
+                from_timestamp: None,
+                after: None,
+                limit: None,
+            },
+            remaining: None,
+            format: "jsonl",
+            include_header: false,
+            pending: None,
+            exhausted: false,
+            started: false,
+            wrote_rows: false,
+            finished: false,
+        };
+        let first = export.fetch()?;
+        assert_eq!(first.len(), super::EXPORT_BATCH_SIZE);
+        
+        // The reading the next page continues from disappears in between
+        let last = first.last().map(|reading| reading.reading_id).unwrap_or_default();
+        conn.execute("DELETE FROM readings WHERE reading_id = ?", [last])?;
+        
+        let rest = export.fetch()?;
+        let timestamps: Vec<i64> = rest.iter().map(|reading| reading.timestamp.timestamp()).collect();
+        assert_eq!(timestamps, (2000..2500).collect::<Vec<i64>>());
+        assert!(export.exhausted);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_index_advice() -> Result<()> {
+        let _pool = setup_test_db()?;
+        
+        let request = Request::get("/api/system/index-advice").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let advice: Vec<IndexAdvice> = serde_json::from_slice(&body)?;
+        assert!(advice.iter().all(|a| !a.plan.is_empty()));
+        
+        // The time-series queries are served by the schema's indices
+        let by_sensor = advice
+            .iter()
+            .find(|a| a.query == "readings_by_sensor_and_time")
+            .expect("sensor/time query checked");
+        assert_eq!(by_sensor.table, "readings");
+        assert!(by_sensor.uses_index, "plan: {:?}", by_sensor.plan);
+        assert!(by_sensor.plan.iter().any(|detail| detail.contains("idx_readings_sensor_time")));
+        assert!(by_sensor.suggestion.is_none());
+        
+        // change_type has no index, so the advisor proposes one
+        let by_change_type = advice
+            .iter()
+            .find(|a| a.query == "readings_by_change_type")
+            .expect("change_type query checked");
+        assert!(!by_change_type.uses_index);
+        assert!(by_change_type.suggestion.as_deref().unwrap_or_default().contains("change_type"));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_retention_prunes_only_old_readings() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let short_lived = create_test_sensor(&conn)?;
+        conn.execute("UPDATE sensors SET retention_days = 2 WHERE sensor_id = ?", [short_lived])?;
+        
+        let now = current_timestamp();
+        let day = 86400;
+        let reading = |sensor_id, age_days: i64| Reading {
+            timestamp: Some(now - age_days * day - 60),
+            sensor_id,
+            value: Some(1.0),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(sensor_id, 40),
+            reading(sensor_id, 31),
+            reading(sensor_id, 5),
+            reading(sensor_id, 0),
+            // The override keeps only the last two days for this sensor
+            reading(short_lived, 5),
+            reading(short_lived, 1),
+        ])?;
+        
+        let request = Request::post("/api/system/maintenance")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tasks": ["vacuum"], "retention_days": 30}"#))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(response["retention_removed"][sensor_id.to_string()], 2);
+        assert_eq!(response["retention_removed"][short_lived.to_string()], 1);
+        assert_eq!(response["tasks_completed"], serde_json::json!(["vacuum"]));
+        
+        let remaining = |sensor_id| -> Result<Vec<i64>> {
+            let query = ReadingQuery {
+                sensor_id: Some(sensor_id),
+                order: Some("asc".to_string()),
+                ..Default::default()
+            };
+            Ok(Reading::get(&query)?.iter().map(|r| (now - r.timestamp.timestamp()) / day).collect())
+        };
+        assert_eq!(remaining(sensor_id)?, vec![5, 0]);
+        assert_eq!(remaining(short_lived)?, vec![1]);
+        
+        let request = Request::post("/api/system/maintenance")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tasks": [], "retention_days": 0}"#))?;
+        let (status, _, _) = send_request(request).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_reindex_task_reports_duration() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let readings: Vec<Reading> = (0..50)
+            .map(|i| Reading {
+                timestamp: Some(1000 + i),
+                sensor_id,
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let request = Request::post("/api/system/maintenance")
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(Body::from(r#"{"tasks": ["analyze", "reindex"]}"#))?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let response: serde_json::Value = serde_json::from_slice(&body)?;
+        assert_eq!(response["tasks_completed"], serde_json::json!(["analyze", "reindex"]));
+        assert!(response["reindex_duration_seconds"].as_f64().is_some_and(|secs| secs >= 0.0));
+        
+        // The rebuilt indexes still answer queries
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            ..Default::default()
+        };
+        assert_eq!(Reading::get(&query)?.len(), 50);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_dump_contains_every_entity_type() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let session_id = create_test_session(&conn, sensor_id, true)?;
+        Reading::bulk_insert(&[
+            Reading { timestamp: Some(100), sensor_id, value: Some(20.5), ..Default::default() },
+            Reading { timestamp: Some(200), sensor_id, value: Some(21.0), ..Default::default() },
+        ])?;
+        
+        let request = Request::get("/api/system/dump").body(Body::empty())?;
+        let (status, headers, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/x-ndjson");
+        
+        let lines: Vec<serde_json::Value> = std::str::from_utf8(&body)?
+            .lines()
+            .map(serde_json::from_str)
+            .collect::<Result<_, _>>()?;
+        
+        // Group rows under the section header that precedes them
+        let mut sections: Vec<(String, Vec<serde_json::Value>)> = Vec::new();
+        for line in lines {
+            match line["section"].as_str() {
+                Some(section) => sections.push((section.to_string(), Vec::new())),
+                None => sections.last_mut().expect("row before any section").1.push(line),
+            }
+        }
+        
+        let names: Vec<&str> = sections.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["sensors", "sessions", "readings"]);
+        
+        assert_eq!(sections[0].1.len(), 1);
+        assert_eq!(sections[0].1[0]["sensor_id"], sensor_id);
+        assert_eq!(sections[1].1.len(), 1);
+        assert_eq!(sections[1].1[0]["session_id"], session_id);
+        
+        // Rows carry raw column values, e.g. integer timestamps
+        let readings: Vec<(i64, f64)> = sections[2]
+            .1
+            .iter()
+            .map(|row| (row["timestamp"].as_i64().unwrap(), row["value"].as_f64().unwrap()))
+            .collect();
+        assert_eq!(readings, vec![(100, 20.5), (200, 21.0)]);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_dump_streams_across_chunks() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Several chunks' worth of readings
+        let readings: Vec<Reading> = (0..3000)
+            .map(|i| Reading { timestamp: Some(1000 + i), sensor_id, value: Some(i as f64), ..Default::default() })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let request = Request::get("/api/system/dump").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert!(body.len() > 2 * super::DUMP_CHUNK_BYTES);
+        
+        // Every line is whole, and every reading arrives once, in order
+        let timestamps: Vec<i64> = std::str::from_utf8(&body)?
+            .lines()
+            .map(serde_json::from_str::<serde_json::Value>)
+            .collect::<Result<Vec<_>, _>>()?
+            .iter()
+            .filter_map(|line| line.get("reading_id").and(line["timestamp"].as_i64()))
+            .collect();
+        assert_eq!(timestamps, (1000..4000).collect::<Vec<i64>>());
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_storage_report_on_populated_database() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let readings: Vec<Reading> = (0..500)
+            .map(|i| Reading {
+                timestamp: Some(1000 + i),
+                sensor_id,
+                value: Some(i as f64 / 10.0),
+                change_type: Some("periodic".to_string()),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let request = Request::get("/api/system/storage-report").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let report: StorageReport = serde_json::from_slice(&body)?;
+        assert_eq!(report.readings_count, 500);
+        assert_eq!(report.database_size_bytes, report.page_size * report.page_count);
+        assert!(report.database_size_bytes > 0);
+        assert_eq!(report.readings_raw_bytes, 500 * (24 + 8 + 8));
+        assert!(report.readings_on_disk_bytes > 0);
+        assert!(report.readings_on_disk_bytes <= report.database_size_bytes);
+        assert!(report.compression_ratio.is_some_and(|ratio| ratio > 0.0));
+        assert!(report.tables.iter().any(|table| table.name == "readings" && table.on_disk_bytes > 0));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_export_downloads_csv_and_json() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // More than one batch, so the body is streamed in several chunks
+        let readings: Vec<Reading> = (0..2500)
+            .map(|i| Reading {
+                timestamp: Some(10_000 + i),
+                sensor_id,
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let uri = format!("/api/system/export?format=csv&sensor_ids={}&start_time=10000&end_time=12499", sensor_id);
+        let (status, headers, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], "text/csv");
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"readings_10000_12499.csv\""
+        );
+        
+        let csv = String::from_utf8(body.to_vec())?;
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2501);
+        assert!(lines[0].starts_with("reading_id,timestamp"));
+        assert_eq!(lines.iter().filter(|line| line.starts_with("reading_id")).count(), 1);
+        
+        let uri = format!("/api/system/export?format=json&sensor_ids={}&limit=1500", sensor_id);
+        let (status, headers, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(headers[header::CONTENT_TYPE], "application/json");
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"readings_start_end.json\""
+        );
+        
+        let exported: Vec<ReadingResponse> = serde_json::from_slice(&body)?;
+        assert_eq!(exported.len(), 1500);
+        let timestamps: Vec<i64> = exported.iter().map(|r| r.timestamp.timestamp()).collect();
+        assert_eq!(timestamps, (10_000..11_500).collect::<Vec<_>>());
+        
+        // An empty range is still a valid JSON document
+        let uri = format!("/api/system/export?format=json&sensor_ids={}&start_time=0&end_time=1", sensor_id);
+        let (_, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(&body[..], b"[]");
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_export_as_xlsx_workbook() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_id = create_test_sensor(&conn)?;
+        Reading::bulk_insert(&[
+            Reading {
+                timestamp: Some(1_704_110_400),  // 2024-01-01 12:00 UTC
+                sensor_id,
+                value: Some(21.5),
+                annotation: Some("door <open>".to_string()),
+                ..Default::default()
+            },
+            Reading {
+                timestamp: Some(1_704_110_460),
+                sensor_id: other_id,
+                value: Some(99.0),
+                ..Default::default()
+            },
+        ])?;
+        
+        let uri = format!("/api/system/export?format=xlsx&sensor_ids={}", sensor_id);
+        let (status, headers, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            headers[header::CONTENT_TYPE],
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet"
+        );
+        assert_eq!(
+            headers[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"readings_start_end.xlsx\""
+        );
+        
+        // Read it back with an independent parser
+        let mut workbook: Xlsx<_> = open_workbook_from_rs(std::io::Cursor::new(body.to_vec()))?;
+        assert_eq!(workbook.sheet_names(), vec!["Sensors", "Readings"]);
+        
+        let sensors = workbook.worksheet_range("Sensors")?;
+        assert_eq!(sensors.get_value((1, 1)), Some(&Data::String("Test Sensor".to_string())));
+        assert_eq!(sensors.height(), 2);
+        
+        // Only the selected sensor's reading, with its timestamp as a date
+        let readings = workbook.worksheet_range("Readings")?;
+        let noon = chrono::NaiveDate::from_ymd_opt(2024, 1, 1).and_then(|date| date.and_hms_opt(12, 0, 0));
+        assert_eq!(readings.get_value((1, 1)).and_then(|cell| cell.as_datetime()), noon);
+        assert_eq!(readings.get_value((1, 3)), Some(&Data::Float(21.5)));
+        assert_eq!(readings.get_value((1, 6)), Some(&Data::String("door <open>".to_string())));
+        assert_eq!(readings.height(), 2);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_backup_copies_live_database() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let readings: Vec<Reading> = (0..250)
+            .map(|i| Reading {
+                timestamp: Some(1000 + i),
+                sensor_id,
+                value: Some(i as f64),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let backup_dir = tempfile::TempDir::new()?;
+        config::set_test_config(config::Config {
+            backup_dir: backup_dir.path().join("nested").to_string_lossy().into_owned(),
+            ..config::Config::default()
+        });
+        
+        let health = get_database_health().await?.0;
+        assert_eq!(health.last_backup, None);
+        
+        let request = Request::post("/api/system/backup").body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::CREATED);
+        let backup: BackupInfo = serde_json::from_slice(&body)?;
+        assert!(backup.size_bytes > 0);
+        
+        // The copy is a standalone database holding everything written so far
+        let copy = rusqlite::Connection::open(&backup.path)?;
+        let count: i64 = copy.query_row("SELECT COUNT(*) FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?;
+        assert_eq!(count, 250);
+        let integrity: String = copy.query_row("PRAGMA integrity_check", [], |row| row.get(0))?;
+        assert_eq!(integrity, "ok");
+        
+        let health = get_database_health().await?.0;
+        assert_eq!(health.last_backup, Some(backup.created_at));
+        
+        Ok(())
+    }
+}
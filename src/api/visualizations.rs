@@ -0,0 +1,330 @@
+use axum::{
+    extract::Query,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::api::response::ResponseFormat;
+use crate::models::{Reading, Sensor};
+use crate::utils::{cache, error::AppError, stats};
+
+#[derive(Debug, Deserialize)]
+pub struct TimeSeriesQuery {
+    pub sensor_ids: Option<String>,  // Comma-separated list
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub interval: Option<String>,    // 'minute', 'hour' (default) or 'day'
+    pub moving_average_window: Option<usize>,  // Buckets per trailing average
+    pub resample: Option<bool>,      // Also interpolate a value at each label
+    pub max_gap: Option<i64>,        // Seconds between readings beyond which resampling gives null
+}
+
+/// One sensor's averages, aligned with the labels of its `TimeSeriesData`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeSeriesDataset {
+    pub sensor_id: i64,
+    pub sensor_name: String,
+    pub unit: String,
+    pub data: Vec<Option<f64>>,  // None for buckets without readings
+    pub moving_average: Option<Vec<Option<f64>>>,  // Only with moving_average_window
+    pub interpolated: Option<Vec<Option<f64>>>,  // Only with resample; the value at each label
+}
+
+/// Chart-ready series sharing a single time axis
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TimeSeriesData {
+    pub labels: Vec<String>,  // RFC 3339 start of each bucket
+    pub datasets: Vec<TimeSeriesDataset>,
+}
+
+/// Get per-sensor averages bucketed by minute, hour or day
+pub async fn get_time_series(
+    format: ResponseFormat,
+    Query(query): Query<TimeSeriesQuery>,
+) -> Result<Response, AppError> {
+    let raw_ids = query.sensor_ids
+        .ok_or_else(|| AppError::BadRequest("sensor_ids is required".to_string()))?;
+    let sensor_ids = raw_ids
+        .split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| {
+            id.parse::<i64>()
+                .map_err(|_| AppError::BadRequest(format!("Invalid sensor id '{}'", id)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    
+    let start_time = query.start_time
+        .ok_or_else(|| AppError::BadRequest("start_time is required".to_string()))?;
+    
+    let end_time = query.end_time
+        .ok_or_else(|| AppError::BadRequest("end_time is required".to_string()))?;
+    
+    let interval = query.interval.as_deref().unwrap_or("hour");
+    let bucket_seconds = stats::parse_interval(interval).ok_or_else(|| {
+        AppError::BadRequest(format!("Unknown interval '{}'; expected minute, hour or day", interval))
+    })?;
+    
+    let window = query.moving_average_window;
+    if window == Some(0) {
+        return Err(AppError::BadRequest("moving_average_window must be at least 1".to_string()));
+    }
+    
+    // Unset max_gap interpolates across gaps of any length
+    let resample = query.resample.unwrap_or(false);
+    if query.max_gap.is_some() && !resample {
+        return Err(AppError::BadRequest("max_gap requires resample=true".to_string()));
+    }
+    if query.max_gap.is_some_and(|gap| gap <= 0) {
+        return Err(AppError::BadRequest("max_gap must be a positive number of seconds".to_string()));
+    }
+    let max_gap = resample.then_some(query.max_gap);
+    
+    let key = format!(
+        "time-series:{}:{}:{}:{}:{:?}:{:?}",
+        raw_ids, bucket_seconds, start_time, end_time, window, max_gap
+    );
+    let series = cache::cached(&key, None, || {
+        time_series(&sensor_ids, bucket_seconds, start_time, end_time, window, max_gap)
+    })?;
+    format.render(&*series)
+}
+
+/// Build the shared bucket axis and fill each sensor's dataset along it
+///
+/// `resample` is None unless requested, and otherwise holds the `max_gap`.
+fn time_series(
+    sensor_ids: &[i64],
+    bucket_seconds: i64,
+    start_time: i64,
+    end_time: i64,
+    moving_average_window: Option<usize>,
+    resample: Option<Option<i64>>,
+) -> anyhow::Result<TimeSeriesData> {
+    let averages = Reading::bucket_averages(sensor_ids, bucket_seconds, start_time, end_time)?;
+    
+    let first_bucket = start_time / bucket_seconds;
+    let buckets: Vec<i64> = (first_bucket..=end_time / bucket_seconds).collect();
+    let boundaries: Vec<i64> = buckets.iter().map(|&bucket| bucket * bucket_seconds).collect();
+    
+    let labels = boundaries
+        .iter()
+        .map(|&boundary| {
+            DateTime::<Utc>::from_timestamp(boundary, 0)
+                .map(|start| start.to_rfc3339())
+                .unwrap_or_default()
+        })
+        .collect();
+    
+    let mut by_sensor: HashMap<i64, Vec<Option<f64>>> = HashMap::new();
+    for average in averages {
+        let data = by_sensor
+            .entry(average.sensor_id)
+            .or_insert_with(|| vec![None; buckets.len()]);
+        data[(average.bucket - first_bucket) as usize] = Some(average.average);
+    }
+    
+    let mut datasets = Vec::with_capacity(sensor_ids.len());
+    for &sensor_id in sensor_ids {
+        // Unknown sensors are a 404 rather than an empty dataset
+        let sensor = Sensor::get_by_id(sensor_id)?;
+        let data = by_sensor
+            .remove(&sensor_id)
+            .unwrap_or_else(|| vec![None; buckets.len()]);
+        datasets.push(TimeSeriesDataset {
+            sensor_id,
+            sensor_name: sensor.sensor_name,
+            unit: sensor.unit.unwrap_or_default(),
+            moving_average: moving_average_window.map(|window| stats::moving_average(&data, window)),
+            interpolated: resample
+                .map(|max_gap| Reading::interpolate(sensor_id, &boundaries, max_gap))
+                .transpose()?,
+            data,
+        });
+    }
+    
+    Ok(TimeSeriesData { labels, datasets })
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use super::TimeSeriesData;
+    use crate::{
+        models::Reading,
+        utils::test_utils::{setup_test_db, create_test_sensor, send_request},
+    };
+
+    #[tokio::test]
+    async fn test_time_series_buckets_by_hour() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let first = create_test_sensor(&conn)?;
+        let second = create_test_sensor(&conn)?;
+        
+        // Hour-aligned start, with readings spread over the first three hours
+        let start = 1_700_000_000 / 3600 * 3600;
+        let reading = |sensor_id, offset, value| Reading {
+            timestamp: Some(start + offset),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(first, 60, 10.0),
+            reading(first, 1800, 20.0),
+            reading(first, 3600 + 60, 30.0),
+            reading(first, 2 * 3600 + 60, 40.0),
+            reading(first, 2 * 3600 + 120, 50.0),
+            reading(second, 3600 + 300, 5.0),
+            // State-only readings don't count towards the average
+            Reading { timestamp: Some(start + 90), sensor_id: second, state: Some(1), ..Default::default() },
+        ])?;
+        
+        let uri = format!(
+            "/api/visualizations/time-series?sensor_ids={},{}&start_time={}&end_time={}&interval=hour",
+            first,
+            second,
+            start,
+            start + 3 * 3600 + 59
+        );
+        let request = Request::get(uri).body(Body::empty())?;
+        let (status, _, body) = send_request(request).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        let series: TimeSeriesData = serde_json::from_slice(&body)?;
+        assert_eq!(series.labels.len(), 4);
+        assert_eq!(series.datasets.len(), 2);
+        
+        assert_eq!(series.datasets[0].sensor_id, first);
+        assert_eq!(series.datasets[0].data, vec![Some(15.0), Some(30.0), Some(45.0), None]);
+        assert_eq!(series.datasets[1].sensor_id, second);
+        assert_eq!(series.datasets[1].data, vec![None, Some(5.0), None, None]);
+        assert!(series.datasets.iter().all(|dataset| dataset.data.len() == series.labels.len()));
+        assert!(series.datasets.iter().all(|dataset| dataset.moving_average.is_none()));
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_time_series_rejects_bad_input() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let uri = format!(
+            "/api/visualizations/time-series?sensor_ids={}&start_time=0&end_time=3600&interval=fortnight",
+            sensor_id
+        );
+        let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        let uri = "/api/visualizations/time-series?sensor_ids=9999&start_time=0&end_time=3600";
+        let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_time_series_moving_average() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // One reading per hour: 10, 20, 30, nothing, 50
+        let start = 1_700_000_000 / 3600 * 3600;
+        let reading = |hour: i64, value| Reading {
+            timestamp: Some(start + hour * 3600 + 60),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(0, 10.0), reading(1, 20.0), reading(2, 30.0), reading(4, 50.0)])?;
+        
+        let uri = format!(
+            "/api/visualizations/time-series?sensor_ids={}&start_time={}&end_time={}&moving_average_window=2",
+            sensor_id,
+            start,
+            start + 4 * 3600 + 599
+        );
+        let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::OK);
+        
+        // The first bucket and the gap average over the points they have
+        let series: TimeSeriesData = serde_json::from_slice(&body)?;
+        assert_eq!(
+            series.datasets[0].moving_average,
+            Some(vec![Some(10.0), Some(15.0), Some(25.0), Some(30.0), Some(50.0)])
+        );
+        
+        let uri = format!(
+            "/api/visualizations/time-series?sensor_ids={}&start_time=0&end_time=3600&moving_average_window=0",
+            sensor_id
+        );
+        let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+    
+    #[tokio::test]
+    async fn test_time_series_resample_interpolates() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Readings at hours 0 and 2, then an outage until hour 10
+        let start = 1_700_000_000 / 3600 * 3600;
+        let reading = |hour: i64, value| Reading {
+            timestamp: Some(start + hour * 3600),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[reading(0, 10.0), reading(2, 30.0), reading(10, 100.0)])?;
+        
+        let resampled = |max_gap: &str| {
+            let uri = format!(
+                "/api/visualizations/time-series?sensor_ids={}&start_time={}&end_time={}&resample=true{}",
+                sensor_id,
+                start,
+                start + 11 * 3600,
+                max_gap
+            );
+            async move {
+                let (status, _, body) = send_request(Request::get(uri).body(Body::empty())?).await?;
+                assert_eq!(status, StatusCode::OK);
+                let series: TimeSeriesData = serde_json::from_slice(&body)?;
+                anyhow::Ok(series.datasets[0].interpolated.clone().expect("resampled series"))
+            }
+        };
+        
+        // The midpoint between hours 0 and 2 is interpolated; after the last reading is null
+        let values = resampled("&max_gap=10800").await?;
+        let mut expected = vec![Some(10.0), Some(20.0), Some(30.0)];
+        expected.extend([None; 7]);
+        expected.extend([Some(100.0), None]);
+        assert_eq!(values, expected);
+        
+        // Without max_gap the outage is bridged too
+        let values = resampled("").await?;
+        assert_eq!(values[3], Some(38.75));
+        assert_eq!(values[10], Some(100.0));
+        
+        let uri = format!(
+            "/api/visualizations/time-series?sensor_ids={}&start_time=0&end_time=3600&max_gap=60",
+            sensor_id
+        );
+        let (status, _, _) = send_request(Request::get(uri).body(Body::empty())?).await?;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        
+        Ok(())
+    }
+}
@@ -0,0 +1,410 @@
+use anyhow::{anyhow, Result};
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+static CONFIG: OnceCell<Arc<Config>> = OnceCell::new();
+
+#[cfg(test)]
+thread_local! {
+    /// Per-thread override so tests can run with their own settings
+    static TEST_CONFIG: std::cell::RefCell<Option<Arc<Config>>> = const { std::cell::RefCell::new(None) };
+}
+
+/// Application configuration, parsed once at startup
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Path of the SQLite database file (`DATABASE_PATH`)
+    pub database_path: String,
+    /// Port the HTTP server listens on (`PORT`)
+    pub port: u16,
+    /// Maximum number of pooled database connections (`DB_POOL_SIZE`)
+    pub pool_max_size: u32,
+    /// Milliseconds a connection waits on a locked database before failing (`DB_BUSY_TIMEOUT_MS`)
+    pub db_busy_timeout_ms: u64,
+    /// Row limit applied to readings queries that don't pass one (`READINGS_DEFAULT_LIMIT`)
+    pub readings_default_limit: usize,
+    /// Upper bound on any requested readings limit; larger ones are clamped (`READINGS_MAX_LIMIT`)
+    pub readings_max_limit: usize,
+    /// Sensor types accepted on create/update, replacing the built-in set (`SENSOR_TYPE_ALLOWLIST`)
+    pub sensor_type_allowlist: Option<Vec<String>>,
+    /// Sensor types accepted on top of the allowed set (`EXTRA_SENSOR_TYPES`)
+    pub extra_sensor_types: Vec<String>,
+    /// URL that receives threshold-breach events; None disables the webhook (`WEBHOOK_URL`)
+    pub webhook_url: Option<String>,
+    /// Create unknown sensors named by incoming readings (`AUTO_PROVISION_SENSORS`)
+    pub auto_provision_sensors: bool,
+    /// Seconds aggregation results stay cached; 0 disables the cache (`QUERY_CACHE_TTL_SECS`)
+    pub query_cache_ttl_secs: u64,
+    /// Most aggregation results the cache holds before evicting the oldest (`QUERY_CACHE_MAX_ENTRIES`)
+    pub query_cache_max_entries: usize,
+    /// Seconds an `Idempotency-Key` is remembered for reading submissions (`IDEMPOTENCY_KEY_TTL_SECS`)
+    pub idempotency_key_ttl_secs: u64,
+    /// Reject readings timestamped before their sensor's active session began (`REJECT_PRE_SESSION_READINGS`)
+    pub reject_pre_session_readings: bool,
+    /// Reject readings outside their sensor's valid range instead of flagging them (`REJECT_OUT_OF_RANGE_READINGS`)
+    pub reject_out_of_range_readings: bool,
+    /// Serve reads only: writes are refused and the database is opened read-only (`READ_ONLY`)
+    pub read_only: bool,
+    /// Directory that database backups are written to (`BACKUP_DIR`)
+    pub backup_dir: String,
+    /// Origins browsers may call the API from, or `*` for any; None allows none (`CORS_ALLOWED_ORIGINS`)
+    pub cors_allowed_origins: Option<Vec<String>>,
+    /// Largest request body accepted by the bulk and import routes, in bytes (`MAX_BULK_BODY_BYTES`)
+    pub max_bulk_body_bytes: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            database_path: "sensor_data.db".to_string(),
+            port: 3000,
+            pool_max_size: 10,
+            db_busy_timeout_ms: 5000,
+            readings_default_limit: 1000,
+            readings_max_limit: 10_000,
+            sensor_type_allowlist: None,
+            extra_sensor_types: Vec::new(),
+            webhook_url: None,
+            auto_provision_sensors: false,
+            query_cache_ttl_secs: 5,
+            query_cache_max_entries: 1024,
+            idempotency_key_ttl_secs: 86_400,
+            reject_pre_session_readings: false,
+            reject_out_of_range_readings: false,
+            read_only: false,
+            backup_dir: "backups".to_string(),
+            cors_allowed_origins: None,
+            max_bulk_body_bytes: 10 * 1024 * 1024,
+        }
+    }
+}
+
+impl Config {
+    /// Load the configuration from the process environment
+    pub fn from_env() -> Result<Self> {
+        Self::from_vars(std::env::vars())
+    }
+
+    /// Build the configuration from a set of variables, applying defaults
+    pub fn from_vars<I>(vars: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (String, String)>,
+    {
+        let vars: HashMap<String, String> = vars.into_iter().collect();
+        let defaults = Self::default();
+
+        let database_path = vars
+            .get("DATABASE_PATH")
+            .cloned()
+            .unwrap_or(defaults.database_path);
+        if database_path.trim().is_empty() {
+            return Err(anyhow!("Invalid value for DATABASE_PATH: must not be empty"));
+        }
+
+        let port = parse_var(&vars, "PORT", defaults.port)?;
+        if port == 0 {
+            return Err(anyhow!("Invalid value for PORT: must be between 1 and 65535"));
+        }
+
+        let pool_max_size = parse_var(&vars, "DB_POOL_SIZE", defaults.pool_max_size)?;
+        if pool_max_size == 0 {
+            return Err(anyhow!("Invalid value for DB_POOL_SIZE: must be at least 1"));
+        }
+
+        // Zero fails immediately on contention, as SQLite does without a timeout
+        let db_busy_timeout_ms = parse_var(&vars, "DB_BUSY_TIMEOUT_MS", defaults.db_busy_timeout_ms)?;
+
+        let readings_default_limit =
+            parse_var(&vars, "READINGS_DEFAULT_LIMIT", defaults.readings_default_limit)?;
+        if readings_default_limit == 0 {
+            return Err(anyhow!("Invalid value for READINGS_DEFAULT_LIMIT: must be at least 1"));
+        }
+
+        let readings_max_limit = parse_var(&vars, "READINGS_MAX_LIMIT", defaults.readings_max_limit)?;
+        if readings_max_limit < readings_default_limit {
+            return Err(anyhow!(
+                "Invalid value for READINGS_MAX_LIMIT: must be at least READINGS_DEFAULT_LIMIT ({})",
+                readings_default_limit
+            ));
+        }
+
+        // Unset or blank keeps the built-in sensor types
+        let sensor_type_allowlist = parse_list(&vars, "SENSOR_TYPE_ALLOWLIST").filter(|types| !types.is_empty());
+        let extra_sensor_types = parse_list(&vars, "EXTRA_SENSOR_TYPES").unwrap_or_default();
+
+        let webhook_url = vars
+            .get("WEBHOOK_URL")
+            .map(|url| url.trim().to_string())
+            .filter(|url| !url.is_empty());
+
+        let auto_provision_sensors =
+            parse_flag(&vars, "AUTO_PROVISION_SENSORS", defaults.auto_provision_sensors)?;
+
+        let query_cache_ttl_secs =
+            parse_var(&vars, "QUERY_CACHE_TTL_SECS", defaults.query_cache_ttl_secs)?;
+
+        let query_cache_max_entries =
+            parse_var(&vars, "QUERY_CACHE_MAX_ENTRIES", defaults.query_cache_max_entries)?;
+        if query_cache_max_entries == 0 {
+            return Err(anyhow!(
+                "Invalid value for QUERY_CACHE_MAX_ENTRIES: must be at least 1; set QUERY_CACHE_TTL_SECS=0 to disable the cache"
+            ));
+        }
+
+        let idempotency_key_ttl_secs =
+            parse_var(&vars, "IDEMPOTENCY_KEY_TTL_SECS", defaults.idempotency_key_ttl_secs)?;
+        if idempotency_key_ttl_secs == 0 {
+            return Err(anyhow!("Invalid value for IDEMPOTENCY_KEY_TTL_SECS: must be at least 1"));
+        }
+
+        let reject_pre_session_readings = parse_flag(
+            &vars,
+            "REJECT_PRE_SESSION_READINGS",
+            defaults.reject_pre_session_readings,
+        )?;
+
+        let reject_out_of_range_readings = parse_flag(
+            &vars,
+            "REJECT_OUT_OF_RANGE_READINGS",
+            defaults.reject_out_of_range_readings,
+        )?;
+
+        let read_only = parse_flag(&vars, "READ_ONLY", defaults.read_only)?;
+
+        let backup_dir = vars
+            .get("BACKUP_DIR")
+            .map(|dir| dir.trim().to_string())
+            .unwrap_or(defaults.backup_dir);
+        if backup_dir.is_empty() {
+            return Err(anyhow!("Invalid value for BACKUP_DIR: must not be empty"));
+        }
+
+        // Unset or blank keeps cross-origin requests blocked
+        let cors_allowed_origins = parse_list(&vars, "CORS_ALLOWED_ORIGINS").filter(|origins| !origins.is_empty());
+        if let Some(origins) = &cors_allowed_origins {
+            let wildcard = origins.iter().any(|origin| origin == "*");
+            let malformed = origins.iter().any(|origin| {
+                let scheme = origin.starts_with("http://") || origin.starts_with("https://");
+                (origin != "*" && !scheme) || origin.contains(char::is_whitespace)
+            });
+            if malformed || (wildcard && origins.len() > 1) {
+                return Err(anyhow!(
+                    "Invalid value for CORS_ALLOWED_ORIGINS: expected '*' or a list of http(s) origins"
+                ));
+            }
+        }
+
+        let max_bulk_body_bytes = parse_var(&vars, "MAX_BULK_BODY_BYTES", defaults.max_bulk_body_bytes)?;
+        if max_bulk_body_bytes == 0 {
+            return Err(anyhow!("Invalid value for MAX_BULK_BODY_BYTES: must be at least 1"));
+        }
+
+        Ok(Self {
+            database_path,
+            port,
+            pool_max_size,
+            db_busy_timeout_ms,
+            readings_default_limit,
+            readings_max_limit,
+            sensor_type_allowlist,
+            extra_sensor_types,
+            webhook_url,
+            auto_provision_sensors,
+            query_cache_ttl_secs,
+            query_cache_max_entries,
+            idempotency_key_ttl_secs,
+            reject_pre_session_readings,
+            reject_out_of_range_readings,
+            read_only,
+            backup_dir,
+            cors_allowed_origins,
+            max_bulk_body_bytes,
+        })
+    }
+}
+
+/// Parse an optional variable, falling back to the default when unset
+fn parse_var<T: FromStr>(vars: &HashMap<String, String>, name: &str, default: T) -> Result<T> {
+    match vars.get(name) {
+        Some(raw) => raw
+            .trim()
+            .parse()
+            .map_err(|_| anyhow!("Invalid value for {}: '{}'", name, raw)),
+        None => Ok(default),
+    }
+}
+
+/// Parse an optional comma-separated variable, dropping blank entries
+fn parse_list(vars: &HashMap<String, String>, name: &str) -> Option<Vec<String>> {
+    vars.get(name).map(|raw| {
+        raw.split(',')
+            .map(|item| item.trim().to_string())
+            .filter(|item| !item.is_empty())
+            .collect()
+    })
+}
+
+/// Parse an optional on/off variable, accepting 1/0 as well as true/false
+fn parse_flag(vars: &HashMap<String, String>, name: &str, default: bool) -> Result<bool> {
+    match vars.get(name).map(|raw| raw.trim()) {
+        Some("1") => Ok(true),
+        Some("0") => Ok(false),
+        _ => parse_var(vars, name, default),
+    }
+}
+
+/// Install the configuration for the lifetime of the process
+pub fn init(config: Config) -> Arc<Config> {
+    CONFIG.get_or_init(|| Arc::new(config)).clone()
+}
+
+/// Get the active configuration (defaults if `init` was never called)
+pub fn get() -> Arc<Config> {
+    #[cfg(test)]
+    if let Some(config) = TEST_CONFIG.with(|cell| cell.borrow().clone()) {
+        return config;
+    }
+
+    CONFIG.get_or_init(|| Arc::new(Config::default())).clone()
+}
+
+/// Override the configuration for the current test thread
+#[cfg(test)]
+pub fn set_test_config(config: Config) {
+    TEST_CONFIG.with(|cell| *cell.borrow_mut() = Some(Arc::new(config)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn vars(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_config_from_vars() -> Result<()> {
+        let config = Config::from_vars(vars(&[
+            ("DATABASE_PATH", "/data/sensors.db"),
+            ("PORT", "8080"),
+            ("DB_POOL_SIZE", "4"),
+            ("DB_BUSY_TIMEOUT_MS", "250"),
+            ("READINGS_DEFAULT_LIMIT", "250"),
+            ("READINGS_MAX_LIMIT", "5000"),
+            ("SENSOR_TYPE_ALLOWLIST", "temperature, power,,flow"),
+            ("EXTRA_SENSOR_TYPES", " pressure ,co2"),
+            ("WEBHOOK_URL", " https://alerts.example.com/hook "),
+            ("AUTO_PROVISION_SENSORS", "1"),
+            ("QUERY_CACHE_TTL_SECS", "30"),
+            ("QUERY_CACHE_MAX_ENTRIES", "64"),
+            ("IDEMPOTENCY_KEY_TTL_SECS", "600"),
+            ("REJECT_PRE_SESSION_READINGS", "true"),
+            ("REJECT_OUT_OF_RANGE_READINGS", "1"),
+            ("READ_ONLY", "1"),
+            ("BACKUP_DIR", "/var/backups/sensors"),
+            ("CORS_ALLOWED_ORIGINS", "https://dashboard.example.com, http://localhost:8080"),
+            ("MAX_BULK_BODY_BYTES", "1048576"),
+            ("UNRELATED", "ignored"),
+        ]))?;
+
+        assert_eq!(config.database_path, "/data/sensors.db");
+        assert_eq!(config.port, 8080);
+        assert_eq!(config.pool_max_size, 4);
+        assert_eq!(config.db_busy_timeout_ms, 250);
+        assert_eq!(config.readings_default_limit, 250);
+        assert_eq!(config.readings_max_limit, 5000);
+        assert_eq!(
+            config.sensor_type_allowlist,
+            Some(vec!["temperature".to_string(), "power".to_string(), "flow".to_string()])
+        );
+        assert_eq!(config.extra_sensor_types, vec!["pressure".to_string(), "co2".to_string()]);
+        assert_eq!(config.webhook_url.as_deref(), Some("https://alerts.example.com/hook"));
+        assert!(config.auto_provision_sensors);
+        assert_eq!(config.query_cache_ttl_secs, 30);
+        assert_eq!(config.query_cache_max_entries, 64);
+        assert_eq!(config.idempotency_key_ttl_secs, 600);
+        assert!(config.reject_pre_session_readings);
+        assert!(config.reject_out_of_range_readings);
+        assert!(config.read_only);
+        assert_eq!(config.backup_dir, "/var/backups/sensors");
+        assert_eq!(
+            config.cors_allowed_origins,
+            Some(vec!["https://dashboard.example.com".to_string(), "http://localhost:8080".to_string()])
+        );
+        assert_eq!(config.max_bulk_body_bytes, 1_048_576);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_config_defaults() -> Result<()> {
+        let config = Config::from_vars(Vec::new())?;
+
+        assert_eq!(config.database_path, "sensor_data.db");
+        assert_eq!(config.port, 3000);
+        assert_eq!(config.pool_max_size, 10);
+        assert_eq!(config.db_busy_timeout_ms, 5000);
+        assert_eq!(config.readings_default_limit, 1000);
+        assert_eq!(config.readings_max_limit, 10_000);
+        assert_eq!(config.sensor_type_allowlist, None);
+        assert!(config.extra_sensor_types.is_empty());
+        assert_eq!(config.webhook_url, None);
+        assert!(!config.auto_provision_sensors);
+        assert_eq!(config.query_cache_ttl_secs, 5);
+        assert_eq!(config.query_cache_max_entries, 1024);
+        assert_eq!(config.idempotency_key_ttl_secs, 86_400);
+        assert!(!config.reject_pre_session_readings);
+        assert!(!config.reject_out_of_range_readings);
+        assert!(!config.read_only);
+        assert_eq!(config.backup_dir, "backups");
+        assert_eq!(config.cors_allowed_origins, None);
+        assert_eq!(config.max_bulk_body_bytes, 10 * 1024 * 1024);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_get_uses_thread_override() {
+        set_test_config(Config {
+            readings_default_limit: 5,
+            ..Config::default()
+        });
+
+        assert_eq!(get().readings_default_limit, 5);
+    }
+
+    #[test]
+    fn test_config_rejects_bad_values() {
+        let err = Config::from_vars(vars(&[("PORT", "not-a-port")])).unwrap_err();
+        assert!(err.to_string().contains("PORT"));
+
+        let err = Config::from_vars(vars(&[("DB_POOL_SIZE", "0")])).unwrap_err();
+        assert!(err.to_string().contains("DB_POOL_SIZE"));
+
+        let err = Config::from_vars(vars(&[("DATABASE_PATH", " ")])).unwrap_err();
+        assert!(err.to_string().contains("DATABASE_PATH"));
+
+        let err = Config::from_vars(vars(&[("READ_ONLY", "2")])).unwrap_err();
+        assert!(err.to_string().contains("READ_ONLY"));
+
+        let err = Config::from_vars(vars(&[("BACKUP_DIR", "  ")])).unwrap_err();
+        assert!(err.to_string().contains("BACKUP_DIR"));
+
+        let err = Config::from_vars(vars(&[("QUERY_CACHE_MAX_ENTRIES", "0")])).unwrap_err();
+        assert!(err.to_string().contains("QUERY_CACHE_MAX_ENTRIES"));
+
+        let err = Config::from_vars(vars(&[("READINGS_MAX_LIMIT", "100")])).unwrap_err();
+        assert!(err.to_string().contains("READINGS_MAX_LIMIT"));
+
+        let err = Config::from_vars(vars(&[("CORS_ALLOWED_ORIGINS", "*, https://a.example.com")])).unwrap_err();
+        assert!(err.to_string().contains("CORS_ALLOWED_ORIGINS"));
+
+        let err = Config::from_vars(vars(&[("CORS_ALLOWED_ORIGINS", "dashboard.example.com")])).unwrap_err();
+        assert!(err.to_string().contains("CORS_ALLOWED_ORIGINS"));
+    }
+}
@@ -1,11 +1,106 @@
 use anyhow::{Context, Result};
 use rusqlite::Connection;
 
+/// A schema change, applied once and recorded under its version
+struct Migration {
+    version: i32,
+    name: &'static str,
+    sql: &'static str,
+}
+
+/// Every migration in the order it applies; versions must be strictly increasing
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial schema",
+        sql: include_str!("../../migrations/001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "sensor archival",
+        sql: include_str!("../../migrations/002_sensor_archival.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "sensor display",
+        sql: include_str!("../../migrations/003_sensor_display.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "sensor deadband",
+        sql: include_str!("../../migrations/004_sensor_deadband.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "API tokens",
+        sql: include_str!("../../migrations/005_api_tokens.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "sensor retention",
+        sql: include_str!("../../migrations/006_sensor_retention.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "reading annotations",
+        sql: include_str!("../../migrations/007_reading_annotations.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "backups",
+        sql: include_str!("../../migrations/008_backups.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "sensor disable",
+        sql: include_str!("../../migrations/009_sensor_disable.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "idempotency keys",
+        sql: include_str!("../../migrations/010_idempotency_keys.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "sensor tags",
+        sql: include_str!("../../migrations/011_sensor_tags.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "one active session",
+        sql: include_str!("../../migrations/012_one_active_session.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "sensor valid range",
+        sql: include_str!("../../migrations/013_sensor_valid_range.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "unique reading instant",
+        sql: include_str!("../../migrations/014_unique_reading_instant.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "sensor audit",
+        sql: include_str!("../../migrations/015_sensor_audit.sql"),
+    },
+];
+
 /// Schema version
-const CURRENT_VERSION: i32 = 1;
+pub const CURRENT_VERSION: i32 = MIGRATIONS[MIGRATIONS.len() - 1].version;
 
 /// Run database migrations
 pub fn run_migrations(conn: &mut Connection) -> Result<()> {
+    migrate_to(conn, CURRENT_VERSION)
+}
+
+/// Apply every migration above the recorded version, up to and including `target`
+///
+/// Pending migrations run in order inside one transaction, and each records
+/// its own version, so an interrupted upgrade leaves the schema untouched
+/// and a later run resumes from whatever version was last committed.
+fn migrate_to(conn: &mut Connection, target: i32) -> Result<()> {
     // Create schema_version table if it doesn't exist
     conn.execute(
         "CREATE TABLE IF NOT EXISTS schema_version (
@@ -16,34 +111,145 @@ pub fn run_migrations(conn: &mut Connection) -> Result<()> {
     .context("Failed to create schema_version table")?;
 
     // Get current schema version
+    let version = get_applied_version(conn)?;
+
+    let pending: Vec<&Migration> = MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > version && migration.version <= target)
+        .collect();
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    // Begin transaction for migration
+    let tx = conn.transaction().context("Failed to begin transaction")?;
+
+    for migration in pending {
+        tx.execute_batch(migration.sql)
+            .with_context(|| format!("Failed to apply {} migration", migration.name))?;
+
+        tx.execute(
+            "INSERT INTO schema_version (version) VALUES (?)",
+            [migration.version],
+        )
+        .context("Failed to update schema version")?;
+    }
+
+    // Commit transaction
+    tx.commit().context("Failed to commit migration transaction")?;
+
+    Ok(())
+}
+
+/// Get the most recently applied schema version (0 if none)
+pub fn get_applied_version(conn: &Connection) -> Result<i32> {
     let version: i32 = conn
         .query_row(
-            "SELECT version FROM schema_version ORDER BY version DESC LIMIT 1",
+            "SELECT COALESCE(MAX(version), 0) FROM schema_version",
             [],
             |row| row.get(0),
         )
-        .unwrap_or(0);
+        .context("Failed to read schema version")?;
 
-    if version < CURRENT_VERSION {
-        // Begin transaction for migration
-        let tx = conn.transaction().context("Failed to begin transaction")?;
+    Ok(version)
+}
 
-        if version == 0 {
-            // Initial schema
-            tx.execute_batch(include_str!("../../migrations/001_initial_schema.sql"))
-                .context("Failed to apply initial schema migration")?;
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        // Update schema version
-        tx.execute(
-            "INSERT INTO schema_version (version) VALUES (?)",
-            [CURRENT_VERSION],
-        )
-        .context("Failed to update schema version")?;
+    fn applied_versions(conn: &Connection) -> Result<Vec<i32>> {
+        let versions = conn
+            .prepare("SELECT version FROM schema_version ORDER BY version")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        Ok(versions)
+    }
 
-        // Commit transaction
-        tx.commit().context("Failed to commit migration transaction")?;
+    fn has_column(conn: &Connection, table: &str, column: &str) -> Result<bool> {
+        let count: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?",
+            [table, column],
+            |row| row.get(0),
+        )?;
+        Ok(count > 0)
     }
 
-    Ok(())
-}
\ No newline at end of file
+    #[test]
+    fn test_migrations_are_ordered() {
+        assert!(MIGRATIONS.windows(2).all(|pair| pair[0].version < pair[1].version));
+        assert_eq!(MIGRATIONS[0].version, 1);
+    }
+
+    #[test]
+    fn test_migrate_from_empty_database() -> Result<()> {
+        let mut conn = Connection::open_in_memory()?;
+
+        run_migrations(&mut conn)?;
+        assert_eq!(applied_versions(&conn)?, (1..=CURRENT_VERSION).collect::<Vec<_>>());
+        assert!(has_column(&conn, "readings", "annotation")?);
+
+        // A second run finds nothing pending
+        run_migrations(&mut conn)?;
+        assert_eq!(applied_versions(&conn)?.len(), CURRENT_VERSION as usize);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_resumes_from_intermediate_version() -> Result<()> {
+        let mut conn = Connection::open_in_memory()?;
+
+        migrate_to(&mut conn, 1)?;
+        assert_eq!(applied_versions(&conn)?, vec![1]);
+        assert!(!has_column(&conn, "sensors", "archived")?);
+
+        // Re-running an ALTER TABLE ... ADD COLUMN would fail, so this also
+        // proves the earlier migrations are skipped
+        migrate_to(&mut conn, 4)?;
+        assert_eq!(applied_versions(&conn)?, vec![1, 2, 3, 4]);
+        assert!(has_column(&conn, "sensors", "deadband")?);
+        assert!(!has_column(&conn, "sensors", "retention_days")?);
+
+        run_migrations(&mut conn)?;
+        assert_eq!(get_applied_version(&conn)?, CURRENT_VERSION);
+        assert!(has_column(&conn, "sensors", "retention_days")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_unique_reading_instant_keeps_the_newest_duplicate() -> Result<()> {
+        let mut conn = Connection::open_in_memory()?;
+        migrate_to(&mut conn, 13)?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_name, sensor_type, created_at, updated_at) VALUES ('Meter', 'power', 0, 0)",
+            [],
+        )?;
+        conn.execute_batch(
+            "INSERT INTO readings (sensor_id, timestamp, value) VALUES (1, 100, 1.0), (1, 100, 2.0), (1, 100, 3.0), (1, 200, 4.0);",
+        )?;
+
+        run_migrations(&mut conn)?;
+        let values: Vec<f64> = conn
+            .prepare("SELECT value FROM readings ORDER BY timestamp")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(values, vec![3.0, 4.0]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_migrate_resumes_from_single_recorded_version() -> Result<()> {
+        // Databases migrated before each step was recorded hold only their latest version
+        let mut conn = Connection::open_in_memory()?;
+        migrate_to(&mut conn, 5)?;
+        conn.execute("DELETE FROM schema_version WHERE version < 5", [])?;
+
+        run_migrations(&mut conn)?;
+        assert_eq!(applied_versions(&conn)?, (5..=CURRENT_VERSION).collect::<Vec<_>>());
+
+        Ok(())
+    }
+}
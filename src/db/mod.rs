@@ -1,9 +1,15 @@
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use once_cell::sync::OnceCell;
 use r2d2::Pool;
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::Connection;
+use rusqlite::{
+    backup::{Backup, StepResult},
+    types::Type,
+    Connection, OpenFlags, Row,
+};
 use std::path::Path;
+use std::time::Duration;
 
 pub mod migrations;
 pub mod schema;
@@ -11,32 +17,104 @@ pub mod schema;
 type DbPool = Pool<SqliteConnectionManager>;
 static DB_POOL: OnceCell<DbPool> = OnceCell::new();
 
+#[cfg(test)]
+thread_local! {
+    /// Per-thread test pool, so tests running in parallel each get their own database;
+    /// the thread that created the database owns its directory
+    static TEST_POOL: std::cell::RefCell<Option<(DbPool, Option<tempfile::TempDir>)>> =
+        const { std::cell::RefCell::new(None) };
+    
+    /// Connections checked out by `get_connection` on this thread
+    static TEST_CHECKOUTS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
 /// Initialize the database connection pool
-pub fn init_pool(db_path: &Path) -> Result<&'static DbPool> {
-    let manager = SqliteConnectionManager::file(db_path)
-        .with_init(|conn| {
-            conn.execute_batch(
-                "PRAGMA journal_mode = WAL;
-                 PRAGMA synchronous = NORMAL;
-                 PRAGMA foreign_keys = ON;
-                 PRAGMA cache_size = 10000;",
-            )?;
-            Ok(())
-        });
-
-    let pool = Pool::new(manager).context("Failed to create database connection pool")?;
+///
+/// Fails if the pool was already initialized, rather than silently keeping
+/// the first pool and discarding the new one.
+pub fn init_pool(db_path: &Path, max_size: u32, busy_timeout: Duration, read_only: bool) -> Result<&'static DbPool> {
+    if DB_POOL.get().is_some() {
+        return Err(anyhow::anyhow!("Database pool is already initialized"));
+    }
     
-    DB_POOL.get_or_init(|| pool);
+    let pool = build_pool(db_path, max_size, busy_timeout, read_only)?;
+    DB_POOL
+        .set(pool)
+        .map_err(|_| anyhow::anyhow!("Database pool is already initialized"))?;
+
+    Ok(DB_POOL.get().expect("pool was just initialized"))
+}
+
+/// Build a connection pool for a database file and bring its schema up to date
+///
+/// A read-only pool opens the file with `SQLITE_OPEN_READ_ONLY` and skips
+/// migrations, so the schema must already be current. Connections wait up
+/// to `busy_timeout` for another connection's lock before giving up with
+/// `SQLITE_BUSY`; WAL lets readers through, but writers still take turns.
+fn build_pool(db_path: &Path, max_size: u32, busy_timeout: Duration, read_only: bool) -> Result<DbPool> {
+    let manager = if read_only {
+        SqliteConnectionManager::file(db_path)
+            .with_flags(
+                OpenFlags::SQLITE_OPEN_READ_ONLY
+                    | OpenFlags::SQLITE_OPEN_URI
+                    | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+            )
+            .with_init(move |conn| {
+                conn.busy_timeout(busy_timeout)?;
+                conn.execute_batch(
+                    "PRAGMA foreign_keys = ON;
+                     PRAGMA cache_size = 10000;",
+                )?;
+                Ok(())
+            })
+    } else {
+        SqliteConnectionManager::file(db_path)
+            .with_init(move |conn| {
+                conn.busy_timeout(busy_timeout)?;
+                conn.execute_batch(
+                    "PRAGMA journal_mode = WAL;
+                     PRAGMA synchronous = NORMAL;
+                     PRAGMA foreign_keys = ON;
+                     PRAGMA cache_size = 10000;",
+                )?;
+                Ok(())
+            })
+    };
+
+    let pool = Pool::builder()
+        .max_size(max_size)
+        .build(manager)
+        .context("Failed to create database connection pool")?;
     
-    // Run migrations
-    let conn = get_connection()?;
-    migrations::run_migrations(&conn)?;
+    // Migrate through this pool directly, before it is installed anywhere
+    let mut conn = pool.get().context("Failed to get database connection from pool")?;
+    if read_only {
+        // Migrations need write access, so they must have been applied already
+        let version = migrations::get_applied_version(&conn)?;
+        if version < migrations::CURRENT_VERSION {
+            return Err(anyhow::anyhow!(
+                "Database schema is at version {} but {} is required; start once without READ_ONLY to migrate",
+                version,
+                migrations::CURRENT_VERSION
+            ));
+        }
+    } else {
+        // Run migrations
+        migrations::run_migrations(&mut conn)?;
+    }
+    drop(conn);
 
-    Ok(DB_POOL.get().unwrap())
+    Ok(pool)
 }
 
 /// Get a connection from the pool
 pub fn get_connection() -> Result<r2d2::PooledConnection<SqliteConnectionManager>> {
+    #[cfg(test)]
+    if let Some(pool) = TEST_POOL.with(|cell| cell.borrow().as_ref().map(|(pool, _)| pool.clone())) {
+        TEST_CHECKOUTS.with(|count| count.set(count.get() + 1));
+        return pool.get().context("Failed to get database connection from pool");
+    }
+    
     match DB_POOL.get() {
         Some(pool) => Ok(pool.get().context("Failed to get database connection from pool")?),
         None => Err(anyhow::anyhow!("Database pool not initialized")),
@@ -44,6 +122,7 @@ pub fn get_connection() -> Result<r2d2::PooledConnection<SqliteConnectionManager
 }
 
 /// Get the database pool
+#[allow(dead_code)]
 pub fn get_pool() -> Result<&'static DbPool> {
     match DB_POOL.get() {
         Some(pool) => Ok(pool),
@@ -51,21 +130,269 @@ pub fn get_pool() -> Result<&'static DbPool> {
     }
 }
 
-/// Create a new in-memory database for testing
+/// Times a backup is retried while another connection holds a conflicting lock
+const BACKUP_RETRIES: u32 = 10;
+
+/// Copy the database behind `conn` into a new file at `dest`
+///
+/// All pages are copied in a single step, which reads one consistent
+/// snapshot. Under WAL that never blocks writers, and concurrent writes
+/// can't force the copy to restart part way through.
+pub fn backup_to(conn: &Connection, dest: &Path) -> Result<()> {
+    let mut dest_conn = Connection::open(dest)
+        .with_context(|| format!("Failed to open backup file {}", dest.display()))?;
+    let backup = Backup::new(conn, &mut dest_conn)?;
+    
+    let mut attempt = 0;
+    loop {
+        match backup.step(-1)? {
+            StepResult::Done => return Ok(()),
+            StepResult::More => continue,
+            StepResult::Busy | StepResult::Locked if attempt < BACKUP_RETRIES => {
+                attempt += 1;
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            _ => return Err(anyhow::anyhow!("Database stayed locked; backup abandoned")),
+        }
+    }
+}
+
+/// Outcome of a WAL checkpoint, as reported by `PRAGMA wal_checkpoint`
+#[derive(Debug, PartialEq)]
+pub struct WalCheckpoint {
+    pub busy: bool,  // Another connection kept the checkpoint from finishing
+    pub log_frames: i64,
+    pub checkpointed_frames: i64,
+}
+
+/// Copy every WAL frame into the database file and truncate the WAL
+///
+/// Afterwards the database file alone holds every committed write, so
+/// it can be copied without its `-wal` sidecar.
+pub fn checkpoint(conn: &Connection) -> Result<WalCheckpoint> {
+    let checkpoint = conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |row| {
+        Ok(WalCheckpoint {
+            busy: row.get::<_, i64>(0)? != 0,
+            log_frames: row.get(1)?,
+            checkpointed_frames: row.get(2)?,
+        })
+    })?;
+    
+    Ok(checkpoint)
+}
+
+/// Read an epoch-seconds column as a UTC datetime
+///
+/// Out-of-range values fail the row with a conversion error rather than
+/// panicking the handler.
+pub fn get_datetime(row: &Row, column: &str) -> rusqlite::Result<DateTime<Utc>> {
+    let index = row.as_ref().column_index(column)?;
+    let timestamp: i64 = row.get(index)?;
+    to_datetime(index, column, timestamp)
+}
+
+/// Read a nullable epoch-seconds column as a UTC datetime
+pub fn get_optional_datetime(row: &Row, column: &str) -> rusqlite::Result<Option<DateTime<Utc>>> {
+    let index = row.as_ref().column_index(column)?;
+    let timestamp: Option<i64> = row.get(index)?;
+    timestamp.map(|timestamp| to_datetime(index, column, timestamp)).transpose()
+}
+
+fn to_datetime(index: usize, column: &str, timestamp: i64) -> rusqlite::Result<DateTime<Utc>> {
+    DateTime::from_timestamp(timestamp, 0).ok_or_else(|| {
+        rusqlite::Error::FromSqlConversionFailure(
+            index,
+            Type::Integer,
+            format!("{} timestamp {} is out of range", column, timestamp).into(),
+        )
+    })
+}
+
+/// Number of connections `get_connection` has handed out on this test thread
 #[cfg(test)]
-pub fn init_test_pool() -> Result<&'static DbPool> {
-    let manager = SqliteConnectionManager::memory().with_init(|conn| {
-        conn.execute_batch("PRAGMA foreign_keys = ON;")?;
+pub fn test_checkout_count() -> usize {
+    TEST_CHECKOUTS.with(|count| count.get())
+}
+
+/// Create a fresh database for the current test thread
+///
+/// Each call replaces the calling thread's pool with one backed by a new
+/// temporary file, which is removed when the thread exits.
+#[cfg(test)]
+pub fn init_test_pool() -> Result<DbPool> {
+    let dir = tempfile::TempDir::new().context("Failed to create test database directory")?;
+    let manager = SqliteConnectionManager::file(dir.path().join("test.db")).with_init(|conn| {
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             PRAGMA foreign_keys = ON;",
+        )?;
         Ok(())
     });
 
-    let pool = Pool::new(manager).context("Failed to create test database pool")?;
-    
-    DB_POOL.get_or_init(|| pool);
+    let pool = Pool::builder()
+        .max_size(4)
+        .build(manager)
+        .context("Failed to create test database pool")?;
     
     // Run migrations on the test database
-    let conn = get_connection()?;
-    migrations::run_migrations(&conn)?;
+    let mut conn = pool.get().context("Failed to get test database connection")?;
+    migrations::run_migrations(&mut conn)?;
+    drop(conn);
+
+    TEST_POOL.with(|cell| *cell.borrow_mut() = Some((pool.clone(), Some(dir))));
+
+    Ok(pool)
+}
+
+/// Point the current thread at another thread's test database
+///
+/// For tests that exercise concurrent access; the database stays owned
+/// by the thread that created it.
+#[cfg(test)]
+pub fn share_test_pool(pool: DbPool) {
+    TEST_POOL.with(|cell| *cell.borrow_mut() = Some((pool, None)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_fresh_pool_migrates_once() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("fresh.db");
+        
+        let pool = build_pool(&path, 2, BUSY_TIMEOUT, false)?;
+        let conn = pool.get()?;
+        let versions: Vec<i32> = conn
+            .prepare("SELECT version FROM schema_version")?
+            .query_map([], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        assert_eq!(versions, (1..=migrations::CURRENT_VERSION).collect::<Vec<_>>());
+        drop(conn);
+        
+        // Reopening an up-to-date database applies nothing further
+        let reopened = build_pool(&path, 2, BUSY_TIMEOUT, false)?;
+        let count: i64 = reopened
+            .get()?
+            .query_row("SELECT COUNT(*) FROM schema_version", [], |row| row.get(0))?;
+        assert_eq!(count, migrations::CURRENT_VERSION as i64);
+        
+        // A read-only pool accepts the migrated schema but refuses writes
+        let read_only = build_pool(&path, 1, BUSY_TIMEOUT, true)?;
+        let err = read_only
+            .get()?
+            .execute("DELETE FROM sensors", [])
+            .unwrap_err();
+        assert_eq!(err.sqlite_error_code(), Some(rusqlite::ErrorCode::ReadOnly));
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_every_pool_enforces_foreign_keys() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("fk.db");
+        let foreign_keys = |conn: &rusqlite::Connection| -> Result<bool> {
+            Ok(conn.query_row("PRAGMA foreign_keys", [], |row| row.get(0))?)
+        };
+        
+        // Hold every connection at once so each one runs the init hook
+        let pools = [
+            build_pool(&path, 2, BUSY_TIMEOUT, false)?,
+            build_pool(&path, 2, BUSY_TIMEOUT, true)?,
+            init_test_pool()?,
+        ];
+        for pool in &pools {
+            let (first, second) = (pool.get()?, pool.get()?);
+            assert!(foreign_keys(&first)? && foreign_keys(&second)?);
+        }
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_writers_wait_out_a_held_lock() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("busy.db");
+        let insert = |conn: &Connection| {
+            conn.execute(
+                "INSERT INTO sensors (sensor_name, sensor_type, created_at, updated_at) VALUES ('s', 'generic', 0, 0)",
+                [],
+            )
+        };
+        
+        // Another writer holds the lock for a moment, as a concurrent bulk insert would
+        let hold_lock = |pool: DbPool| -> Result<std::thread::JoinHandle<()>> {
+            let holder = pool.get()?;
+            holder.execute_batch("BEGIN IMMEDIATE")?;
+            Ok(std::thread::spawn(move || {
+                std::thread::sleep(Duration::from_millis(200));
+                holder.execute_batch("COMMIT").unwrap();
+            }))
+        };
+        
+        let patient = build_pool(&path, 2, BUSY_TIMEOUT, false)?;
+        let holder = hold_lock(patient.clone())?;
+        insert(&*patient.get()?)?;
+        holder.join().unwrap();
+        
+        // Without a timeout the same write fails straight away
+        let impatient = build_pool(&path, 2, Duration::ZERO, false)?;
+        let holder = hold_lock(impatient.clone())?;
+        let err = insert(&*impatient.get()?).unwrap_err();
+        assert_eq!(err.sqlite_error_code(), Some(rusqlite::ErrorCode::DatabaseBusy));
+        holder.join().unwrap();
+        
+        Ok(())
+    }
+
+    #[test]
+    fn test_checkpoint_empties_the_wal() -> Result<()> {
+        let dir = tempfile::TempDir::new()?;
+        let path = dir.path().join("checkpoint.db");
+        let wal_size = || std::fs::metadata(dir.path().join("checkpoint.db-wal")).map(|meta| meta.len());
+        
+        let pool = build_pool(&path, 2, BUSY_TIMEOUT, false)?;
+        let conn = pool.get()?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_name, sensor_type, created_at, updated_at) VALUES ('s', 'generic', 0, 0)",
+            [],
+        )?;
+        assert!(wal_size()? > 0);
+        
+        let checkpoint = checkpoint(&conn)?;
+        assert!(!checkpoint.busy, "{:?}", checkpoint);
+        assert_eq!(wal_size()?, 0);
+        
+        // The write now lives in the database file itself
+        drop(conn);
+        drop(pool);
+        let copy = dir.path().join("copy.db");
+        std::fs::copy(&path, &copy)?;
+        let count: i64 = Connection::open(&copy)?.query_row("SELECT COUNT(*) FROM sensors", [], |row| row.get(0))?;
+        assert_eq!(count, 1);
+        
+        Ok(())
+    }
 
-    Ok(DB_POOL.get().unwrap())
-}
\ No newline at end of file
+    #[test]
+    fn test_init_pool_twice_is_an_error() -> Result<()> {
+        // The process-wide pool outlives this test, so its directory must too
+        let temp_dir = tempfile::TempDir::new()?;
+        let dir = temp_dir.path().to_path_buf();
+        std::mem::forget(temp_dir);
+        
+        init_pool(&dir.join("first.db"), 2, BUSY_TIMEOUT, false)?;
+        let err = init_pool(&dir.join("second.db"), 2, BUSY_TIMEOUT, false).unwrap_err();
+        assert!(err.to_string().contains("already initialized"));
+        
+        // The first pool is still the one in use
+        assert!(dir.join("first.db").exists());
+        assert!(!dir.join("second.db").exists());
+        
+        Ok(())
+    }
+}
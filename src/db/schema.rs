@@ -1,7 +1,8 @@
-/// Database schema constants and helpers
+//! Database schema constants and helpers
+#![allow(dead_code)]
 
 /// Schema version
-pub const SCHEMA_VERSION: i32 = 1;
+pub const SCHEMA_VERSION: i32 = super::migrations::CURRENT_VERSION;
 
 /// SQL to create the initial schema tables and indices
 pub const INITIAL_SCHEMA: &str = include_str!("../../migrations/001_initial_schema.sql");
@@ -1,8 +1,10 @@
 use std::path::Path;
 use std::net::SocketAddr;
+use std::time::Duration;
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+mod config;
 mod db;
 mod models;
 mod api;
@@ -19,33 +21,81 @@ async fn main() -> anyhow::Result<()> {
         .with(tracing_subscriber::fmt::layer())
         .init();
     
-    // Get database path from env var or use default
-    let db_path = std::env::var("DATABASE_PATH")
-        .unwrap_or_else(|_| "sensor_data.db".to_string());
+    // Load configuration once from the environment
+    let config = config::init(config::Config::from_env()?);
     
     // Initialize the database
-    let path = Path::new(&db_path);
-    db::init_pool(path)?;
+    let path = Path::new(&config.database_path);
+    let busy_timeout = Duration::from_millis(config.db_busy_timeout_ms);
+    db::init_pool(path, config.pool_max_size, busy_timeout, config.read_only)?;
     
-    tracing::info!("Initialized database at {}", db_path);
+    if config.read_only {
+        tracing::info!("Initialized read-only database at {}", config.database_path);
+    } else {
+        tracing::info!("Initialized database at {}", config.database_path);
+    }
+    
+    // Deliver threshold breaches to the webhook, if one is configured
+    utils::webhook::init(config.webhook_url.as_deref());
     
     // Create API router
-    let app = api::create_router()
+    let app = api::create_router(api::AppState::default())
         .layer(TraceLayer::new_for_http());
     
-    // Get port from env var or use default
-    let port = std::env::var("PORT")
-        .ok()
-        .and_then(|p| p.parse::<u16>().ok())
-        .unwrap_or(3000);
-    
     // Run server
-    let addr = SocketAddr::from(([0, 0, 0, 0], port));
+    let addr = SocketAddr::from(([0, 0, 0, 0], config.port));
     tracing::info!("Starting server on {}", addr);
     
-    axum::Server::bind(&addr)
-        .serve(app.into_make_service())
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(shutdown_signal())
         .await?;
     
+    // In-flight requests have finished; leave a database file that is complete on its own
+    if !config.read_only {
+        match db::get_connection().and_then(|conn| db::checkpoint(&conn)) {
+            Ok(checkpoint) if checkpoint.busy => tracing::warn!(
+                "WAL checkpoint incomplete: {} of {} frames copied",
+                checkpoint.checkpointed_frames,
+                checkpoint.log_frames
+            ),
+            Ok(_) => tracing::info!("WAL checkpointed and truncated"),
+            Err(err) => tracing::error!("WAL checkpoint failed: {:#}", err),
+        }
+    }
+    
     Ok(())
+}
+
+/// Resolve on Ctrl+C or SIGTERM, starting a graceful shutdown
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(err) = tokio::signal::ctrl_c().await {
+            tracing::error!("Failed to listen for Ctrl+C: {}", err);
+            std::future::pending::<()>().await;
+        }
+    };
+    
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(err) => {
+                tracing::error!("Failed to listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    
+    tracing::info!("Shutting down; waiting for in-flight requests");
 }
\ No newline at end of file
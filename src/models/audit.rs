@@ -0,0 +1,180 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, Row};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::db::{get_connection, get_datetime};
+use crate::models::SensorResponse;
+use crate::utils::{current_timestamp, error::AppError};
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use crate::{
+        models::{Sensor, SensorAuditEntry, SensorPatch},
+        utils::{
+            error::AppError,
+            test_utils::{setup_test_db, create_test_sensor},
+        },
+    };
+    
+    #[test]
+    fn test_sensor_changes_are_audited() -> Result<()> {
+        let pool = setup_test_db()?;
+        let sensor_id = create_test_sensor(&*pool.get()?)?;
+        // The helper inserts directly, so the trail starts with this edit
+        assert!(SensorAuditEntry::for_sensor(sensor_id)?.is_empty());
+        
+        Sensor::patch(sensor_id, &SensorPatch { location: Some("Lab".to_string()), ..SensorPatch::default() })?;
+        Sensor::patch(sensor_id, &SensorPatch { threshold_max: Some(30.0), ..SensorPatch::default() })?;
+        // Changing nothing leaves nothing to record
+        Sensor::patch(sensor_id, &SensorPatch { location: Some("Lab".to_string()), ..SensorPatch::default() })?;
+        Sensor::disable(sensor_id)?;
+        Sensor::delete(sensor_id)?;
+        
+        let trail = SensorAuditEntry::for_sensor(sensor_id)?;
+        let actions: Vec<&str> = trail.iter().map(|entry| entry.action.as_str()).collect();
+        assert_eq!(actions, vec!["update", "update", "delete", "delete"]);
+        
+        assert_eq!(trail[0].old_value.as_ref().unwrap()["location"], "Test Location");
+        assert_eq!(trail[0].new_value.as_ref().unwrap()["location"], "Lab");
+        assert_eq!(trail[1].old_value.as_ref().unwrap()["threshold_max"], 25.0);
+        assert_eq!(trail[1].new_value.as_ref().unwrap()["threshold_max"], 30.0);
+        // A soft delete keeps the sensor, now disabled; a hard delete leaves nothing
+        assert!(trail[2].new_value.as_ref().unwrap()["disabled_at"].is_string());
+        assert!(trail[3].old_value.is_some() && trail[3].new_value.is_none());
+        
+        // A new sensor starts its own trail rather than reusing the deleted sensor's id
+        let sensor = Sensor {
+            sensor_id: None,
+            sensor_name: "Audited".to_string(),
+            sensor_type: "power".to_string(),
+            location: None,
+            unit: None,
+            threshold_min: None,
+            threshold_max: None,
+            calibration_date: None,
+            notes: None,
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let created = sensor.create()?;
+        let trail = SensorAuditEntry::for_sensor(created)?;
+        assert_eq!(trail.len(), 1);
+        assert_eq!(trail[0].action, "create");
+        assert!(trail[0].old_value.is_none());
+        assert_eq!(trail[0].new_value.as_ref().unwrap()["sensor_name"], "Audited");
+        
+        // A failed change leaves no entry behind
+        let err = Sensor::patch(created, &SensorPatch { deadband: Some(-1.0), ..SensorPatch::default() }).unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        assert_eq!(SensorAuditEntry::for_sensor(created)?.len(), 1);
+        
+        Ok(())
+    }
+}
+
+/// What happened to a sensor in one audit entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuditAction {
+    Create,
+    Update,
+    Delete,
+}
+
+impl AuditAction {
+    fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::Create => "create",
+            AuditAction::Update => "update",
+            AuditAction::Delete => "delete",
+        }
+    }
+}
+
+/// One change to a sensor, with the sensor as it was before and after
+///
+/// Soft deletes are `delete` entries whose new value shows `disabled_at`;
+/// after a hard delete the new value is null.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorAuditEntry {
+    pub audit_id: i64,
+    pub sensor_id: i64,
+    pub action: String,
+    pub old_value: Option<Value>,
+    pub new_value: Option<Value>,
+    pub changed_at: DateTime<Utc>,
+}
+
+impl SensorAuditEntry {
+    /// Record a change on the connection or transaction that made it
+    ///
+    /// Callers pass the transaction holding the change itself, so the entry
+    /// commits or rolls back with it. Changes that leave the sensor as it
+    /// was are not recorded.
+    pub fn record(
+        conn: &Connection,
+        sensor_id: i64,
+        action: AuditAction,
+        old: Option<&SensorResponse>,
+        new: Option<&SensorResponse>,
+    ) -> Result<()> {
+        let old_value = old.map(serde_json::to_string).transpose()?;
+        let new_value = new.map(serde_json::to_string).transpose()?;
+        if old_value.is_some() && old_value == new_value {
+            return Ok(());
+        }
+        
+        conn.execute(
+            "INSERT INTO sensor_audit (sensor_id, action, old_value, new_value, changed_at)
+             VALUES (?, ?, ?, ?, ?)",
+            params![sensor_id, action.as_str(), old_value, new_value, current_timestamp()],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Get a sensor's audit trail, oldest change first
+    ///
+    /// The trail outlives the sensor, so a hard-deleted sensor still has
+    /// one; a sensor that never existed is not found.
+    pub fn for_sensor(sensor_id: i64) -> Result<Vec<SensorAuditEntry>> {
+        let conn = get_connection()?;
+        
+        let mut stmt = conn.prepare("SELECT * FROM sensor_audit WHERE sensor_id = ? ORDER BY audit_id")?;
+        let entries = stmt
+            .query_map(params![sensor_id], Self::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        if entries.is_empty() && !crate::models::Sensor::exists(&conn, sensor_id)? {
+            return Err(AppError::NotFound(format!("Sensor {} not found", sensor_id)).into());
+        }
+        
+        Ok(entries)
+    }
+    
+    fn from_row(row: &Row) -> rusqlite::Result<Self> {
+        let json = |column: &str| -> rusqlite::Result<Option<Value>> {
+            let text: Option<String> = row.get(column)?;
+            text.map(|text| serde_json::from_str(&text))
+                .transpose()
+                .map_err(|err| rusqlite::Error::FromSqlConversionFailure(0, rusqlite::types::Type::Text, Box::new(err)))
+        };
+        
+        Ok(SensorAuditEntry {
+            audit_id: row.get("audit_id")?,
+            sensor_id: row.get("sensor_id")?,
+            action: row.get("action")?,
+            old_value: json("old_value")?,
+            new_value: json("new_value")?,
+            changed_at: get_datetime(row, "changed_at")?,
+        })
+    }
+}
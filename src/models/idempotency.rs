@@ -0,0 +1,127 @@
+use anyhow::Result;
+use rusqlite::{params, OptionalExtension};
+use serde_json::Value;
+
+use crate::config;
+use crate::db::get_connection;
+use crate::utils::{current_timestamp, error::AppError};
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use serde_json::json;
+    use crate::{
+        config::{self, Config},
+        db::get_connection,
+        models::IdempotentResponse,
+        utils::{error::AppError, test_utils::setup_test_db},
+    };
+
+    #[test]
+    fn test_keys_replay_until_they_expire() -> Result<()> {
+        let _pool = setup_test_db()?;
+        config::set_test_config(Config {
+            idempotency_key_ttl_secs: 60,
+            ..Config::default()
+        });
+        
+        // The first claim proceeds; a second one while it runs is refused
+        assert!(IdempotentResponse::claim("retry-1")?.is_none());
+        let err = IdempotentResponse::claim("retry-1").unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::Conflict(_))));
+        
+        let response = IdempotentResponse { status_code: 201, body: json!({"reading_id": 7}) };
+        response.save("retry-1")?;
+        let replayed = IdempotentResponse::claim("retry-1")?.expect("stored response");
+        assert_eq!((replayed.status_code, replayed.body), (201, json!({"reading_id": 7})));
+        
+        // Released claims can be retried from scratch
+        assert!(IdempotentResponse::claim("retry-2")?.is_none());
+        IdempotentResponse::release("retry-2")?;
+        assert!(IdempotentResponse::claim("retry-2")?.is_none());
+        
+        // Past the TTL a key is forgotten
+        get_connection()?.execute("UPDATE idempotency_keys SET created_at = created_at - 61", [])?;
+        assert!(IdempotentResponse::claim("retry-1")?.is_none());
+        
+        Ok(())
+    }
+}
+
+/// Response to a request sent with an `Idempotency-Key` header
+///
+/// Retries carrying the same key get this response back instead of
+/// repeating the request, until the key is older than
+/// `IDEMPOTENCY_KEY_TTL_SECS`.
+#[derive(Debug)]
+pub struct IdempotentResponse {
+    pub status_code: u16,
+    pub body: Value,
+}
+
+impl IdempotentResponse {
+    /// Claim a key for a new request, or get the response already stored for it
+    ///
+    /// Returns None when the caller should handle the request and then
+    /// `save` or `release` the key. A key whose first request is still
+    /// being handled is a conflict.
+    pub fn claim(key: &str) -> Result<Option<IdempotentResponse>> {
+        let conn = get_connection()?;
+        let now = current_timestamp();
+        let ttl = config::get().idempotency_key_ttl_secs as i64;
+        
+        conn.execute("DELETE FROM idempotency_keys WHERE created_at <= ?", params![now - ttl])?;
+        
+        let claimed = conn.execute(
+            "INSERT OR IGNORE INTO idempotency_keys (idempotency_key, created_at) VALUES (?, ?)",
+            params![key, now],
+        )?;
+        if claimed > 0 {
+            return Ok(None);
+        }
+        
+        let stored: Option<(Option<u16>, Option<String>)> = conn
+            .query_row(
+                "SELECT status_code, response FROM idempotency_keys WHERE idempotency_key = ?",
+                params![key],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        
+        match stored {
+            Some((Some(status_code), Some(response))) => Ok(Some(IdempotentResponse {
+                status_code,
+                body: serde_json::from_str(&response)?,
+            })),
+            _ => Err(AppError::Conflict(format!(
+                "A request with Idempotency-Key '{}' is still being processed",
+                key
+            ))
+            .into()),
+        }
+    }
+    
+    /// Store the response for a claimed key so retries replay it
+    pub fn save(&self, key: &str) -> Result<()> {
+        let conn = get_connection()?;
+        
+        conn.execute(
+            "UPDATE idempotency_keys SET status_code = ?, response = ? WHERE idempotency_key = ?",
+            params![self.status_code, self.body.to_string(), key],
+        )?;
+        
+        Ok(())
+    }
+    
+    /// Give up a claimed key after a failed request, so a retry runs it again
+    pub fn release(key: &str) -> Result<()> {
+        let conn = get_connection()?;
+        
+        conn.execute(
+            "DELETE FROM idempotency_keys WHERE idempotency_key = ? AND response IS NULL",
+            params![key],
+        )?;
+        
+        Ok(())
+    }
+}
@@ -1,7 +1,13 @@
 pub mod sensor;
 pub mod reading;
 pub mod session;
+pub mod token;
+pub mod idempotency;
+pub mod audit;
 
-pub use sensor::{Sensor, SensorResponse, SensorQuery};
-pub use reading::{Reading, ReadingResponse, ReadingQuery, ReadingBulkInsert, ReadingBulkResponse};
-pub use session::{LoggingSession, LoggingSessionResponse};
\ No newline at end of file
+pub use sensor::{Sensor, SensorPatch, SensorResponse, SensorQuery, SensorStats, SensorConfigDocument, SensorConfigImport, SensorBulkCreate, SensorBulkResponse, SensorBulkDelete, SensorBatchDelete, SensorDeleteResult};
+pub use reading::{BatchSettings, Reading, ReadingResponse, CurrentReadingQuery, ReadingQuery, ReadingAggregateQuery, ReadingHistogramQuery, ReadingExportRange, ReadingDeleteQuery, ReadingSpanQuery, ReadingSteppedQuery, ReadingIntegralQuery, ReadingTypeAggregateQuery, ReadingGroupedAggregateQuery, ReadingBulkInsert, ReadingBulkQuery, ReadingBulkResponse, ReadingLineError, ReadingNdjsonResponse, ReadingSubmission};
+pub use session::{LoggingSession, LoggingSessionResponse};
+pub use token::ApiToken;
+pub use idempotency::IdempotentResponse;
+pub use audit::SensorAuditEntry;
\ No newline at end of file
@@ -1,22 +1,566 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Row};
+use rusqlite::{params, types::Value, Connection, ErrorCode, OptionalExtension, Row};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::db::get_connection;
+use crate::config;
+use crate::db::{get_connection, get_datetime};
+use crate::models::{LoggingSession, Sensor};
+use crate::utils::{
+    cache,
+    error::AppError,
+    ingest,
+    normalize_timestamp,
+    stats::{classify_value, nearest_rank, percentile},
+    units,
+    webhook::{self, BreachEvent},
+};
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use crate::{
+        config::{self, Config},
+        models::{reading::OnConflict, Reading, ReadingQuery},
+        utils::{
+            current_timestamp,
+            error::AppError,
+            test_utils::{setup_test_db, create_test_sensor, create_test_session},
+        },
+    };
+
+    #[test]
+    fn test_annotation_round_trip() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, annotation: Option<&str>| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(20.0),
+            annotation: annotation.map(str::to_string),
+            ..Default::default()
+        };
+        reading(100, Some("calibration run")).create()?;
+        reading(200, None).create()?;
+        Reading::bulk_insert(&[reading(300, Some("door opened")), reading(400, None)])?;
+        
+        let annotated = |has_annotation| -> Result<Vec<(i64, Option<String>)>> {
+            let query = ReadingQuery {
+                sensor_id: Some(sensor_id),
+                has_annotation,
+                order: Some("asc".to_string()),
+                ..Default::default()
+            };
+            Ok(Reading::get(&query)?
+                .into_iter()
+                .map(|r| (r.timestamp.timestamp(), r.annotation))
+                .collect())
+        };
+        
+        assert_eq!(
+            annotated(Some(true))?,
+            vec![(100, Some("calibration run".to_string())), (300, Some("door opened".to_string()))]
+        );
+        assert_eq!(annotated(Some(false))?, vec![(200, None), (400, None)]);
+        assert_eq!(annotated(None)?.len(), 4);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_millisecond_timestamps_are_stored_as_seconds() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(20.0),
+            ..Default::default()
+        };
+        let seconds = reading(1_712_921_800).create()?.expect("stored");
+        let (_, ids) = Reading::bulk_upsert(&[reading(1_712_921_900)], OnConflict::Error, true)?;
+        
+        let stored = |reading_id: i64| -> Result<i64> {
+            Ok(conn.query_row("SELECT timestamp FROM readings WHERE reading_id = ?", [reading_id], |row| row.get(0))?)
+        };
+        assert_eq!(stored(seconds)?, 1_712_921_800);
+        assert_eq!(stored(ids[0])?, 1_712_921_900);
+        
+        // The millisecond forms land on the seconds already stored
+        let err = reading(1_712_921_800_250).create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::Conflict(_))));
+        let (inserted, _) = Reading::bulk_upsert(&[reading(1_712_921_900_999)], OnConflict::Ignore, false)?;
+        assert_eq!(inserted, 0);
+        
+        // On a sensor of their own they are stored as those seconds
+        let other_sensor = create_test_sensor(&conn)?;
+        let millis = Reading { sensor_id: other_sensor, ..reading(1_712_921_800_250) }.create()?.expect("stored");
+        let (_, ids) = Reading::bulk_upsert(
+            &[Reading { sensor_id: other_sensor, ..reading(1_712_921_900_999) }],
+            OnConflict::Error,
+            true,
+        )?;
+        assert_eq!(stored(millis)?, 1_712_921_800);
+        assert_eq!(stored(ids[0])?, 1_712_921_900);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_create_rejects_a_second_reading_in_the_same_second() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, value| Reading {
+            timestamp,
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        reading(Some(1_712_921_800), 20.0).create()?.expect("stored");
+        
+        // A different value at the same instant is still a clash, and the stored one is kept
+        let err = reading(Some(1_712_921_800), 21.0).create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::Conflict(_))));
+        let value: f64 = conn.query_row("SELECT value FROM readings WHERE sensor_id = ?", [sensor_id], |row| row.get(0))?;
+        assert_eq!(value, 20.0);
+        
+        // An untimestamped reading clashes the same way with one already stored for
+        // the current second; every second for the next minute is taken, so the
+        // clash doesn't depend on the clock standing still
+        let now = current_timestamp();
+        let taken: Vec<Reading> = (now..=now + 60).map(|timestamp| reading(Some(timestamp), 22.0)).collect();
+        Reading::bulk_insert(&taken)?;
+        let err = reading(None, 23.0).create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::Conflict(_))));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_readings_for_missing_sensor_are_not_found() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |sensor_id| Reading {
+            sensor_id,
+            value: Some(20.0),
+            ..Default::default()
+        };
+        
+        let err = reading(9999).create().unwrap_err();
+        match err.downcast_ref::<AppError>() {
+            Some(AppError::NotFound(message)) => assert_eq!(message, "Sensor 9999 not found"),
+            other => panic!("expected not found, got {:?}", other),
+        }
+        
+        // The whole batch is rejected, naming the first reading that failed
+        let err = Reading::bulk_insert(&[reading(sensor_id), reading(sensor_id), reading(9999)]).unwrap_err();
+        match err.downcast_ref::<AppError>() {
+            Some(AppError::NotFound(message)) => assert!(message.contains("Reading 2 references sensor 9999")),
+            other => panic!("expected not found, got {:?}", other),
+        }
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM readings", [], |row| row.get(0))?;
+        assert_eq!(count, 0);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_get_with_large_integer_sensor_id() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        // Beyond 2^53, so the id only round-trips when compared as an integer
+        let large_id: i64 = 9_007_199_254_740_993;
+        let other_id = create_test_sensor(&conn)?;
+        conn.execute(
+            "INSERT INTO sensors (sensor_id, sensor_name, sensor_type, created_at, updated_at)
+             VALUES (?, 'Large ID Sensor', 'power', 0, 0)",
+            [large_id],
+        )?;
+        
+        let readings: Vec<Reading> = [(large_id, 1.0), (large_id, 2.0), (other_id, 3.0)]
+            .iter()
+            .map(|&(sensor_id, value)| Reading {
+                timestamp: Some(value as i64 * 100),
+                sensor_id,
+                value: Some(value),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let query = ReadingQuery {
+            sensor_id: Some(large_id),
+            start_time: Some(0),
+            ..Default::default()
+        };
+        
+        let results = Reading::get(&query)?;
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.sensor_id == large_id));
+        
+        // The typed filter is answered from the (sensor_id, timestamp) index
+        let (filters, params) = Reading::build_filters(&query);
+        let plan_sql = format!("EXPLAIN QUERY PLAN SELECT * FROM readings WHERE 1=1{}", filters);
+        let mut stmt = conn.prepare(&plan_sql)?;
+        let plan: Vec<String> = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| row.get::<_, String>(3))?
+            .collect::<Result<_, _>>()?;
+        
+        assert!(
+            plan.iter().any(|detail| detail.contains("idx_readings_sensor_time")),
+            "expected index usage, got {:?}",
+            plan
+        );
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_dedup_across_batches() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Five identical readings one second apart, then a distinct one
+        let mut readings: Vec<Reading> = (0..5)
+            .map(|i| Reading {
+                timestamp: Some(100 + i),
+                sensor_id,
+                state: Some(1),
+                ..Default::default()
+            })
+            .collect();
+        readings.push(Reading {
+            timestamp: Some(106),
+            sensor_id,
+            state: Some(0),
+            ..Default::default()
+        });
+        Reading::bulk_insert(&readings)?;
+        
+        // A batch size of two forces the duplicate run to span several batches
+        let removed = Reading::dedup(10, 2)?;
+        assert_eq!(removed, 4);
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            ..Default::default()
+        };
+        let mut states: Vec<(i64, Option<i64>)> = Reading::get(&query)?
+            .iter()
+            .map(|r| (r.timestamp.timestamp(), r.state))
+            .collect();
+        states.sort();
+        assert_eq!(states, vec![(100, Some(1)), (106, Some(0))]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_exclude_change_type_keeps_nulls() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, change_type: Option<&str>| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(1.0),
+            change_type: change_type.map(str::to_string),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(100, Some("periodic")),
+            reading(200, Some("manual")),
+            reading(300, None),
+            reading(400, Some("manual")),
+        ])?;
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            exclude_change_type: Some("manual".to_string()),
+            ..Default::default()
+        };
+        let timestamps: Vec<i64> = Reading::get(&query)?
+            .iter()
+            .map(|r| r.timestamp.timestamp())
+            .collect();
+        assert_eq!(timestamps, vec![300, 100]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_create_rejects_reading_before_session_start() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let session_id = create_test_session(&conn, sensor_id, true)?;
+        let start_time: i64 = conn.query_row(
+            "SELECT start_time FROM logging_sessions WHERE session_id = ?",
+            [session_id],
+            |row| row.get(0),
+        )?;
+        
+        let reading = |timestamp| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(20.0),
+            ..Default::default()
+        };
+        
+        // Off by default, so early readings are stored as before
+        reading(start_time - 60).create()?;
+        
+        config::set_test_config(Config {
+            reject_pre_session_readings: true,
+            ..Config::default()
+        });
+        
+        let err = reading(start_time - 60).create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        reading(start_time).create()?;
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            ..Default::default()
+        };
+        assert_eq!(Reading::get(&query)?.len(), 2);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_downsampled_aggregates() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Two minute buckets, inserted out of timestamp order
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(6030, 3.0),
+            reading(6010, 1.0),
+            reading(6050, 5.0),
+            reading(6020, 2.0),
+            reading(6090, 10.0),
+            reading(6070, 20.0),
+            // State-only readings don't count
+            Reading { timestamp: Some(6040), sensor_id, state: Some(1), ..Default::default() },
+        ])?;
+        
+        let downsample = |agg: &str| -> Result<Vec<(i64, f64, i64)>> {
+            let query = ReadingQuery {
+                sensor_id: Some(sensor_id),
+                order: Some("asc".to_string()),
+                bucket: Some(60),
+                agg: Some(agg.to_string()),
+                ..Default::default()
+            };
+            Ok(Reading::get_downsampled(&query)?
+                .iter()
+                .map(|r| (r.bucket_start.timestamp(), r.value, r.count))
+                .collect())
+        };
+        
+        assert_eq!(downsample("avg")?, vec![(6000, 2.75, 4), (6060, 15.0, 2)]);
+        assert_eq!(downsample("min")?, vec![(6000, 1.0, 4), (6060, 10.0, 2)]);
+        assert_eq!(downsample("max")?, vec![(6000, 5.0, 4), (6060, 20.0, 2)]);
+        assert_eq!(downsample("sum")?, vec![(6000, 11.0, 4), (6060, 30.0, 2)]);
+        assert_eq!(downsample("first")?, vec![(6000, 1.0, 4), (6060, 20.0, 2)]);
+        assert_eq!(downsample("last")?, vec![(6000, 5.0, 4), (6060, 10.0, 2)]);
+        
+        let err = downsample("median").unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_rate_of_change_on_ramp() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let other_sensor = create_test_sensor(&conn)?;
+        
+        // Rising 2 units per second, sampled at uneven intervals
+        let reading = |sensor_id, timestamp: i64| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(timestamp as f64 * 2.0),
+            ..Default::default()
+        };
+        Reading::bulk_insert(&[
+            reading(sensor_id, 100),
+            reading(sensor_id, 110),
+            reading(sensor_id, 130),
+            reading(sensor_id, 190),
+            // Interleaved readings from another sensor never pair with this one
+            reading(other_sensor, 120),
+        ])?;
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            order: Some("asc".to_string()),
+            ..Default::default()
+        };
+        let rates: Vec<(i64, f64)> = Reading::get_rate_of_change(&query)?
+            .iter()
+            .map(|r| (r.timestamp.timestamp(), r.rate))
+            .collect();
+        assert_eq!(rates, vec![(110, 2.0), (130, 2.0), (190, 2.0)]);
+        
+        // A single reading has nothing to compare against
+        let query = ReadingQuery { sensor_id: Some(other_sensor), ..Default::default() };
+        assert!(Reading::get_rate_of_change(&query)?.is_empty());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_create_suppresses_changes_within_deadband() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        
+        // No deadband by default, so every reading is stored
+        assert!(reading(100, 20.0).create()?.is_some());
+        assert!(reading(200, 20.0).create()?.is_some());
+        
+        conn.execute("UPDATE sensors SET deadband = 0.5 WHERE sensor_id = ?", [sensor_id])?;
+        
+        assert_eq!(reading(300, 20.3).create()?, None);
+        assert_eq!(reading(400, 19.5).create()?, None);
+        assert!(reading(500, 21.0).create()?.is_some());
+        // Compared against the latest stored value, not the suppressed ones
+        assert_eq!(reading(600, 20.6).create()?, None);
+        assert!(reading(700, 20.4).create()?.is_some());
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            order: Some("asc".to_string()),
+            ..Default::default()
+        };
+        let values: Vec<Option<f64>> = Reading::get(&query)?.iter().map(|r| r.value).collect();
+        assert_eq!(values, vec![Some(20.0), Some(20.0), Some(21.0), Some(20.4)]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_out_of_range_readings_are_flagged_or_rejected() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        conn.execute("UPDATE sensors SET valid_min = -40, valid_max = 85 WHERE sensor_id = ?", [sensor_id])?;
+        
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        
+        // Flagged by default: stored, but marked
+        reading(100, 21.0).create()?;
+        reading(200, 120.0).create()?;
+        Reading::bulk_insert(&[reading(300, -41.0), reading(400, 85.0)])?;
+        
+        let query = ReadingQuery {
+            sensor_id: Some(sensor_id),
+            order: Some("asc".to_string()),
+            ..Default::default()
+        };
+        let flags: Vec<bool> = Reading::get(&query)?.iter().map(|r| r.out_of_range).collect();
+        assert_eq!(flags, vec![false, true, true, false]);
+        
+        config::set_test_config(Config {
+            reject_out_of_range_readings: true,
+            ..Config::default()
+        });
+        
+        let err = reading(500, 85.5).create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        // One bad reading rejects the whole batch
+        let err = Reading::bulk_insert(&[reading(600, 20.0), reading(700, -50.0)]).unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(message)) if message.starts_with("Reading 1 ")));
+        
+        reading(800, -40.0).create()?;
+        Reading::bulk_insert(&[reading(900, 0.0)])?;
+        
+        let flags: Vec<bool> = Reading::get(&query)?.iter().map(|r| r.out_of_range).collect();
+        assert_eq!(flags, vec![false, true, true, false, false, false]);
+        
+        Ok(())
+    }
+}
+
+/// Upper bound on the number of boundaries in a stepped series
+const MAX_STEPPED_POINTS: i64 = 10_000;
+
+/// Upper bound on the number of buckets in a time series
+const MAX_TIME_SERIES_BUCKETS: i64 = 10_000;
+
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
 pub struct Reading {
     pub reading_id: Option<i64>,
-    pub timestamp: Option<i64>,  // Will be set automatically if not provided
+    pub timestamp: Option<i64>,  // Will be set automatically if not provided; milliseconds are converted
     pub sensor_id: i64,
     pub value: Option<f64>,      // For analog sensors
     pub state: Option<i64>,      // For digital/boolean sensors
     pub change_type: Option<String>,
+    pub annotation: Option<String>,  // Free-form note, e.g. 'calibration run'
+}
+
+/// Body of a single reading submission
+///
+/// With auto-provisioning enabled, a reading may name its sensor instead of
+/// giving a `sensor_id`; the sensor is created if no sensor has that name.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingSubmission {
+    pub sensor_id: Option<i64>,
+    pub sensor_name: Option<String>,
+    pub sensor_type: Option<String>,
+    pub timestamp: Option<i64>,
+    pub value: Option<f64>,
+    pub state: Option<i64>,
+    pub change_type: Option<String>,
+    pub annotation: Option<String>,
 }
 
+/// Outcome of a reading submission
 #[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingSubmissionResult {
+    pub reading_id: Option<i64>,  // None when suppressed by the sensor's deadband
+    pub sensor_id: i64,
+    pub sensor_created: bool,
+    pub sensor_status: Option<String>,  // 'healthy', 'warning' or 'critical'; None without a value
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ReadingResponse {
     pub reading_id: i64,
     pub timestamp: DateTime<Utc>,
@@ -24,15 +568,249 @@ pub struct ReadingResponse {
     pub value: Option<f64>,
     pub state: Option<i64>,
     pub change_type: Option<String>,
+    pub annotation: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,  // Only set once the value has been converted
+    #[serde(default)]
+    pub out_of_range: bool,  // Value fell outside the sensor's valid range when stored
 }
 
+/// Current reading of a sensor, or a stand-in built from a caller-supplied default
 #[derive(Debug, Serialize, Deserialize)]
+pub struct CurrentReadingResponse {
+    pub reading_id: Option<i64>,  // None when synthesized
+    pub timestamp: DateTime<Utc>,
+    pub sensor_id: i64,
+    pub value: Option<f64>,
+    pub state: Option<i64>,
+    pub change_type: Option<String>,
+    pub annotation: Option<String>,
+    pub synthesized: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct CurrentReadingQuery {
+    pub default: Option<f64>,  // Value to report when the sensor has no readings
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct ReadingQuery {
     pub sensor_id: Option<i64>,
     pub start_time: Option<i64>,
     pub end_time: Option<i64>,
+    pub exclude_change_type: Option<String>,  // Readings without a change_type are kept
+    pub has_annotation: Option<bool>,  // Only annotated (true) or unannotated (false) readings
+    pub order: Option<String>,  // 'asc' or 'desc' by timestamp (default)
     pub limit: Option<usize>,
     pub offset: Option<usize>,
+    pub after: Option<String>,  // next_cursor of the previous page; excludes offset
+    pub bucket: Option<i64>,    // Downsample into buckets this many seconds wide
+    pub agg: Option<String>,    // 'avg' (default), 'min', 'max', 'sum', 'first' or 'last'
+    pub paginated: Option<bool>,  // Wrap results in a PaginatedReadings
+    pub tail: Option<usize>,    // Most recent N readings, oldest first; excludes limit and offset
+    pub rate: Option<bool>,     // Return the rate of change between consecutive readings
+    pub convert_to: Option<String>,  // Unit to convert values into, e.g. 'F' or 'W'
+}
+
+/// Rate of change between a reading and the previous one from the same sensor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingRate {
+    pub sensor_id: i64,
+    pub timestamp: DateTime<Utc>,  // Timestamp of the later reading
+    pub rate: f64,  // Units per second
+}
+
+/// A page of readings with the total matching the query's filters
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PaginatedReadings {
+    pub readings: Vec<ReadingResponse>,
+    pub total: i64,
+    pub limit: usize,
+    pub offset: usize,
+    pub has_more: bool,
+    pub next_cursor: Option<String>,  // Pass as `after` to fetch the next page; None on the last page
+}
+
+/// One sensor's aggregated value within a downsampling bucket
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DownsampledReading {
+    pub sensor_id: i64,
+    pub bucket_start: DateTime<Utc>,
+    pub value: f64,
+    pub count: i64,  // Numeric readings in the bucket
+}
+
+/// Selection of readings for an export, resumable from a given position
+#[derive(Debug, Default)]
+pub struct ReadingExportRange {
+    pub sensor_ids: Vec<i64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub from_reading_id: Option<i64>,  // Resume after this reading, exclusive
+    pub from_timestamp: Option<i64>,   // Resume at this timestamp, inclusive
+    pub after: Option<(i64, i64)>,     // Resume after this (timestamp, reading_id), exclusive; overrides from_reading_id
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingSpan {
+    pub sensor_id: i64,
+    pub first_ts: Option<i64>,
+    pub last_ts: Option<i64>,
+    pub count: i64,
+}
+
+/// Readings to delete: a time range, or every reading of a sensor with `confirm`
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingDeleteQuery {
+    pub sensor_id: Option<i64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub confirm: Option<bool>,  // Required to delete a sensor's readings without a time range
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingSpanQuery {
+    pub include_empty: Option<bool>,  // Also list sensors without readings
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingAggregateQuery {
+    pub sensor_id: Option<i64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub clip: Option<String>,  // Percentile range such as 'p1,p99'
+}
+
+/// Summary statistics over a sensor's values
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingAggregate {
+    pub sensor_id: i64,
+    pub count: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub avg: Option<f64>,
+    pub clipped_count: usize,  // Values dropped as outliers
+}
+
+/// Bins in a value histogram when the request doesn't say
+pub const DEFAULT_HISTOGRAM_BINS: usize = 10;
+
+/// Upper bound on the number of bins in a value histogram
+pub const MAX_HISTOGRAM_BINS: usize = 1_000;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingHistogramQuery {
+    pub sensor_id: Option<i64>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub bins: Option<usize>,  // Defaults to DEFAULT_HISTOGRAM_BINS
+}
+
+/// Counts of a sensor's values in equal-width bins spanning their min and max
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingHistogram {
+    pub sensor_id: i64,
+    pub count: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub bins: Vec<HistogramBin>,  // Empty without values; a single bin when all values are equal
+}
+
+/// Values from `lower` up to, but excluding, `upper`; the last bin includes `upper`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HistogramBin {
+    pub lower: f64,
+    pub upper: f64,
+    pub count: usize,
+}
+
+/// Distribution of the numeric values matching a `ReadingQuery`
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingStats {
+    pub count: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub std_dev: Option<f64>,  // Population standard deviation
+    pub p50: Option<f64>,      // Percentiles use the nearest-rank method
+    pub p90: Option<f64>,
+    pub p95: Option<f64>,
+    pub p99: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingIntegralQuery {
+    pub start: Option<i64>,
+    pub end: Option<i64>,
+}
+
+/// Trapezoidal integral of a sensor's values over time
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingIntegral {
+    pub sensor_id: i64,
+    pub unit: Option<String>,
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+    pub unit_seconds: f64,  // e.g. kW·s, or L/min·s
+    pub unit_minutes: f64,  // e.g. liters from L/min
+    pub unit_hours: f64,    // e.g. kWh from kW
+    pub segments: usize,    // Runs of consecutive non-null values
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingTypeAggregateQuery {
+    pub interval: Option<String>,  // 'minute', 'hour' (default) or 'day'
+    pub agg: Option<String>,       // 'sum' (default), 'avg', 'min' or 'max'
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+/// Combined value of all sensors of a type within one time bucket
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TypeBucket {
+    pub bucket_start: i64,
+    pub value: f64,
+    pub sensor_count: i64,  // Sensors with readings in this bucket
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingGroupedAggregateQuery {
+    pub sensor_type: Option<String>,
+    pub interval: Option<String>,  // 'minute', 'hour' (default) or 'day'
+    pub agg: Option<String>,       // 'avg' (default), 'sum', 'min' or 'max'
+    pub start_time: Option<i64>,
+    pub end_time: Option<i64>,
+}
+
+/// Each sensor's aggregate within one time bucket, keyed by sensor_id
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GroupedBucket {
+    pub bucket_start: i64,
+    pub values: BTreeMap<i64, Option<f64>>,  // None for sensors without readings in the bucket
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingSteppedQuery {
+    pub interval: Option<i64>,    // Seconds between boundaries
+    pub start_time: Option<i64>,  // First boundary
+    pub end_time: Option<i64>,    // Last boundary, inclusive
+}
+
+/// Last known value of a sensor as of an interval boundary
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SteppedPoint {
+    pub timestamp: i64,
+    pub value: Option<f64>,
+    pub state: Option<i64>,
+}
+
+/// Average of one sensor's values within a time bucket
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BucketAverage {
+    pub sensor_id: i64,
+    pub bucket: i64,  // timestamp / bucket_seconds
+    pub average: f64,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,149 +818,1738 @@ pub struct ReadingBulkInsert {
     pub readings: Vec<Reading>,
 }
 
+#[derive(Debug, Default, Deserialize)]
+pub struct ReadingBulkQuery {
+    pub return_ids: Option<bool>,  // Include the assigned reading_ids in the response
+    pub on_conflict: Option<OnConflict>,  // Defaults to error
+}
+
+/// How a bulk insert treats a reading at an instant its sensor already has one for
+#[derive(Debug, Default, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnConflict {
+    #[default]
+    Error,    // Reject the whole batch
+    Ignore,   // Keep the stored reading and skip the new one
+    Replace,  // Overwrite the stored reading, keeping its reading_id
+}
+
+impl OnConflict {
+    /// Upsert clause appended to the INSERT
+    fn clause(self) -> &'static str {
+        match self {
+            OnConflict::Error => "",
+            OnConflict::Ignore => " ON CONFLICT (sensor_id, timestamp) DO NOTHING",
+            OnConflict::Replace => {
+                " ON CONFLICT (sensor_id, timestamp) DO UPDATE SET
+                    value = excluded.value,
+                    state = excluded.state,
+                    change_type = excluded.change_type,
+                    annotation = excluded.annotation,
+                    out_of_range = excluded.out_of_range"
+            }
+        }
+    }
+}
+
+/// Settings a batch insert takes from the process, read before the batch
+/// is handed to another thread
+#[derive(Debug, Clone, Copy)]
+pub struct BatchSettings {
+    reject_out_of_range: bool,
+    notify: bool,  // Whether breaches are collected for the webhook
+}
+
+impl BatchSettings {
+    pub fn current() -> Self {
+        BatchSettings {
+            reject_out_of_range: config::get().reject_out_of_range_readings,
+            notify: webhook::is_enabled(),
+        }
+    }
+}
+
+/// A committed batch whose caches, ingest count and webhook events are still to be updated
+#[must_use]
+pub struct WrittenBatch {
+    count: usize,
+    ids: Vec<i64>,
+    sensor_ids: HashSet<i64>,
+    breaches: Vec<BreachEvent>,
+}
+
+impl WrittenBatch {
+    /// Publish the batch, returning the number of readings written and their ids
+    pub fn finish(self) -> (usize, Vec<i64>) {
+        for sensor_id in self.sensor_ids {
+            cache::invalidate_sensor(sensor_id);
+        }
+        ingest::record(self.count);
+        
+        for event in self.breaches {
+            webhook::notify(event);
+        }
+        
+        (self.count, self.ids)
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ReadingBulkResponse {
     pub inserted_count: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reading_ids: Option<Vec<i64>>,
+    pub success: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingLineError {
+    pub line: usize,
+    pub error: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadingNdjsonResponse {
+    pub inserted_count: usize,
+    pub first_error_line: Option<usize>,
+    pub errors: Vec<ReadingLineError>,
     pub success: bool,
 }
 
+impl ReadingQuery {
+    /// Rows to return: the requested limit capped at `READINGS_MAX_LIMIT`,
+    /// or `READINGS_DEFAULT_LIMIT` when none was given
+    pub fn effective_limit(&self) -> usize {
+        let config = config::get();
+        self.limit
+            .unwrap_or(config.readings_default_limit)
+            .min(config.readings_max_limit)
+    }
+    
+    /// Whether the requested limit or tail exceeded `READINGS_MAX_LIMIT` and was cut down
+    pub fn limit_clamped(&self) -> bool {
+        let max = config::get().readings_max_limit;
+        self.limit.is_some_and(|limit| limit > max) || self.tail.is_some_and(|tail| tail > max)
+    }
+    
+    /// Whether readings are ordered newest first, the default
+    pub fn descending(&self) -> Result<bool, AppError> {
+        match self.order.as_deref() {
+            Some("asc") => Ok(false),
+            Some("desc") | None => Ok(true),
+            Some(other) => Err(AppError::BadRequest(format!(
+                "Unknown order '{}'; expected asc or desc",
+                other
+            ))),
+        }
+    }
+    
+    /// Reject negative timestamps and ranges that end before they start
+    ///
+    /// Either bound may be left out; a one-sided range is always valid.
+    pub fn validate_time_range(&self) -> Result<(), AppError> {
+        for (name, bound) in [("start_time", self.start_time), ("end_time", self.end_time)] {
+            if let Some(timestamp) = bound.filter(|&timestamp| timestamp < 0) {
+                return Err(AppError::BadRequest(format!("{} must not be negative, got {}", name, timestamp)));
+            }
+        }
+        
+        if let (Some(start_time), Some(end_time)) = (self.start_time, self.end_time) {
+            if start_time > end_time {
+                return Err(AppError::BadRequest(format!(
+                    "start_time must not be later than end_time, got {} > {}",
+                    start_time, end_time
+                )));
+            }
+        }
+        
+        Ok(())
+    }
+}
+
 impl Reading {
     /// Create a new reading
-    pub fn create(&self) -> Result<i64> {
+    ///
+    /// Returns None when the value is within the sensor's deadband of the
+    /// latest stored value, in which case nothing is written.
+    ///
+    /// A sensor holds at most one reading per second, so a reading whose
+    /// timestamp (after millisecond inputs are truncated, or the current
+    /// time when none is given) matches a stored one is a `Conflict`.
+    pub fn create(&self) -> Result<Option<i64>> {
         let conn = get_connection()?;
+        if !Sensor::exists(&conn, self.sensor_id)? {
+            return Err(AppError::NotFound(format!("Sensor {} not found", self.sensor_id)).into());
+        }
+        Sensor::ensure_writable(&conn, self.sensor_id)?;
         
         // Use current time if timestamp is not provided
-        let timestamp = self.timestamp.unwrap_or_else(|| {
+        let timestamp = self.timestamp.map(normalize_timestamp).unwrap_or_else(|| {
             SystemTime::now()
                 .duration_since(UNIX_EPOCH)
                 .expect("Time went backwards")
                 .as_secs() as i64
         });
         
-        let result = conn.execute(
-            "INSERT INTO readings (
-                timestamp, sensor_id, value, state, change_type
-            ) VALUES (?, ?, ?, ?, ?)",
-            params![
-                timestamp,
-                self.sensor_id,
-                self.value,
-                self.state,
-                self.change_type
-            ],
-        )?;
+        if config::get().reject_pre_session_readings {
+            Self::ensure_within_session(&conn, self.sensor_id, timestamp)?;
+        }
+        
+        let out_of_range = match Self::range_violation(Self::valid_range(&conn, self.sensor_id)?, self.value) {
+            Some(violation) if config::get().reject_out_of_range_readings => {
+                return Err(AppError::BadRequest(format!("Reading value {}", violation)).into());
+            }
+            violation => violation.is_some(),
+        };
+        
+        if let Some(value) = self.value {
+            if Self::within_deadband(&conn, self.sensor_id, value)? {
+                return Ok(None);
+            }
+        }
+        
+        let result = conn
+            .execute(
+                "INSERT INTO readings (
+                    timestamp, sensor_id, value, state, change_type, annotation, out_of_range
+                ) VALUES (?, ?, ?, ?, ?, ?, ?)",
+                params![
+                    timestamp,
+                    self.sensor_id,
+                    self.value,
+                    self.state,
+                    self.change_type,
+                    self.annotation,
+                    out_of_range
+                ],
+            )
+            .map_err(|err| {
+                if Self::is_duplicate(&err) {
+                    AppError::Conflict(format!("Sensor {} already has a reading at {}", self.sensor_id, timestamp))
+                } else {
+                    AppError::Database(err)
+                }
+            })?;
         
         if result == 0 {
             return Err(anyhow::anyhow!("Failed to create reading"));
         }
         
-        let id = conn.last_insert_rowid();
-        Ok(id)
+        let id = conn.last_insert_rowid();
+        cache::invalidate_sensor(self.sensor_id);
+        ingest::record(1);
+        
+        if webhook::is_enabled() {
+            if let Some(event) = Self::check_thresholds(&conn, &mut HashMap::new(), id, timestamp, self.sensor_id, self.value)? {
+                webhook::notify(event);
+            }
+        }
+        
+        Ok(Some(id))
+    }
+    
+    /// Whether `value` is too close to the sensor's latest stored value to be worth keeping
+    fn within_deadband(conn: &Connection, sensor_id: i64, value: f64) -> Result<bool> {
+        let deadband: Option<f64> = conn
+            .query_row("SELECT deadband FROM sensors WHERE sensor_id = ?", params![sensor_id], |row| row.get(0))
+            .optional()?
+            .flatten();
+        
+        let Some(deadband) = deadband else {
+            return Ok(false);
+        };
+        
+        let last: Option<f64> = conn
+            .query_row(
+                "SELECT value FROM readings
+                 WHERE sensor_id = ? AND value IS NOT NULL
+                 ORDER BY timestamp DESC, reading_id DESC
+                 LIMIT 1",
+                params![sensor_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        Ok(last.is_some_and(|last| (value - last).abs() <= deadband))
+    }
+    
+    /// The sensor's (valid_min, valid_max) range
+    fn valid_range(conn: &Connection, sensor_id: i64) -> Result<(Option<f64>, Option<f64>)> {
+        let range = conn.query_row(
+            "SELECT valid_min, valid_max FROM sensors WHERE sensor_id = ?",
+            params![sensor_id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        
+        Ok(range)
+    }
+    
+    /// Describe how `value` falls outside `range`, or None when it is within it
+    fn range_violation((valid_min, valid_max): (Option<f64>, Option<f64>), value: Option<f64>) -> Option<String> {
+        let value = value?;
+        
+        match (valid_min, valid_max) {
+            (Some(min), _) if value < min => Some(format!("{} is below the sensor's valid_min {}", value, min)),
+            (_, Some(max)) if value > max => Some(format!("{} is above the sensor's valid_max {}", value, max)),
+            _ => None,
+        }
+    }
+    
+    /// Reject a timestamp earlier than the start of the sensor's active session, if any
+    fn ensure_within_session(conn: &Connection, sensor_id: i64, timestamp: i64) -> Result<()> {
+        if let Some(session) = LoggingSession::get_active_with(conn, sensor_id)? {
+            let start_time = session.start_time.timestamp();
+            if timestamp < start_time {
+                return Err(AppError::BadRequest(format!(
+                    "Reading timestamp {} is before the active session started at {}",
+                    timestamp, start_time
+                ))
+                .into());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Create a reading, provisioning its sensor by name when allowed
+    pub fn submit(submission: ReadingSubmission) -> Result<ReadingSubmissionResult> {
+        let (sensor_id, sensor_created) = match submission.sensor_id {
+            Some(sensor_id) => (sensor_id, false),
+            None => Self::provision_sensor(&submission)?,
+        };
+        
+        let reading = Reading {
+            reading_id: None,
+            timestamp: submission.timestamp,
+            sensor_id,
+            value: submission.value,
+            state: submission.state,
+            change_type: submission.change_type,
+            annotation: submission.annotation,
+        };
+        let reading_id = reading.create()?;
+        
+        // Saves clients a status call straight after submitting
+        let sensor_status = match submission.value {
+            Some(value) => {
+                let sensor = Sensor::get_by_id(sensor_id)?;
+                Some(classify_value(value, sensor.threshold_min, sensor.threshold_max).to_string())
+            }
+            None => None,
+        };
+        
+        Ok(ReadingSubmissionResult {
+            reading_id,
+            sensor_id,
+            sensor_created,
+            sensor_status,
+        })
+    }
+    
+    /// Find the sensor a submission names, creating it if there is none
+    fn provision_sensor(submission: &ReadingSubmission) -> Result<(i64, bool)> {
+        if !config::get().auto_provision_sensors {
+            return Err(AppError::BadRequest("sensor_id is required".to_string()).into());
+        }
+        
+        let (Some(sensor_name), Some(sensor_type)) = (&submission.sensor_name, &submission.sensor_type) else {
+            return Err(AppError::BadRequest(
+                "sensor_id, or sensor_name and sensor_type, are required".to_string(),
+            )
+            .into());
+        };
+        
+        if sensor_name.trim().is_empty() {
+            return Err(AppError::BadRequest("sensor_name must not be empty".to_string()).into());
+        }
+        
+        let existing: Option<i64> = get_connection()?
+            .query_row(
+                "SELECT sensor_id FROM sensors WHERE sensor_name = ? ORDER BY sensor_id LIMIT 1",
+                params![sensor_name],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        if let Some(sensor_id) = existing {
+            return Ok((sensor_id, false));
+        }
+        
+        let sensor = Sensor {
+            sensor_id: None,
+            sensor_name: sensor_name.clone(),
+            sensor_type: sensor_type.clone(),
+            location: None,
+            unit: None,
+            threshold_min: None,
+            threshold_max: None,
+            calibration_date: None,
+            notes: Some("Auto-provisioned from an incoming reading".to_string()),
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
+            created_at: None,
+            updated_at: None,
+        };
+        let sensor_id = sensor.create()?;
+        tracing::info!("Auto-provisioned sensor {} ('{}')", sensor_id, sensor_name);
+        
+        Ok((sensor_id, true))
+    }
+    
+    /// Whether an insert failed because the sensor already has a reading at that instant
+    fn is_duplicate(err: &rusqlite::Error) -> bool {
+        err.sqlite_error_code() == Some(ErrorCode::ConstraintViolation) && err.to_string().contains("UNIQUE")
+    }
+    
+    /// Bulk insert readings
+    #[allow(dead_code)]
+    pub fn bulk_insert(readings: &[Reading]) -> Result<usize> {
+        let (count, _) = Self::insert_batch(readings, OnConflict::Error, false)?;
+        Ok(count)
+    }
+    
+    /// Bulk insert readings, resolving clashes with stored readings as `on_conflict` says
+    ///
+    /// Returns the number of readings written, replacements included, and
+    /// with `return_ids` their ids in input order. Ignored readings appear
+    /// in neither. The ids come back from the INSERT itself, which costs no
+    /// extra query, but the id list grows with the batch, so leave
+    /// `return_ids` off when only the count is needed.
+    pub fn bulk_upsert(readings: &[Reading], on_conflict: OnConflict, return_ids: bool) -> Result<(usize, Vec<i64>)> {
+        Self::insert_batch(readings, on_conflict, return_ids)
+    }
+    
+    /// Bulk insert readings on a connection the caller already holds
+    ///
+    /// Only the connection is used, so this can run on a blocking thread;
+    /// `finish` the result on the thread that took `settings`.
+    pub fn bulk_insert_with(conn: &mut Connection, readings: &[Reading], settings: BatchSettings) -> Result<WrittenBatch> {
+        Self::write_batch(conn, readings, OnConflict::Error, false, settings)
+    }
+    
+    /// Insert readings in one transaction, optionally collecting their ids
+    fn insert_batch(readings: &[Reading], on_conflict: OnConflict, return_ids: bool) -> Result<(usize, Vec<i64>)> {
+        let mut conn = get_connection()?;
+        let written = Self::write_batch(&mut conn, readings, on_conflict, return_ids, BatchSettings::current())?;
+        
+        Ok(written.finish())
+    }
+    
+    /// Write readings in one transaction, leaving the bookkeeping to `WrittenBatch::finish`
+    fn write_batch(
+        conn: &mut Connection,
+        readings: &[Reading],
+        on_conflict: OnConflict,
+        return_ids: bool,
+        settings: BatchSettings,
+    ) -> Result<WrittenBatch> {
+        let tx = conn.transaction()?;
+        
+        // Readings are named by their index, unless there is only the one
+        let name = |index: usize| if readings.len() == 1 { "Reading".to_string() } else { format!("Reading {}", index) };
+        
+        // Check each sensor once, naming the first reading that points at a missing one
+        let mut sensor_ids = HashSet::new();
+        let mut ranges = HashMap::new();
+        for (index, reading) in readings.iter().enumerate() {
+            if sensor_ids.insert(reading.sensor_id) {
+                if !Sensor::exists(&tx, reading.sensor_id)? {
+                    return Err(AppError::NotFound(format!(
+                        "{} references sensor {}, which does not exist",
+                        name(index), reading.sensor_id
+                    ))
+                    .into());
+                }
+                Sensor::ensure_writable(&tx, reading.sensor_id)?;
+                ranges.insert(reading.sensor_id, Self::valid_range(&tx, reading.sensor_id)?);
+            }
+        }
+        
+        // Resolve range checks up front so a rejected batch writes nothing
+        let mut flags = Vec::with_capacity(readings.len());
+        for (index, reading) in readings.iter().enumerate() {
+            match Self::range_violation(ranges[&reading.sensor_id], reading.value) {
+                Some(violation) if settings.reject_out_of_range => {
+                    return Err(AppError::BadRequest(format!("{} value {}", name(index), violation)).into());
+                }
+                violation => flags.push(violation.is_some()),
+            }
+        }
+        
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("Time went backwards")?
+            .as_secs() as i64;
+        
+        let mut stmt = tx.prepare(&format!(
+            "INSERT INTO readings (
+                timestamp, sensor_id, value, state, change_type, annotation, out_of_range
+            ) VALUES (?, ?, ?, ?, ?, ?, ?){}
+            RETURNING reading_id",
+            on_conflict.clause()
+        ))?;
+        
+        let mut count = 0;
+        let mut ids = Vec::new();
+        let mut inserted = Vec::new();
+        
+        for (index, (reading, out_of_range)) in readings.iter().zip(flags).enumerate() {
+            // Use current time if timestamp is not provided
+            let timestamp = reading.timestamp.map(normalize_timestamp).unwrap_or(now);
+            
+            let written: Option<i64> = stmt
+                .query_row(
+                    params![
+                        timestamp,
+                        reading.sensor_id,
+                        reading.value,
+                        reading.state,
+                        reading.change_type,
+                        reading.annotation,
+                        out_of_range
+                    ],
+                    |row| row.get(0),
+                )
+                .optional()
+                .map_err(|err| {
+                    if Self::is_duplicate(&err) {
+                        AppError::Conflict(format!(
+                            "{} duplicates sensor {}'s reading at {}; set on_conflict to ignore or replace it",
+                            name(index), reading.sensor_id, timestamp
+                        ))
+                    } else {
+                        AppError::Database(err)
+                    }
+                })?;
+            
+            // Nothing comes back for a reading skipped by on_conflict=ignore
+            let Some(reading_id) = written else {
+                continue;
+            };
+            
+            if return_ids {
+                ids.push(reading_id);
+            }
+            
+            if settings.notify && reading.value.is_some() {
+                inserted.push((reading_id, timestamp, reading.sensor_id, reading.value));
+            }
+            
+            count += 1;
+        }
+        
+        drop(stmt);
+        tx.commit()?;
+        
+        let mut breaches = Vec::new();
+        let mut thresholds = HashMap::new();
+        for (reading_id, timestamp, sensor_id, value) in inserted {
+            breaches.extend(Self::check_thresholds(conn, &mut thresholds, reading_id, timestamp, sensor_id, value)?);
+        }
+        
+        Ok(WrittenBatch { count, ids, sensor_ids, breaches })
+    }
+    
+    /// The webhook event for a reading that breaches its sensor's thresholds, if any
+    ///
+    /// `thresholds` caches each sensor's (min, max) across calls.
+    fn check_thresholds(
+        conn: &Connection,
+        thresholds: &mut HashMap<i64, (Option<f64>, Option<f64>)>,
+        reading_id: i64,
+        timestamp: i64,
+        sensor_id: i64,
+        value: Option<f64>,
+    ) -> Result<Option<BreachEvent>> {
+        let Some(value) = value else {
+            return Ok(None);
+        };
+        
+        let (threshold_min, threshold_max) = match thresholds.get(&sensor_id) {
+            Some(&cached) => cached,
+            None => {
+                let fetched = conn.query_row(
+                    "SELECT threshold_min, threshold_max FROM sensors WHERE sensor_id = ?",
+                    params![sensor_id],
+                    |row| Ok((row.get(0)?, row.get(1)?)),
+                )?;
+                *thresholds.entry(sensor_id).or_insert(fetched)
+            }
+        };
+        
+        Ok(BreachEvent::check(sensor_id, reading_id, timestamp, value, threshold_min, threshold_max))
+    }
+    
+    /// Get readings by id, in id order; unknown ids are skipped
+    pub fn get_by_ids(ids: &[i64]) -> Result<Vec<ReadingResponse>> {
+        let conn = get_connection()?;
+        let mut readings = Vec::with_capacity(ids.len());
+        
+        // Stay well under SQLite's bound parameter limit
+        for chunk in ids.chunks(500) {
+            let placeholders = vec!["?"; chunk.len()].join(", ");
+            let mut stmt = conn.prepare(&format!(
+                "SELECT * FROM readings WHERE reading_id IN ({}) ORDER BY reading_id",
+                placeholders
+            ))?;
+            let rows = stmt.query_map(rusqlite::params_from_iter(chunk.iter()), Self::from_row)?;
+            for row in rows {
+                readings.push(row?);
+            }
+        }
+        
+        Ok(readings)
+    }
+    
+    /// Get readings based on query parameters
+    pub fn get(query: &ReadingQuery) -> Result<Vec<ReadingResponse>> {
+        if let Some(tail) = query.tail {
+            return Self::get_tail(query, tail);
+        }
+        
+        let conn = get_connection()?;
+        
+        let (filters, mut params) = Self::build_filters(query);
+        let mut sql = format!("SELECT * FROM readings WHERE 1=1{}", filters);
+        let descending = query.descending()?;
+        
+        if let Some(ref cursor) = query.after {
+            if query.offset.is_some() {
+                return Err(AppError::BadRequest("after can't be combined with offset".to_string()).into());
+            }
+            let (timestamp, reading_id) = Self::decode_cursor(cursor)?;
+            sql.push_str(Self::cursor_filter(descending));
+            params.push(Value::Integer(timestamp));
+            params.push(Value::Integer(reading_id));
+        }
+        
+        // reading_id breaks timestamp ties, so cursors see a stable order
+        if descending {
+            sql.push_str(" ORDER BY timestamp DESC, reading_id DESC");
+        } else {
+            sql.push_str(" ORDER BY timestamp ASC, reading_id ASC");
+        }
+        
+        let limit = query.effective_limit();
+        sql.push_str(" LIMIT ?");
+        params.push(Value::Integer(limit as i64));
+        
+        if let Some(offset) = query.offset {
+            sql.push_str(" OFFSET ?");
+            params.push(Value::Integer(offset as i64));
+        }
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let reading_iter = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+            Self::from_row(row)
+        })?;
+        
+        let mut readings = Vec::new();
+        for reading in reading_iter {
+            readings.push(reading?);
+        }
+        
+        Ok(readings)
+    }
+    
+    /// Filter keeping readings that come after a cursor in the given order
+    fn cursor_filter(descending: bool) -> &'static str {
+        if descending {
+            " AND (timestamp, reading_id) < (?, ?)"
+        } else {
+            " AND (timestamp, reading_id) > (?, ?)"
+        }
+    }
+    
+    /// Cursor pointing just past `reading`
+    ///
+    /// Clients should treat it as opaque; it encodes the reading's
+    /// timestamp and id as `<timestamp>-<reading_id>`.
+    fn encode_cursor(reading: &ReadingResponse) -> String {
+        format!("{}-{}", reading.timestamp.timestamp(), reading.reading_id)
+    }
+    
+    fn decode_cursor(cursor: &str) -> Result<(i64, i64), AppError> {
+        cursor
+            .split_once('-')
+            .and_then(|(timestamp, reading_id)| Some((timestamp.parse().ok()?, reading_id.parse().ok()?)))
+            .ok_or_else(|| AppError::BadRequest(format!("Invalid cursor '{}'", cursor)))
+    }
+    
+    /// Get the most recent `tail` readings matching the filters, oldest first
+    ///
+    /// The window always ends at the newest reading, so `limit` and `offset`
+    /// have no sensible meaning here and are rejected.
+    fn get_tail(query: &ReadingQuery, tail: usize) -> Result<Vec<ReadingResponse>> {
+        if query.limit.is_some() || query.offset.is_some() {
+            return Err(AppError::BadRequest("tail can't be combined with limit or offset".to_string()).into());
+        }
+        
+        let conn = get_connection()?;
+        
+        let (filters, mut params) = Self::build_filters(query);
+        let sql = format!(
+            "SELECT * FROM (
+                SELECT * FROM readings WHERE 1=1{} ORDER BY timestamp DESC LIMIT ?
+             ) ORDER BY timestamp ASC",
+            filters
+        );
+        params.push(Value::Integer(tail.min(config::get().readings_max_limit) as i64));
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let readings = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), Self::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(readings)
+    }
+    
+    /// Convert values into `unit`, starting from each reading's sensor unit
+    ///
+    /// Every converted reading reports `unit`. A sensor without a unit, or
+    /// with one that can't become `unit`, fails the whole request.
+    pub fn convert_units(readings: &mut [ReadingResponse], unit: &str) -> Result<()> {
+        let conn = get_connection()?;
+        let mut conversions: HashMap<i64, units::Conversion> = HashMap::new();
+        
+        for reading in readings.iter_mut() {
+            let conversion = match conversions.get(&reading.sensor_id) {
+                Some(conversion) => *conversion,
+                None => {
+                    let sensor = Sensor::get_by_id_with(&conn, reading.sensor_id)?;
+                    let from = sensor.unit.ok_or_else(|| {
+                        AppError::BadRequest(format!("Sensor {} has no unit to convert from", reading.sensor_id))
+                    })?;
+                    let conversion = units::conversion(&from, unit)?;
+                    conversions.insert(reading.sensor_id, conversion);
+                    conversion
+                }
+            };
+            
+            reading.value = reading.value.map(|value| conversion.apply(value));
+            reading.unit = Some(unit.to_string());
+        }
+        
+        Ok(())
+    }
+    
+    /// Get a page of readings along with the number matching the filters
+    pub fn get_page(query: &ReadingQuery) -> Result<PaginatedReadings> {
+        let readings = Self::get(query)?;
+        
+        let conn = get_connection()?;
+        let (filters, params) = Self::build_filters(query);
+        let total: i64 = conn.query_row(
+            &format!("SELECT COUNT(*) FROM readings WHERE 1=1{}", filters),
+            rusqlite::params_from_iter(params.iter()),
+            |row| row.get(0),
+        )?;
+        
+        let limit = query.effective_limit();
+        let offset = query.offset.unwrap_or(0);
+        
+        // A cursor hides how many readings came before it, so look past the page instead
+        let has_more = match (&query.after, readings.last()) {
+            (None, _) => ((offset + readings.len()) as i64) < total,
+            (Some(_), None) => false,
+            (Some(_), Some(last)) => {
+                let mut params = params;
+                params.push(Value::Integer(last.timestamp.timestamp()));
+                params.push(Value::Integer(last.reading_id));
+                conn.query_row(
+                    &format!(
+                        "SELECT EXISTS(SELECT 1 FROM readings WHERE 1=1{}{})",
+                        filters,
+                        Self::cursor_filter(query.descending()?)
+                    ),
+                    rusqlite::params_from_iter(params.iter()),
+                    |row| row.get(0),
+                )?
+            }
+        };
+        let next_cursor = readings.last().filter(|_| has_more).map(Self::encode_cursor);
+        
+        Ok(PaginatedReadings {
+            has_more,
+            readings,
+            total,
+            limit,
+            offset,
+            next_cursor,
+        })
+    }
+    
+    /// Get readings aggregated per sensor into `bucket` second wide buckets
+    ///
+    /// Buckets are aligned to the epoch and only numeric readings count.
+    /// `limit` and `offset` apply to buckets rather than raw readings.
+    pub fn get_downsampled(query: &ReadingQuery) -> Result<Vec<DownsampledReading>> {
+        let bucket = query.bucket
+            .ok_or_else(|| AppError::BadRequest("bucket is required".to_string()))?;
+        if bucket <= 0 {
+            return Err(AppError::BadRequest("bucket must be a positive number of seconds".to_string()).into());
+        }
+        
+        let order = match query.order.as_deref() {
+            Some("asc") => "ASC",
+            Some("desc") | None => "DESC",
+            Some(other) => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown order '{}'; expected asc or desc",
+                    other
+                ))
+                .into());
+            }
+        };
+        
+        let (filters, filter_params) = Self::build_filters(query);
+        let mut params = vec![Value::Integer(bucket), Value::Integer(bucket)];
+        
+        let buckets = match query.agg.as_deref().unwrap_or("avg") {
+            agg @ ("avg" | "min" | "max" | "sum") => format!(
+                "SELECT sensor_id, timestamp / ? AS bucket, {}(value) AS value, COUNT(*) AS count
+                 FROM readings
+                 WHERE value IS NOT NULL{}
+                 GROUP BY sensor_id, bucket",
+                agg.to_uppercase(),
+                filters
+            ),
+            agg @ ("first" | "last") => {
+                // Rank by timestamp, then reading_id, so row order never picks the value
+                let direction = if agg == "first" { "ASC" } else { "DESC" };
+                params.push(Value::Integer(bucket));
+                params.push(Value::Integer(bucket));
+                format!(
+                    "SELECT sensor_id, bucket, value, count FROM (
+                        SELECT sensor_id, timestamp / ? AS bucket, value,
+                               ROW_NUMBER() OVER (
+                                   PARTITION BY sensor_id, timestamp / ?
+                                   ORDER BY timestamp {direction}, reading_id {direction}
+                               ) AS position,
+                               COUNT(*) OVER (PARTITION BY sensor_id, timestamp / ?) AS count
+                        FROM readings
+                        WHERE value IS NOT NULL{filters}
+                     )
+                     WHERE position = 1"
+                )
+            }
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown agg '{}'; expected avg, min, max, sum, first or last",
+                    other
+                ))
+                .into());
+            }
+        };
+        params.extend(filter_params);
+        
+        let mut sql = format!(
+            "SELECT sensor_id, bucket * ? AS bucket_start, value, count FROM ({})
+             ORDER BY bucket {}, sensor_id",
+            buckets, order
+        );
+        
+        let limit = query.effective_limit();
+        sql.push_str(" LIMIT ?");
+        params.push(Value::Integer(limit as i64));
+        
+        if let Some(offset) = query.offset {
+            sql.push_str(" OFFSET ?");
+            params.push(Value::Integer(offset as i64));
+        }
+        
+        let conn = get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let readings = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok(DownsampledReading {
+                    sensor_id: row.get("sensor_id")?,
+                    bucket_start: get_datetime(row, "bucket_start")?,
+                    value: row.get("value")?,
+                    count: row.get("count")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(readings)
     }
     
-    /// Bulk insert readings
-    pub fn bulk_insert(readings: &[Reading]) -> Result<usize> {
-        let mut conn = get_connection()?;
-        let tx = conn.transaction()?;
+    /// Get the first derivative of each sensor's values, in units per second
+    ///
+    /// Each point compares a reading with the sensor's previous reading.
+    /// Pairs where either reading has no value are skipped. `limit` and
+    /// `offset` apply to the rates rather than the raw readings.
+    pub fn get_rate_of_change(query: &ReadingQuery) -> Result<Vec<ReadingRate>> {
+        let order = match query.order.as_deref() {
+            Some("asc") => "ASC",
+            Some("desc") | None => "DESC",
+            Some(other) => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown order '{}'; expected asc or desc",
+                    other
+                ))
+                .into());
+            }
+        };
         
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .context("Time went backwards")?
-            .as_secs() as i64;
+        let (filters, mut params) = Self::build_filters(query);
         
-        let mut stmt = tx.prepare(
-            "INSERT INTO readings (
-                timestamp, sensor_id, value, state, change_type
-            ) VALUES (?, ?, ?, ?, ?)"
-        )?;
+        // A sensor has one reading per timestamp, so every delta is positive
+        let mut sql = format!(
+            "SELECT sensor_id, timestamp,
+                    (value - previous_value) / CAST(timestamp - previous_timestamp AS REAL) AS rate
+             FROM (
+                SELECT sensor_id, timestamp, value,
+                       LAG(timestamp) OVER sensor_window AS previous_timestamp,
+                       LAG(value) OVER sensor_window AS previous_value
+                FROM readings
+                WHERE 1=1{}
+                WINDOW sensor_window AS (PARTITION BY sensor_id ORDER BY timestamp)
+             )
+             WHERE value IS NOT NULL
+               AND previous_value IS NOT NULL
+             ORDER BY timestamp {}, sensor_id",
+            filters, order
+        );
         
-        let mut count = 0;
+        let limit = query.effective_limit();
+        sql.push_str(" LIMIT ?");
+        params.push(Value::Integer(limit as i64));
         
-        for reading in readings {
-            // Use current time if timestamp is not provided
-            let timestamp = reading.timestamp.unwrap_or(now);
-            
-            stmt.execute(params![
-                timestamp,
-                reading.sensor_id,
-                reading.value,
-                reading.state,
-                reading.change_type
-            ])?;
-            
-            count += 1;
+        if let Some(offset) = query.offset {
+            sql.push_str(" OFFSET ?");
+            params.push(Value::Integer(offset as i64));
         }
         
-        tx.commit()?;
+        let conn = get_connection()?;
+        let mut stmt = conn.prepare(&sql)?;
+        let rates = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok(ReadingRate {
+                    sensor_id: row.get("sensor_id")?,
+                    timestamp: get_datetime(row, "timestamp")?,
+                    rate: row.get("rate")?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
         
-        Ok(count)
+        Ok(rates)
     }
     
-    /// Get readings based on query parameters
-    pub fn get(query: &ReadingQuery) -> Result<Vec<ReadingResponse>> {
+    /// Get readings for an export in stable (timestamp, reading_id) order
+    ///
+    /// The ordering is total, so a client can resume an interrupted export
+    /// by passing the last reading_id it received as `from_reading_id`.
+    /// Callers paging through an export pass the last position as `after`
+    /// instead, which keeps working if that reading is deleted meanwhile.
+    pub fn export(range: &ReadingExportRange) -> Result<Vec<ReadingResponse>> {
         let conn = get_connection()?;
         
         let mut sql = String::from("SELECT * FROM readings WHERE 1=1");
+        let mut params: Vec<Value> = Vec::new();
+        
+        if !range.sensor_ids.is_empty() {
+            let placeholders = vec!["?"; range.sensor_ids.len()].join(", ");
+            sql.push_str(&format!(" AND sensor_id IN ({})", placeholders));
+            params.extend(range.sensor_ids.iter().map(|&id| Value::Integer(id)));
+        }
+        
+        if let Some(start_time) = range.start_time {
+            sql.push_str(" AND timestamp >= ?");
+            params.push(Value::Integer(start_time));
+        }
+        
+        if let Some(end_time) = range.end_time {
+            sql.push_str(" AND timestamp <= ?");
+            params.push(Value::Integer(end_time));
+        }
+        
+        if let Some(from_timestamp) = range.from_timestamp {
+            sql.push_str(" AND timestamp >= ?");
+            params.push(Value::Integer(from_timestamp));
+        }
+        
+        let after = match (range.after, range.from_reading_id) {
+            (Some(position), _) => Some(position),
+            (None, Some(from_reading_id)) => {
+                let from_timestamp: i64 = conn
+                    .query_row(
+                        "SELECT timestamp FROM readings WHERE reading_id = ?",
+                        params![from_reading_id],
+                        |row| row.get(0),
+                    )
+                    .map_err(|err| match err {
+                        rusqlite::Error::QueryReturnedNoRows => anyhow::Error::from(AppError::BadRequest(
+                            format!("from_reading_id {} does not exist", from_reading_id),
+                        )),
+                        err => err.into(),
+                    })?;
+                Some((from_timestamp, from_reading_id))
+            },
+            (None, None) => None,
+        };
+        
+        if let Some((timestamp, reading_id)) = after {
+            sql.push_str(" AND (timestamp, reading_id) > (?, ?)");
+            params.push(Value::Integer(timestamp));
+            params.push(Value::Integer(reading_id));
+        }
+        
+        sql.push_str(" ORDER BY timestamp, reading_id");
+        
+        if let Some(limit) = range.limit {
+            sql.push_str(" LIMIT ?");
+            params.push(Value::Integer(limit as i64));
+        }
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let readings = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), Self::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(readings)
+    }
+    
+    /// Build the filter conditions for a readings query
+    ///
+    /// Parameters are bound with their SQL types so integer columns are
+    /// compared as integers and can use the readings indices.
+    fn build_filters(query: &ReadingQuery) -> (String, Vec<Value>) {
+        let mut sql = String::new();
         let mut params = Vec::new();
         
         if let Some(sensor_id) = query.sensor_id {
             sql.push_str(" AND sensor_id = ?");
-            params.push(sensor_id.to_string());
+            params.push(Value::Integer(sensor_id));
         }
         
         if let Some(start_time) = query.start_time {
             sql.push_str(" AND timestamp >= ?");
-            params.push(start_time.to_string());
+            params.push(Value::Integer(start_time));
         }
         
         if let Some(end_time) = query.end_time {
             sql.push_str(" AND timestamp <= ?");
-            params.push(end_time.to_string());
+            params.push(Value::Integer(end_time));
         }
         
-        sql.push_str(" ORDER BY timestamp DESC");
+        if let Some(ref change_type) = query.exclude_change_type {
+            // A bare != would also drop NULLs, which never compare equal or unequal
+            sql.push_str(" AND (change_type IS NULL OR change_type != ?)");
+            params.push(Value::Text(change_type.to_string()));
+        }
         
-        if let Some(limit) = query.limit {
-            sql.push_str(" LIMIT ?");
-            params.push(limit.to_string());
-        } else {
-            sql.push_str(" LIMIT 1000"); // Default limit
+        match query.has_annotation {
+            Some(true) => sql.push_str(" AND annotation IS NOT NULL"),
+            Some(false) => sql.push_str(" AND annotation IS NULL"),
+            None => {}
         }
         
-        if let Some(offset) = query.offset {
-            sql.push_str(" OFFSET ?");
-            params.push(offset.to_string());
+        (sql, params)
+    }
+    
+    /// Get the current reading for a sensor
+    #[allow(dead_code)]
+    pub fn get_current(sensor_id: i64) -> Result<ReadingResponse> {
+        let conn = get_connection()?;
+        
+        let reading = Self::get_current_with(&conn, sensor_id)?
+            .ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+        
+        Ok(reading)
+    }
+    
+    /// Get the current reading for a sensor (if any) on a connection the caller already holds
+    pub fn get_current_with(conn: &Connection, sensor_id: i64) -> Result<Option<ReadingResponse>> {
+        let reading = conn
+            .query_row(
+                "SELECT * FROM readings 
+                 WHERE sensor_id = ? 
+                 ORDER BY timestamp DESC 
+                 LIMIT 1",
+                params![sensor_id],
+                Self::from_row,
+            )
+            .optional()?;
+        
+        Ok(reading)
+    }
+    
+    /// Get the current reading of every sensor that has one, in sensor order
+    ///
+    /// One query for all sensors, so dashboards don't need a request per sensor.
+    /// Readings sharing the newest timestamp resolve to the last one stored.
+    pub fn get_current_for_all() -> Result<Vec<ReadingResponse>> {
+        let conn = get_connection()?;
+        
+        let mut stmt = conn.prepare(
+            "SELECT * FROM (
+                SELECT *, ROW_NUMBER() OVER (
+                    PARTITION BY sensor_id
+                    ORDER BY timestamp DESC, reading_id DESC
+                ) AS position
+                FROM readings
+             )
+             WHERE position = 1
+             ORDER BY sensor_id"
+        )?;
+        let readings = stmt
+            .query_map([], Self::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(readings)
+    }
+    
+    /// Get the current reading for a sensor, falling back to a default value
+    ///
+    /// Without a default, a sensor with no readings is reported as not found.
+    /// Unknown sensors are not found either way.
+    pub fn get_current_or_default(sensor_id: i64, default: Option<f64>) -> Result<CurrentReadingResponse> {
+        let conn = get_connection()?;
+        
+        if let Some(reading) = Self::get_current_with(&conn, sensor_id)? {
+            return Ok(CurrentReadingResponse {
+                reading_id: Some(reading.reading_id),
+                timestamp: reading.timestamp,
+                sensor_id: reading.sensor_id,
+                value: reading.value,
+                state: reading.state,
+                change_type: reading.change_type,
+                annotation: reading.annotation,
+                synthesized: false,
+            });
+        }
+        
+        let Some(default) = default else {
+            return Err(rusqlite::Error::QueryReturnedNoRows.into());
+        };
+        
+        Sensor::get_by_id_with(&conn, sensor_id)?;
+        
+        Ok(CurrentReadingResponse {
+            reading_id: None,
+            timestamp: Utc::now(),
+            sensor_id,
+            value: Some(default),
+            state: None,
+            change_type: None,
+            annotation: None,
+            synthesized: true,
+        })
+    }
+    
+    /// Get the first and last reading timestamps and reading count per sensor
+    pub fn spans(include_empty: bool) -> Result<Vec<ReadingSpan>> {
+        let conn = get_connection()?;
+        
+        let mut sql = String::from(
+            "SELECT s.sensor_id, MIN(r.timestamp), MAX(r.timestamp), COUNT(r.reading_id)
+             FROM sensors s
+             LEFT JOIN readings r ON r.sensor_id = s.sensor_id
+             GROUP BY s.sensor_id"
+        );
+        
+        if !include_empty {
+            sql.push_str(" HAVING COUNT(r.reading_id) > 0");
         }
         
+        sql.push_str(" ORDER BY s.sensor_id");
+        
         let mut stmt = conn.prepare(&sql)?;
-        let reading_iter = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
-            Self::from_row(row)
+        let spans = stmt
+            .query_map([], |row| {
+                Ok(ReadingSpan {
+                    sensor_id: row.get(0)?,
+                    first_ts: row.get(1)?,
+                    last_ts: row.get(2)?,
+                    count: row.get(3)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(spans)
+    }
+    
+    /// Aggregate a sensor's values over a time range
+    ///
+    /// With `clip`, values outside the (low, high) percentiles are dropped
+    /// before aggregating, so a few garbage spikes can't skew the average.
+    pub fn aggregate(
+        sensor_id: i64,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        clip: Option<(f64, f64)>,
+    ) -> Result<ReadingAggregate> {
+        let conn = get_connection()?;
+        
+        let mut values: Vec<f64> = {
+            let mut stmt = conn.prepare(
+                "SELECT value FROM readings
+                 WHERE sensor_id = ? AND value IS NOT NULL
+                   AND timestamp >= COALESCE(?, timestamp)
+                   AND timestamp <= COALESCE(?, timestamp)"
+            )?;
+            let rows = stmt.query_map(params![sensor_id, start_time, end_time], |row| row.get(0))?;
+            rows.collect::<Result<_, _>>()?
+        };
+        values.sort_by(f64::total_cmp);
+        
+        let total = values.len();
+        
+        if let Some((low, high)) = clip {
+            if let (Some(low), Some(high)) = (percentile(&values, low), percentile(&values, high)) {
+                values.retain(|&value| (low..=high).contains(&value));
+            }
+        }
+        
+        let avg = (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64);
+        
+        Ok(ReadingAggregate {
+            sensor_id,
+            count: values.len(),
+            min: values.first().copied(),
+            max: values.last().copied(),
+            avg,
+            clipped_count: total - values.len(),
+        })
+    }
+    
+    /// Count a sensor's values in `bins` equal-width bins between their min and max
+    pub fn histogram(
+        sensor_id: i64,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+        bins: Option<usize>,
+    ) -> Result<ReadingHistogram> {
+        let bins = bins.unwrap_or(DEFAULT_HISTOGRAM_BINS);
+        if !(1..=MAX_HISTOGRAM_BINS).contains(&bins) {
+            return Err(AppError::BadRequest(format!(
+                "bins must be between 1 and {}",
+                MAX_HISTOGRAM_BINS
+            ))
+            .into());
+        }
+        
+        let conn = get_connection()?;
+        let filters = "WHERE sensor_id = ? AND value IS NOT NULL
+                   AND timestamp >= COALESCE(?, timestamp)
+                   AND timestamp <= COALESCE(?, timestamp)";
+        
+        let (count, min, max): (usize, Option<f64>, Option<f64>) = conn.query_row(
+            &format!("SELECT COUNT(*), MIN(value), MAX(value) FROM readings {}", filters),
+            params![sensor_id, start_time, end_time],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        
+        let histogram = |bins| ReadingHistogram { sensor_id, count, min, max, bins };
+        let (Some(min), Some(max)) = (min, max) else {
+            return Ok(histogram(Vec::new()));
+        };
+        
+        // Equal values leave no width to divide, so they share one bin
+        if min == max {
+            return Ok(histogram(vec![HistogramBin { lower: min, upper: max, count }]));
+        }
+        
+        let width = (max - min) / bins as f64;
+        let mut counts = vec![0; bins];
+        {
+            // The maximum lands on index `bins`, so it is folded into the last bin
+            let mut stmt = conn.prepare(&format!(
+                "SELECT MIN(CAST((value - ?) * ? / ? AS INTEGER), ?) AS bin, COUNT(*)
+                 FROM readings {}
+                 GROUP BY bin",
+                filters
+            ))?;
+            let rows = stmt.query_map(
+                params![min, bins as i64, max - min, bins as i64 - 1, sensor_id, start_time, end_time],
+                |row| Ok((row.get::<_, usize>(0)?, row.get::<_, usize>(1)?)),
+            )?;
+            for row in rows {
+                let (bin, count) = row?;
+                counts[bin] = count;
+            }
+        }
+        
+        let bins = counts
+            .into_iter()
+            .enumerate()
+            .map(|(i, count)| HistogramBin {
+                lower: min + width * i as f64,
+                upper: if i + 1 == bins { max } else { min + width * (i + 1) as f64 },
+                count,
+            })
+            .collect();
+        
+        Ok(histogram(bins))
+    }
+    
+    /// Integrate a sensor's values over time with the trapezoidal rule
+    ///
+    /// A reading without a value ends the current segment, so nothing is
+    /// integrated across the gap it marks.
+    pub fn integral(sensor_id: i64, start_time: Option<i64>, end_time: Option<i64>) -> Result<ReadingIntegral> {
+        let conn = get_connection()?;
+        let sensor = Sensor::get_by_id_with(&conn, sensor_id)?;
+        
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, value FROM readings
+             WHERE sensor_id = ?
+               AND timestamp >= COALESCE(?, timestamp)
+               AND timestamp <= COALESCE(?, timestamp)
+             ORDER BY timestamp, reading_id"
+        )?;
+        let readings: Vec<(i64, Option<f64>)> = stmt
+            .query_map(params![sensor_id, start_time, end_time], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<_, _>>()?;
+        
+        let mut unit_seconds = 0.0;
+        let mut segments = 0;
+        let mut previous: Option<(i64, f64)> = None;
+        
+        for (timestamp, value) in readings {
+            match (previous, value) {
+                (Some((prev_timestamp, prev_value)), Some(value)) => {
+                    unit_seconds += (prev_value + value) / 2.0 * (timestamp - prev_timestamp) as f64;
+                },
+                (None, Some(_)) => segments += 1,
+                _ => {}
+            }
+            previous = value.map(|value| (timestamp, value));
+        }
+        
+        Ok(ReadingIntegral {
+            sensor_id,
+            unit: sensor.unit,
+            start_time,
+            end_time,
+            unit_seconds,
+            unit_minutes: unit_seconds / 60.0,
+            unit_hours: unit_seconds / 3600.0,
+            segments,
+        })
+    }
+    
+    /// Summarize the values matching a query's filters
+    ///
+    /// `limit`, `offset` and `order` are ignored so the whole range is covered.
+    /// State-only readings carry no value and are left out.
+    pub fn stats(query: &ReadingQuery) -> Result<ReadingStats> {
+        let conn = get_connection()?;
+        
+        let (filters, params) = Self::build_filters(query);
+        let sql = format!("SELECT value FROM readings WHERE value IS NOT NULL{}", filters);
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let mut values: Vec<f64> = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        values.sort_by(f64::total_cmp);
+        
+        let count = values.len();
+        let mean = (count > 0).then(|| values.iter().sum::<f64>() / count as f64);
+        let std_dev = mean.map(|mean| {
+            let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / count as f64;
+            variance.sqrt()
+        });
+        
+        Ok(ReadingStats {
+            count,
+            min: values.first().copied(),
+            max: values.last().copied(),
+            mean,
+            std_dev,
+            p50: nearest_rank(&values, 50.0),
+            p90: nearest_rank(&values, 90.0),
+            p95: nearest_rank(&values, 95.0),
+            p99: nearest_rank(&values, 99.0),
+        })
+    }
+    
+    /// Get the last known value as of each interval boundary
+    ///
+    /// Boundaries run from `start_time` to `end_time` in steps of `interval`.
+    /// Each carries the latest reading at or before it, so values step
+    /// forward across empty intervals; boundaries before the first reading
+    /// are null.
+    pub fn stepped(sensor_id: i64, interval: i64, start_time: i64, end_time: i64) -> Result<Vec<SteppedPoint>> {
+        if interval <= 0 {
+            return Err(AppError::BadRequest("interval must be a positive number of seconds".to_string()).into());
+        }
+        
+        if start_time > end_time {
+            return Err(AppError::BadRequest("start_time must not be later than end_time".to_string()).into());
+        }
+        
+        let boundaries = (end_time - start_time) / interval + 1;
+        if boundaries > MAX_STEPPED_POINTS {
+            return Err(AppError::BadRequest(format!(
+                "Requested {} boundaries; at most {} are allowed",
+                boundaries, MAX_STEPPED_POINTS
+            ))
+            .into());
+        }
+        
+        let conn = get_connection()?;
+        
+        // Value carried into the first boundary
+        let mut last: Option<(Option<f64>, Option<i64>)> = conn
+            .query_row(
+                "SELECT value, state FROM readings
+                 WHERE sensor_id = ? AND timestamp <= ?
+                 ORDER BY timestamp DESC, reading_id DESC
+                 LIMIT 1",
+                params![sensor_id, start_time],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, value, state FROM readings
+             WHERE sensor_id = ? AND timestamp > ? AND timestamp <= ?
+             ORDER BY timestamp, reading_id"
+        )?;
+        let readings: Vec<(i64, Option<f64>, Option<i64>)> = stmt
+            .query_map(params![sensor_id, start_time, end_time], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?))
+            })?
+            .collect::<Result<_, _>>()?;
+        let mut readings = readings.into_iter().peekable();
+        
+        let mut points = Vec::with_capacity(boundaries as usize);
+        
+        for boundary in (0..boundaries).map(|step| start_time + step * interval) {
+            while let Some((_, value, state)) = readings.next_if(|&(timestamp, _, _)| timestamp <= boundary) {
+                last = Some((value, state));
+            }
+            
+            let (value, state) = last.unwrap_or((None, None));
+            points.push(SteppedPoint {
+                timestamp: boundary,
+                value,
+                state,
+            });
+        }
+        
+        Ok(points)
+    }
+    
+    /// Estimate a sensor's value at each boundary by linear interpolation
+    ///
+    /// A boundary with a reading on it takes that value; otherwise it lies on
+    /// the line between the nearest numeric readings either side. Boundaries
+    /// outside the sensor's readings are null, as are those between readings
+    /// more than `max_gap` seconds apart.
+    pub fn interpolate(sensor_id: i64, boundaries: &[i64], max_gap: Option<i64>) -> Result<Vec<Option<f64>>> {
+        let (Some(&first), Some(&last)) = (boundaries.first(), boundaries.last()) else {
+            return Ok(Vec::new());
+        };
+        
+        let conn = get_connection()?;
+        
+        // The readings on either side of the range anchor its first and last boundaries
+        let before: Option<(i64, f64)> = conn
+            .query_row(
+                "SELECT timestamp, value FROM readings
+                 WHERE sensor_id = ? AND timestamp < ? AND value IS NOT NULL
+                 ORDER BY timestamp DESC
+                 LIMIT 1",
+                params![sensor_id, first],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let after: Option<(i64, f64)> = conn
+            .query_row(
+                "SELECT timestamp, value FROM readings
+                 WHERE sensor_id = ? AND timestamp > ? AND value IS NOT NULL
+                 ORDER BY timestamp
+                 LIMIT 1",
+                params![sensor_id, last],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        
+        let mut stmt = conn.prepare(
+            "SELECT timestamp, value FROM readings
+             WHERE sensor_id = ? AND timestamp >= ? AND timestamp <= ? AND value IS NOT NULL
+             ORDER BY timestamp"
+        )?;
+        let within = stmt
+            .query_map(params![sensor_id, first, last], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<Result<Vec<(i64, f64)>, _>>()?;
+        
+        let points: Vec<(i64, f64)> = before.into_iter().chain(within).chain(after).collect();
+        
+        let values = boundaries
+            .iter()
+            .map(|&boundary| {
+                let next = points.partition_point(|&(timestamp, _)| timestamp < boundary);
+                match (next.checked_sub(1).map(|index| points[index]), points.get(next)) {
+                    (_, Some(&(timestamp, value))) if timestamp == boundary => Some(value),
+                    (Some((t0, v0)), Some(&(t1, v1))) => {
+                        if max_gap.is_some_and(|gap| t1 - t0 > gap) {
+                            None
+                        } else {
+                            Some(v0 + (v1 - v0) * (boundary - t0) as f64 / (t1 - t0) as f64)
+                        }
+                    }
+                    _ => None,
+                }
+            })
+            .collect();
+        
+        Ok(values)
+    }
+    
+    /// Average value per sensor in each `bucket_seconds` wide bucket
+    ///
+    /// Buckets are aligned to the epoch and only those holding at least one
+    /// numeric reading are returned.
+    pub fn bucket_averages(
+        sensor_ids: &[i64],
+        bucket_seconds: i64,
+        start_time: i64,
+        end_time: i64,
+    ) -> Result<Vec<BucketAverage>> {
+        if bucket_seconds <= 0 {
+            return Err(AppError::BadRequest("bucket size must be a positive number of seconds".to_string()).into());
+        }
+        
+        if start_time > end_time {
+            return Err(AppError::BadRequest("start_time must not be later than end_time".to_string()).into());
+        }
+        
+        let buckets = end_time / bucket_seconds - start_time / bucket_seconds + 1;
+        if buckets > MAX_TIME_SERIES_BUCKETS {
+            return Err(AppError::BadRequest(format!(
+                "Requested {} buckets; at most {} are allowed",
+                buckets, MAX_TIME_SERIES_BUCKETS
+            ))
+            .into());
+        }
+        
+        if sensor_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let conn = get_connection()?;
+        
+        let placeholders = vec!["?"; sensor_ids.len()].join(", ");
+        let sql = format!(
+            "SELECT sensor_id, timestamp / ? AS bucket, AVG(value) FROM readings
+             WHERE sensor_id IN ({}) AND timestamp >= ? AND timestamp <= ? AND value IS NOT NULL
+             GROUP BY sensor_id, bucket
+             ORDER BY sensor_id, bucket",
+            placeholders
+        );
+        
+        let mut params = vec![Value::Integer(bucket_seconds)];
+        params.extend(sensor_ids.iter().map(|&id| Value::Integer(id)));
+        params.push(Value::Integer(start_time));
+        params.push(Value::Integer(end_time));
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let averages = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok(BucketAverage {
+                    sensor_id: row.get(0)?,
+                    bucket: row.get(1)?,
+                    average: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(averages)
+    }
+    
+    /// Combine every sensor of a type into one bucketed series
+    ///
+    /// Each sensor is first averaged within the bucket, so a sensor reporting
+    /// more often doesn't weigh more; `agg` then combines those averages.
+    pub fn aggregate_by_type(
+        sensor_type: &str,
+        bucket_seconds: i64,
+        agg: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> Result<Vec<TypeBucket>> {
+        let combine = match agg {
+            "sum" => "SUM",
+            "avg" => "AVG",
+            "min" => "MIN",
+            "max" => "MAX",
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown agg '{}'; expected sum, avg, min or max",
+                    other
+                ))
+                .into());
+            }
+        };
+        
+        let conn = get_connection()?;
+        
+        let mut filters = String::new();
+        let mut params = vec![Value::Integer(bucket_seconds), Value::Text(sensor_type.to_string())];
+        
+        if let Some(start_time) = start_time {
+            filters.push_str(" AND r.timestamp >= ?");
+            params.push(Value::Integer(start_time));
+        }
+        
+        if let Some(end_time) = end_time {
+            filters.push_str(" AND r.timestamp <= ?");
+            params.push(Value::Integer(end_time));
+        }
+        
+        let sql = format!(
+            "SELECT bucket, {}(average), COUNT(*) FROM (
+                 SELECT r.sensor_id, r.timestamp / ? AS bucket, AVG(r.value) AS average
+                 FROM readings r
+                 JOIN sensors s ON s.sensor_id = r.sensor_id
+                 WHERE s.sensor_type = ? AND r.value IS NOT NULL{}
+                 GROUP BY r.sensor_id, bucket
+             )
+             GROUP BY bucket
+             ORDER BY bucket",
+            combine, filters
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let buckets = stmt
+            .query_map(rusqlite::params_from_iter(params.iter()), |row| {
+                Ok(TypeBucket {
+                    bucket_start: row.get::<_, i64>(0)? * bucket_seconds,
+                    value: row.get(1)?,
+                    sensor_count: row.get(2)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(buckets)
+    }
+    
+    /// Aggregate every sensor of a type per bucket, keeping sensors apart
+    ///
+    /// Only buckets where some sensor has readings are returned; within them
+    /// every sensor of the type appears, with None when it has no data.
+    pub fn aggregate_grouped(
+        sensor_type: &str,
+        bucket_seconds: i64,
+        agg: &str,
+        start_time: Option<i64>,
+        end_time: Option<i64>,
+    ) -> Result<Vec<GroupedBucket>> {
+        let aggregate = match agg {
+            "avg" => "AVG",
+            "sum" => "SUM",
+            "min" => "MIN",
+            "max" => "MAX",
+            other => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown agg '{}'; expected avg, sum, min or max",
+                    other
+                ))
+                .into());
+            }
+        };
+        
+        let conn = get_connection()?;
+        
+        let sensor_ids: Vec<i64> = conn
+            .prepare("SELECT sensor_id FROM sensors WHERE sensor_type = ? ORDER BY sensor_id")?
+            .query_map(params![sensor_type], |row| row.get(0))?
+            .collect::<Result<_, _>>()?;
+        
+        let mut filters = String::new();
+        let mut params = vec![Value::Integer(bucket_seconds), Value::Text(sensor_type.to_string())];
+        
+        if let Some(start_time) = start_time {
+            filters.push_str(" AND r.timestamp >= ?");
+            params.push(Value::Integer(start_time));
+        }
+        
+        if let Some(end_time) = end_time {
+            filters.push_str(" AND r.timestamp <= ?");
+            params.push(Value::Integer(end_time));
+        }
+        
+        let sql = format!(
+            "SELECT r.sensor_id, r.timestamp / ? AS bucket, {}(r.value)
+             FROM readings r
+             JOIN sensors s ON s.sensor_id = r.sensor_id
+             WHERE s.sensor_type = ? AND r.value IS NOT NULL{}
+             GROUP BY r.sensor_id, bucket
+             ORDER BY bucket, r.sensor_id",
+            aggregate, filters
+        );
+        
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(params.iter()), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?, row.get::<_, f64>(2)?))
         })?;
         
-        let mut readings = Vec::new();
-        for reading in reading_iter {
-            readings.push(reading?);
+        let mut buckets: Vec<GroupedBucket> = Vec::new();
+        for row in rows {
+            let (sensor_id, bucket, value) = row?;
+            let bucket_start = bucket * bucket_seconds;
+            
+            if buckets.last().map(|b| b.bucket_start) != Some(bucket_start) {
+                buckets.push(GroupedBucket {
+                    bucket_start,
+                    values: sensor_ids.iter().map(|&id| (id, None)).collect(),
+                });
+            }
+            if let Some(current) = buckets.last_mut() {
+                current.values.insert(sensor_id, Some(value));
+            }
         }
         
-        Ok(readings)
+        Ok(buckets)
     }
     
-    /// Get the current reading for a sensor
-    pub fn get_current(sensor_id: i64) -> Result<ReadingResponse> {
+    /// Get the reading just before the current one for a sensor
+    pub fn get_previous(sensor_id: i64) -> Result<ReadingResponse> {
         let conn = get_connection()?;
         
         let reading = conn.query_row(
             "SELECT * FROM readings 
              WHERE sensor_id = ? 
-             ORDER BY timestamp DESC 
-             LIMIT 1",
+             ORDER BY timestamp DESC, reading_id DESC 
+             LIMIT 1 OFFSET 1",
             params![sensor_id],
-            |row| Self::from_row(row),
+            Self::from_row,
         )?;
         
         Ok(reading)
     }
     
+    /// Get a sensor's annotated readings, oldest first
+    ///
+    /// Annotations mark points worth a second look, so there are few enough
+    /// of them to return without paging.
+    pub fn get_annotated(sensor_id: i64) -> Result<Vec<ReadingResponse>> {
+        let conn = get_connection()?;
+        if !Sensor::exists(&conn, sensor_id)? {
+            return Err(AppError::NotFound(format!("Sensor {} not found", sensor_id)).into());
+        }
+        
+        let mut stmt = conn.prepare(
+            "SELECT * FROM readings
+             WHERE sensor_id = ? AND annotation IS NOT NULL AND annotation <> ''
+             ORDER BY timestamp, reading_id"
+        )?;
+        let readings = stmt
+            .query_map(params![sensor_id], Self::from_row)?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(readings)
+    }
+    
     /// Delete readings in a time range
     pub fn delete_range(sensor_id: Option<i64>, start_time: i64, end_time: i64) -> Result<usize> {
         let conn = get_connection()?;
@@ -202,20 +2569,125 @@ impl Reading {
         
         let count = conn.execute(&sql, rusqlite::params_from_iter(params.iter()))?;
         
+        match sensor_id {
+            Some(id) => cache::invalidate_sensor(id),
+            None => cache::clear(),
+        }
+        
+        Ok(count)
+    }
+    
+    /// Delete every reading for a sensor
+    pub fn delete_all_for_sensor(sensor_id: i64) -> Result<usize> {
+        let conn = get_connection()?;
+        if !Sensor::exists(&conn, sensor_id)? {
+            return Err(AppError::NotFound(format!("Sensor {} not found", sensor_id)).into());
+        }
+        
+        let count = conn.execute("DELETE FROM readings WHERE sensor_id = ?", params![sensor_id])?;
+        cache::invalidate_sensor(sensor_id);
+        
         Ok(count)
     }
     
+    /// Delete a single reading by ID, returning whether it existed
+    pub fn delete_by_id(id: i64) -> Result<bool> {
+        let conn = get_connection()?;
+        
+        let count = conn.execute("DELETE FROM readings WHERE reading_id = ?", params![id])?;
+        cache::clear();
+        
+        Ok(count > 0)
+    }
+    
+    /// Collapse consecutive duplicate readings for every sensor
+    ///
+    /// A reading is removed when it has the same value and state as the last
+    /// kept reading of its sensor and arrives within `tolerance_secs` of it.
+    /// Readings are scanned `batch_size` at a time and each batch's deletes
+    /// commit in their own short transaction. Returns the number removed.
+    pub fn dedup(tolerance_secs: i64, batch_size: usize) -> Result<usize> {
+        let mut conn = get_connection()?;
+        
+        let sensor_ids: Vec<i64> = {
+            let mut stmt = conn.prepare("SELECT DISTINCT sensor_id FROM readings")?;
+            let ids = stmt.query_map([], |row| row.get(0))?;
+            ids.collect::<Result<_, _>>()?
+        };
+        
+        let mut removed = 0;
+        
+        for sensor_id in sensor_ids {
+            // (timestamp, value, state) of the last reading that was kept
+            let mut last_kept: Option<(i64, Option<f64>, Option<i64>)> = None;
+            // Keyset cursor over (timestamp, reading_id)
+            let mut cursor = (i64::MIN, i64::MIN);
+            
+            loop {
+                let batch: Vec<(i64, i64, Option<f64>, Option<i64>)> = {
+                    let mut stmt = conn.prepare(
+                        "SELECT reading_id, timestamp, value, state FROM readings
+                         WHERE sensor_id = ? AND (timestamp, reading_id) > (?, ?)
+                         ORDER BY timestamp, reading_id
+                         LIMIT ?"
+                    )?;
+                    let rows = stmt.query_map(
+                        params![sensor_id, cursor.0, cursor.1, batch_size as i64],
+                        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)),
+                    )?;
+                    rows.collect::<Result<_, _>>()?
+                };
+                
+                let mut duplicates = Vec::new();
+                
+                for &(reading_id, timestamp, value, state) in &batch {
+                    match last_kept {
+                        Some((kept_timestamp, kept_value, kept_state))
+                            if kept_value == value
+                                && kept_state == state
+                                && timestamp - kept_timestamp <= tolerance_secs =>
+                        {
+                            duplicates.push(reading_id);
+                        }
+                        _ => last_kept = Some((timestamp, value, state)),
+                    }
+                    
+                    cursor = (timestamp, reading_id);
+                }
+                
+                if !duplicates.is_empty() {
+                    let tx = conn.transaction()?;
+                    {
+                        let mut stmt = tx.prepare("DELETE FROM readings WHERE reading_id = ?")?;
+                        for reading_id in &duplicates {
+                            stmt.execute(params![reading_id])?;
+                        }
+                    }
+                    tx.commit()?;
+                    cache::invalidate_sensor(sensor_id);
+                    
+                    removed += duplicates.len();
+                }
+                
+                if batch.len() < batch_size {
+                    break;
+                }
+            }
+        }
+        
+        Ok(removed)
+    }
+    
     /// Convert a database row to a ReadingResponse
     fn from_row(row: &Row) -> Result<ReadingResponse, rusqlite::Error> {
         let reading_id: i64 = row.get("reading_id")?;
-        let timestamp: i64 = row.get("timestamp")?;
+        let timestamp = get_datetime(row, "timestamp")?;
         let sensor_id: i64 = row.get("sensor_id")?;
         let value: Option<f64> = row.get("value")?;
         let state: Option<i64> = row.get("state")?;
         let change_type: Option<String> = row.get("change_type")?;
-        
-        let timestamp = DateTime::from_timestamp(timestamp, 0)
-            .expect("Invalid timestamp");
+        let annotation: Option<String> = row.get("annotation")?;
+        let out_of_range: bool = row.get("out_of_range")?;
         
         Ok(ReadingResponse {
             reading_id,
@@ -224,6 +2696,9 @@ impl Reading {
             value,
             state,
             change_type,
+            annotation,
+            unit: None,
+            out_of_range,
         })
     }
 }
\ No newline at end of file
@@ -1,17 +1,53 @@
 use anyhow::{Context, Result};
-use chrono::{DateTime, NaiveDateTime, Utc};
-use rusqlite::{params, Connection, Row};
+use chrono::{DateTime, Utc};
+use rusqlite::{params, types::Value, Connection, OptionalExtension, Row, TransactionBehavior};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::db::get_connection;
+use crate::config;
+use crate::db::{get_connection, get_datetime, get_optional_datetime};
+use crate::models::audit::{AuditAction, SensorAuditEntry};
+use crate::utils::{cache, error::AppError};
+
+const SECONDS_PER_DAY: i64 = 86400;
+
+/// Sensor types accepted unless `SENSOR_TYPE_ALLOWLIST` replaces them
+pub const DEFAULT_SENSOR_TYPES: &[&str] = &["temperature", "power", "flow", "light", "humidity", "generic"];
+
+/// Longest accepted sensor tag
+const MAX_TAG_LENGTH: usize = 64;
+
+/// Insert a sensor, picking its id in the same statement
+///
+/// SQLite would hand a hard-deleted sensor's id to the next sensor, which
+/// would then inherit its audit trail, so ids also skip those in the trail.
+/// Reading the maximum inside the INSERT means it is taken under the write
+/// lock, so concurrent creates can't pick the same id.
+const INSERT_SENSOR: &str = "INSERT INTO sensors (
+        sensor_name, sensor_type, location, unit,
+        threshold_min, threshold_max, calibration_date, notes,
+        display_color, display_order, deadband, retention_days, valid_min, valid_max,
+        created_at, updated_at, sensor_id
+    ) VALUES (
+        ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+        MAX(
+            COALESCE((SELECT MAX(sensor_id) FROM sensors), 0),
+            COALESCE((SELECT MAX(sensor_id) FROM sensor_audit), 0)
+        ) + 1
+    )";
 
 #[cfg(test)]
 mod tests {
     use anyhow::Result;
+    use std::sync::{Arc, Barrier};
     use crate::{
-        models::Sensor,
-        utils::test_utils::{setup_test_db, create_test_sensor},
+        config,
+        db::share_test_pool,
+        models::{sensor::DEFAULT_SENSOR_TYPES, Sensor, SensorPatch, SensorQuery},
+        utils::{
+            error::AppError,
+            test_utils::{setup_test_db, create_test_sensor},
+        },
     };
 
     #[test]
@@ -28,6 +64,12 @@ mod tests {
             threshold_max: Some(25.0),
             calibration_date: None,
             notes: Some("Test sensor".to_string()),
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
             created_at: None,
             updated_at: None,
         };
@@ -43,6 +85,55 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_concurrent_creates_get_distinct_ids() -> Result<()> {
+        let pool = setup_test_db()?;
+        
+        // Release every create at once so their id choices overlap
+        let barrier = Arc::new(Barrier::new(4));
+        let creates: Vec<_> = (0..4)
+            .map(|index| {
+                let (pool, barrier) = (pool.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    share_test_pool(pool);
+                    barrier.wait();
+                    Sensor {
+                        sensor_id: None,
+                        sensor_name: format!("Concurrent Sensor {}", index),
+                        sensor_type: "temperature".to_string(),
+                        location: None,
+                        unit: None,
+                        threshold_min: None,
+                        threshold_max: None,
+                        calibration_date: None,
+                        notes: None,
+                        display_color: None,
+                        display_order: None,
+                        deadband: None,
+                        retention_days: None,
+                        valid_min: None,
+                        valid_max: None,
+                        created_at: None,
+                        updated_at: None,
+                    }
+                    .create()
+                })
+            })
+            .collect();
+        let mut ids = creates
+            .into_iter()
+            .map(|create| create.join().unwrap())
+            .collect::<Result<Vec<i64>>>()?;
+        
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), 4);
+        let count: i64 = pool.get()?.query_row("SELECT COUNT(*) FROM sensors", [], |row| row.get(0))?;
+        assert_eq!(count, 4);
+        
+        Ok(())
+    }
+    
     #[test]
     fn test_update_sensor() -> Result<()> {
         let pool = setup_test_db()?;
@@ -50,21 +141,18 @@ mod tests {
         
         let sensor_id = create_test_sensor(&conn)?;
         
-        let sensor = Sensor {
-            sensor_id: None,
-            sensor_name: "Updated Sensor".to_string(),
-            sensor_type: "humidity".to_string(),
+        let patch = SensorPatch {
+            sensor_name: Some("Updated Sensor".to_string()),
+            sensor_type: Some("humidity".to_string()),
             location: Some("New Location".to_string()),
             unit: Some("%".to_string()),
             threshold_min: Some(30.0),
             threshold_max: Some(70.0),
-            calibration_date: None,
             notes: Some("Updated notes".to_string()),
-            created_at: None,
-            updated_at: None,
+            ..Default::default()
         };
         
-        sensor.update(sensor_id)?;
+        Sensor::patch(sensor_id, &patch)?;
         
         let retrieved = Sensor::get_by_id(sensor_id)?;
         assert_eq!(retrieved.sensor_name, "Updated Sensor");
@@ -74,6 +162,36 @@ mod tests {
         Ok(())
     }
     
+    #[test]
+    fn test_patch_preserves_omitted_fields() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let sensor_id = create_test_sensor(&conn)?;
+        let before = Sensor::get_by_id(sensor_id)?;
+        
+        let patch = SensorPatch {
+            threshold_max: Some(30.0),
+            ..Default::default()
+        };
+        Sensor::patch(sensor_id, &patch)?;
+        
+        let after = Sensor::get_by_id(sensor_id)?;
+        assert_eq!(after.threshold_max, Some(30.0));
+        assert_eq!(after.sensor_name, before.sensor_name);
+        assert_eq!(after.sensor_type, before.sensor_type);
+        assert_eq!(after.location, before.location);
+        assert_eq!(after.unit, before.unit);
+        assert_eq!(after.threshold_min, before.threshold_min);
+        assert_eq!(after.notes, before.notes);
+        
+        // Unknown sensors are reported as not found
+        let err = Sensor::patch(9999, &patch).unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::NotFound(_))));
+        
+        Ok(())
+    }
+    
     #[test]
     fn test_delete_sensor() -> Result<()> {
         let pool = setup_test_db()?;
@@ -107,6 +225,12 @@ mod tests {
             threshold_max: Some(50.0),
             calibration_date: None,
             notes: Some("Test flow sensor".to_string()),
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
             created_at: None,
             updated_at: None,
         };
@@ -114,18 +238,20 @@ mod tests {
         sensor2.create()?;
         
         // Test with no filters
-        let query = crate::models::SensorQuery {
+        let query = SensorQuery {
             sensor_type: None,
             location: None,
+            ..Default::default()
         };
         
         let sensors = Sensor::get_all(&query)?;
         assert_eq!(sensors.len(), 2, "Should retrieve 2 sensors");
         
         // Test with type filter
-        let query = crate::models::SensorQuery {
+        let query = SensorQuery {
             sensor_type: Some("flow".to_string()),
             location: None,
+            ..Default::default()
         };
         
         let sensors = Sensor::get_all(&query)?;
@@ -133,9 +259,10 @@ mod tests {
         assert_eq!(sensors[0].sensor_type, "flow");
         
         // Test with location filter
-        let query = crate::models::SensorQuery {
+        let query = SensorQuery {
             sensor_type: None,
             location: Some("Building B".to_string()),
+            ..Default::default()
         };
         
         let sensors = Sensor::get_all(&query)?;
@@ -144,6 +271,231 @@ mod tests {
         
         Ok(())
     }
+    
+    #[test]
+    fn test_sensor_type_allowlist() -> Result<()> {
+        let _pool = setup_test_db()?;
+        config::set_test_config(config::Config {
+            sensor_type_allowlist: Some(vec!["temperature".to_string(), "flow".to_string()]),
+            ..config::Config::default()
+        });
+        
+        let mut sensor = Sensor {
+            sensor_id: None,
+            sensor_name: "Boiler Temp".to_string(),
+            sensor_type: "temperatur".to_string(),
+            location: None,
+            unit: Some("C".to_string()),
+            threshold_min: None,
+            threshold_max: None,
+            calibration_date: None,
+            notes: None,
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
+            created_at: None,
+            updated_at: None,
+        };
+        
+        // Off-list types are rejected as bad requests
+        let err = sensor.create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        
+        sensor.sensor_type = "temperature".to_string();
+        let id = sensor.create()?;
+        
+        let patch = SensorPatch {
+            sensor_type: Some("pressure".to_string()),
+            ..Default::default()
+        };
+        assert!(Sensor::patch(id, &patch).is_err());
+        assert_eq!(Sensor::get_by_id(id)?.sensor_type, "temperature");
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_sensor_type_defaults_and_extras() -> Result<()> {
+        let _pool = setup_test_db()?;
+        let sensor = |sensor_type: &str| Sensor {
+            sensor_id: None,
+            sensor_name: "Line Pressure".to_string(),
+            sensor_type: sensor_type.to_string(),
+            location: None,
+            unit: None,
+            threshold_min: None,
+            threshold_max: None,
+            calibration_date: None,
+            notes: None,
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
+            created_at: None,
+            updated_at: None,
+        };
+        
+        // Built-in types, including the catch-all, are accepted out of the box
+        for sensor_type in DEFAULT_SENSOR_TYPES {
+            sensor(sensor_type).create()?;
+        }
+        
+        let err = sensor("temprature").create().unwrap_err();
+        match err.downcast_ref::<AppError>() {
+            Some(AppError::BadRequest(message)) => assert!(message.contains("'temprature'")),
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+        assert!(sensor("pressure").create().is_err());
+        
+        // Extra types extend the built-in set rather than replacing it
+        config::set_test_config(config::Config {
+            extra_sensor_types: vec!["pressure".to_string()],
+            ..config::Config::default()
+        });
+        sensor("pressure").create()?;
+        sensor("generic").create()?;
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_get_all_created_after() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let old_sensor = create_test_sensor(&conn)?;
+        let new_sensor = create_test_sensor(&conn)?;
+        // The update trigger stamps updated_at with the current time on both rows
+        conn.execute("UPDATE sensors SET created_at = 1000 WHERE sensor_id = ?", [old_sensor])?;
+        conn.execute("UPDATE sensors SET created_at = 5000 WHERE sensor_id = ?", [new_sensor])?;
+        let now = crate::utils::current_timestamp();
+        
+        let ids = |query: SensorQuery| -> Result<Vec<i64>> {
+            Ok(Sensor::get_all(&query)?.iter().map(|s| s.sensor_id).collect())
+        };
+        
+        assert_eq!(ids(SensorQuery { created_after: Some(2000), ..Default::default() })?, vec![new_sensor]);
+        assert_eq!(ids(SensorQuery { created_before: Some(2000), ..Default::default() })?, vec![old_sensor]);
+        assert_eq!(ids(SensorQuery { updated_after: Some(now - 60), ..Default::default() })?.len(), 2);
+        assert!(ids(SensorQuery { updated_after: Some(now + 60), ..Default::default() })?.is_empty());
+        assert!(ids(SensorQuery { created_after: Some(6000), ..Default::default() })?.is_empty());
+        
+        // Invalid epochs and inverted ranges are rejected
+        assert!(ids(SensorQuery { created_after: Some(-1), ..Default::default() }).is_err());
+        let inverted = SensorQuery {
+            created_after: Some(5000),
+            created_before: Some(1000),
+            ..Default::default()
+        };
+        assert!(ids(inverted).is_err());
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_get_all_name_search_and_type_list() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        
+        let sensor = |name: &str, sensor_type: &str| -> Result<i64> {
+            let sensor_id = create_test_sensor(&conn)?;
+            conn.execute(
+                "UPDATE sensors SET sensor_name = ?, sensor_type = ? WHERE sensor_id = ?",
+                rusqlite::params![name, sensor_type, sensor_id],
+            )?;
+            Ok(sensor_id)
+        };
+        let boiler = sensor("Boiler Flow Temp", "temperature")?;
+        let meter = sensor("Main Meter", "power")?;
+        let pump = sensor("Pump_2 100% duty", "flow")?;
+        let other_pump = sensor("Pump 2 Outlet", "flow")?;
+        
+        let ids = |query: SensorQuery| -> Result<Vec<i64>> {
+            Ok(Sensor::get_all(&query)?.iter().map(|s| s.sensor_id).collect())
+        };
+        let named = |term: &str| ids(SensorQuery { name_contains: Some(term.to_string()), ..Default::default() });
+        
+        assert_eq!(named("flow")?, vec![boiler]);
+        assert_eq!(named("PUMP")?, vec![pump, other_pump]);
+        // Wildcard characters only match themselves
+        assert_eq!(named("_")?, vec![pump]);
+        assert_eq!(named("100%")?, vec![pump]);
+        assert!(named("%%")?.is_empty());
+        
+        let typed = |types: &str| ids(SensorQuery { sensor_type: Some(types.to_string()), ..Default::default() });
+        assert_eq!(typed("power")?, vec![meter]);
+        assert_eq!(typed("power, flow")?, vec![meter, pump, other_pump]);
+        assert!(typed(" , ").is_err());
+        
+        // Filters combine
+        let query = SensorQuery {
+            sensor_type: Some("temperature,flow".to_string()),
+            name_contains: Some("outlet".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(ids(query)?, vec![other_pump]);
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_get_all_sorted_by_display_order() -> Result<()> {
+        let _pool = setup_test_db()?;
+        
+        let sensor = |name: &str, display_order: Option<i64>| Sensor {
+            sensor_id: None,
+            sensor_name: name.to_string(),
+            sensor_type: "power".to_string(),
+            location: None,
+            unit: Some("kW".to_string()),
+            threshold_min: None,
+            threshold_max: None,
+            calibration_date: None,
+            notes: None,
+            display_color: Some("#1f77b4".to_string()),
+            display_order,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
+            created_at: None,
+            updated_at: None,
+        };
+        sensor("Unordered", None).create()?;
+        sensor("Third", Some(30)).create()?;
+        sensor("First", Some(10)).create()?;
+        let second = sensor("Second", Some(20)).create()?;
+        
+        let query = SensorQuery {
+            sort: Some("display_order".to_string()),
+            ..Default::default()
+        };
+        let names: Vec<String> = Sensor::get_all(&query)?.into_iter().map(|s| s.sensor_name).collect();
+        assert_eq!(names, vec!["First", "Second", "Third", "Unordered"]);
+        
+        let retrieved = Sensor::get_by_id(second)?;
+        assert_eq!(retrieved.display_color.as_deref(), Some("#1f77b4"));
+        assert_eq!(retrieved.display_order, Some(20));
+        
+        // Colors must be hex codes
+        let mut bad_color = sensor("Bad Color", None);
+        bad_color.display_color = Some("blue".to_string());
+        let err = bad_color.create().unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        
+        let unknown_sort = SensorQuery {
+            sort: Some("name".to_string()),
+            ..Default::default()
+        };
+        assert!(Sensor::get_all(&unknown_sort).is_err());
+        
+        Ok(())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -157,12 +509,39 @@ pub struct Sensor {
     pub threshold_max: Option<f64>,
     pub calibration_date: Option<i64>,
     pub notes: Option<String>,
+    pub display_color: Option<String>,  // Hex color, '#RRGGBB' or '#RGB'
+    pub display_order: Option<i64>,
+    pub deadband: Option<f64>,  // Minimum value change worth storing; None stores every reading
+    pub retention_days: Option<i64>,  // Overrides the maintenance retention for this sensor
+    #[serde(default)]
+    pub valid_min: Option<f64>,  // Readings below this are rejected or flagged as out of range
+    #[serde(default)]
+    pub valid_max: Option<f64>,  // Readings above this are rejected or flagged as out of range
     #[serde(skip_serializing_if = "Option::is_none")]
     pub created_at: Option<i64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<i64>,
 }
 
+/// Partial sensor update; fields left as None keep their stored value
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SensorPatch {
+    pub sensor_name: Option<String>,
+    pub sensor_type: Option<String>,
+    pub location: Option<String>,
+    pub unit: Option<String>,
+    pub threshold_min: Option<f64>,
+    pub threshold_max: Option<f64>,
+    pub calibration_date: Option<i64>,
+    pub notes: Option<String>,
+    pub display_color: Option<String>,
+    pub display_order: Option<i64>,
+    pub deadband: Option<f64>,
+    pub retention_days: Option<i64>,
+    pub valid_min: Option<f64>,
+    pub valid_max: Option<f64>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SensorResponse {
     pub sensor_id: i64,
@@ -174,41 +553,224 @@ pub struct SensorResponse {
     pub threshold_max: Option<f64>,
     pub calibration_date: Option<DateTime<Utc>>,
     pub notes: Option<String>,
+    pub display_color: Option<String>,
+    pub display_order: Option<i64>,
+    pub deadband: Option<f64>,
+    pub retention_days: Option<i64>,
+    pub valid_min: Option<f64>,
+    pub valid_max: Option<f64>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub archived: bool,
+    pub disabled_at: Option<DateTime<Utc>>,  // Set once the sensor is soft-deleted
 }
 
-#[derive(Debug, Deserialize)]
-pub struct SensorQuery {
-    pub sensor_type: Option<String>,
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorStats {
+    pub sensor_id: i64,
+    pub ingest_count: i64,
+    pub last_ingest: Option<DateTime<Utc>>,
+    pub readings_per_day: Vec<DailyReadingCount>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DailyReadingCount {
+    pub date: String,
+    pub count: i64,
+}
+
+/// Version of the sensor configuration document format
+pub const SENSOR_CONFIG_VERSION: u32 = 1;
+
+/// Sensor fleet configuration, without readings, keyed by sensor name
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorConfigDocument {
+    pub version: u32,
+    pub sensors: Vec<SensorConfig>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SensorConfig {
+    pub sensor_name: String,
+    pub sensor_type: String,
+    #[serde(default)]
     pub location: Option<String>,
+    #[serde(default)]
+    pub unit: Option<String>,
+    #[serde(default)]
+    pub threshold_min: Option<f64>,
+    #[serde(default)]
+    pub threshold_max: Option<f64>,
+    #[serde(default)]
+    pub calibration_date: Option<i64>,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub display_color: Option<String>,
+    #[serde(default)]
+    pub display_order: Option<i64>,
+    #[serde(default)]
+    pub deadband: Option<f64>,
+    #[serde(default)]
+    pub retention_days: Option<i64>,
+    #[serde(default)]
+    pub valid_min: Option<f64>,
+    #[serde(default)]
+    pub valid_max: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorConfigImport {
+    pub created: usize,
+    pub updated: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorBulkCreate {
+    pub sensors: Vec<Sensor>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorBulkResponse {
+    pub created_count: usize,
+    pub sensor_ids: Vec<i64>,  // In request order
+    pub success: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorBulkDelete {
+    pub ids: Vec<i64>,
+    #[serde(default)]
+    pub confirm: bool,  // Must be true; guards against accidental mass deletes
+    #[serde(default)]
+    pub atomic: bool,  // Delete nothing unless every id exists
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorBatchDelete {
+    pub sensor_ids: Vec<i64>,
+    #[serde(default)]
+    pub hard: bool,  // Remove the sensors and their readings instead of disabling them
+    #[serde(default)]
+    pub atomic: bool,  // Delete nothing unless every id exists
+}
+
+/// Outcome of deleting one sensor in a bulk delete
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SensorDeleteResult {
+    pub sensor_id: i64,
+    pub deleted: bool,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SensorQuery {
+    pub sensor_type: Option<String>,  // Comma-separated list matches any of the types
+    pub location: Option<String>,     // Exact match
+    pub name_contains: Option<String>,  // Case-insensitive substring of sensor_name
+    pub tag: Option<String>,          // Only sensors carrying this tag
+    pub include_disabled: Option<bool>,  // Also list soft-deleted sensors
+    pub created_after: Option<i64>,   // Inclusive, epoch seconds
+    pub created_before: Option<i64>,  // Inclusive, epoch seconds
+    pub updated_after: Option<i64>,   // Inclusive, epoch seconds
+    pub sort: Option<String>,         // 'display_order'; defaults to sensor_id
 }
 
 impl Sensor {
     /// Create a new sensor
     pub fn create(&self) -> Result<i64> {
-        let conn = get_connection()?;
+        self.validate()?;
+        
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
         
         let now = SystemTime::now()
             .duration_since(UNIX_EPOCH)
             .context("Time went backwards")?
             .as_secs() as i64;
         
+        let id = Self::insert_with(&tx, self, now)?;
+        tx.commit()?;
+        // Spans that list empty sensors now have one more entry
+        cache::clear();
+        
+        Ok(id)
+    }
+    
+    /// Create several sensors in one transaction, returning their ids in order
+    ///
+    /// Nothing is created unless every sensor is valid; the error names the
+    /// first sensor that failed by its position and name.
+    pub fn bulk_create(sensors: &[Sensor]) -> Result<Vec<i64>> {
+        let name_error = |index: usize, sensor: &Sensor, err: anyhow::Error| -> anyhow::Error {
+            match err.downcast::<AppError>() {
+                Ok(AppError::BadRequest(message)) => AppError::BadRequest(format!(
+                    "Sensor {} ('{}'): {}",
+                    index, sensor.sensor_name, message
+                ))
+                .into(),
+                Ok(other) => other.into(),
+                Err(err) => err.context(format!("Failed to create sensor {} ('{}')", index, sensor.sensor_name)),
+            }
+        };
+        
+        for (index, sensor) in sensors.iter().enumerate() {
+            sensor.validate().map_err(|err| name_error(index, sensor, err))?;
+        }
+        
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
+        
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .context("Time went backwards")?
+            .as_secs() as i64;
+        
+        let mut ids = Vec::with_capacity(sensors.len());
+        for (index, sensor) in sensors.iter().enumerate() {
+            ids.push(Self::insert_with(&tx, sensor, now).map_err(|err| name_error(index, sensor, err))?);
+        }
+        
+        tx.commit()?;
+        cache::clear();
+        
+        Ok(ids)
+    }
+    
+    /// Check every field that has rules beyond its type
+    fn validate(&self) -> Result<()> {
+        Self::validate_type(&self.sensor_type)?;
+        Self::validate_color(self.display_color.as_deref())?;
+        Self::validate_deadband(self.deadband)?;
+        Self::validate_retention(self.retention_days)?;
+        Self::validate_range(self.valid_min, self.valid_max)?;
+        
+        Ok(())
+    }
+    
+    /// Insert a validated sensor on a connection the caller already holds
+    ///
+    /// The creation is audited on the same connection, so pass a transaction.
+    fn insert_with(conn: &Connection, sensor: &Sensor, now: i64) -> Result<i64> {
         let result = conn.execute(
-            "INSERT INTO sensors (
-                sensor_name, sensor_type, location, unit, 
-                threshold_min, threshold_max, calibration_date, notes,
-                created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+            INSERT_SENSOR,
             params![
-                self.sensor_name, 
-                self.sensor_type, 
-                self.location, 
-                self.unit,
-                self.threshold_min, 
-                self.threshold_max, 
-                self.calibration_date, 
-                self.notes,
+                sensor.sensor_name, 
+                sensor.sensor_type, 
+                sensor.location, 
+                sensor.unit,
+                sensor.threshold_min, 
+                sensor.threshold_max, 
+                sensor.calibration_date, 
+                sensor.notes,
+                sensor.display_color,
+                sensor.display_order,
+                sensor.deadband,
+                sensor.retention_days,
+                sensor.valid_min,
+                sensor.valid_max,
                 now, 
                 now
             ],
@@ -219,37 +781,224 @@ impl Sensor {
         }
         
         let id = conn.last_insert_rowid();
+        let new = Self::get_by_id_with(conn, id)?;
+        SensorAuditEntry::record(conn, id, AuditAction::Create, None, Some(&new))?;
+        
         Ok(id)
     }
     
+    /// Check a sensor type against the allowed set plus any configured extras
+    fn validate_type(sensor_type: &str) -> Result<()> {
+        let config = config::get();
+        let mut allowed: Vec<&str> = match config.sensor_type_allowlist {
+            Some(ref allowlist) => allowlist.iter().map(String::as_str).collect(),
+            None => DEFAULT_SENSOR_TYPES.to_vec(),
+        };
+        allowed.extend(config.extra_sensor_types.iter().map(String::as_str));
+        
+        if !allowed.contains(&sensor_type) {
+            return Err(AppError::BadRequest(format!(
+                "Unknown sensor_type '{}'; expected one of: {}",
+                sensor_type,
+                allowed.join(", ")
+            ))
+            .into());
+        }
+        
+        Ok(())
+    }
+    
+    /// Check that a display color is a '#RGB' or '#RRGGBB' hex code
+    fn validate_color(color: Option<&str>) -> Result<()> {
+        if let Some(color) = color {
+            let valid = color
+                .strip_prefix('#')
+                .is_some_and(|hex| matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit()));
+            
+            if !valid {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid display_color '{}'; expected a hex color like #1f77b4",
+                    color
+                ))
+                .into());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Reject negative or non-finite deadbands
+    fn validate_deadband(deadband: Option<f64>) -> Result<()> {
+        if let Some(deadband) = deadband {
+            if !deadband.is_finite() || deadband < 0.0 {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid deadband {}; expected a non-negative number",
+                    deadband
+                ))
+                .into());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Reject retention periods shorter than a day
+    fn validate_retention(retention_days: Option<i64>) -> Result<()> {
+        if let Some(days) = retention_days {
+            if days < 1 {
+                return Err(AppError::BadRequest(format!(
+                    "Invalid retention_days {}; expected at least 1",
+                    days
+                ))
+                .into());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Reject non-finite bounds and ranges whose minimum exceeds their maximum
+    fn validate_range(valid_min: Option<f64>, valid_max: Option<f64>) -> Result<()> {
+        for (name, bound) in [("valid_min", valid_min), ("valid_max", valid_max)] {
+            if bound.is_some_and(|bound| !bound.is_finite()) {
+                return Err(AppError::BadRequest(format!("{} must be a finite number", name)).into());
+            }
+        }
+        
+        if let (Some(min), Some(max)) = (valid_min, valid_max) {
+            if min > max {
+                return Err(AppError::BadRequest(format!(
+                    "valid_min {} exceeds valid_max {}",
+                    min, max
+                ))
+                .into());
+            }
+        }
+        
+        Ok(())
+    }
+    
+    /// Reject negative epochs and inverted created_at ranges
+    fn validate_time_filters(query: &SensorQuery) -> Result<()> {
+        let filters = [
+            ("created_after", query.created_after),
+            ("created_before", query.created_before),
+            ("updated_after", query.updated_after),
+        ];
+        
+        for (name, value) in filters {
+            if value.is_some_and(|ts| ts < 0) {
+                return Err(AppError::BadRequest(format!("{} must be a non-negative epoch timestamp", name)).into());
+            }
+        }
+        
+        if let (Some(after), Some(before)) = (query.created_after, query.created_before) {
+            if after > before {
+                return Err(AppError::BadRequest("created_after must not be later than created_before".to_string()).into());
+            }
+        }
+        
+        Ok(())
+    }
+    
     /// Get a sensor by ID
     pub fn get_by_id(id: i64) -> Result<SensorResponse> {
         let conn = get_connection()?;
-        
+        Self::get_by_id_with(&conn, id)
+    }
+    
+    /// Get a sensor by ID on a connection the caller already holds
+    pub fn get_by_id_with(conn: &Connection, id: i64) -> Result<SensorResponse> {
         let sensor = conn.query_row(
             "SELECT * FROM sensors WHERE sensor_id = ?",
             params![id],
-            |row| Self::from_row(row),
+            Self::from_row,
         )?;
         
         Ok(sensor)
     }
     
+    /// Get a sensor by ID, or None when there is no such sensor
+    fn find_with(conn: &Connection, id: i64) -> Result<Option<SensorResponse>> {
+        let sensor = conn
+            .query_row("SELECT * FROM sensors WHERE sensor_id = ?", params![id], Self::from_row)
+            .optional()?;
+        
+        Ok(sensor)
+    }
+    
     /// Get all sensors with optional filtering
     pub fn get_all(query: &SensorQuery) -> Result<Vec<SensorResponse>> {
+        Self::validate_time_filters(query)?;
+        
         let conn = get_connection()?;
         
         let mut sql = String::from("SELECT * FROM sensors WHERE 1=1");
-        let mut params = Vec::new();
+        let mut params: Vec<Value> = Vec::new();
+        
+        if !query.include_disabled.unwrap_or(false) {
+            sql.push_str(" AND disabled_at IS NULL");
+        }
         
         if let Some(ref sensor_type) = query.sensor_type {
-            sql.push_str(" AND sensor_type = ?");
-            params.push(sensor_type.to_string());
+            let types: Vec<&str> = sensor_type
+                .split(',')
+                .map(str::trim)
+                .filter(|sensor_type| !sensor_type.is_empty())
+                .collect();
+            if types.is_empty() {
+                return Err(AppError::BadRequest("sensor_type must name at least one type".to_string()).into());
+            }
+            sql.push_str(&format!(" AND sensor_type IN ({})", vec!["?"; types.len()].join(", ")));
+            params.extend(types.into_iter().map(|sensor_type| Value::Text(sensor_type.to_string())));
         }
         
         if let Some(ref location) = query.location {
             sql.push_str(" AND location = ?");
-            params.push(location.to_string());
+            params.push(Value::Text(location.to_string()));
+        }
+        
+        if let Some(ref name_contains) = query.name_contains {
+            // Search for % and _ literally rather than as wildcards
+            let escaped = name_contains
+                .replace('\\', "\\\\")
+                .replace('%', "\\%")
+                .replace('_', "\\_");
+            sql.push_str(" AND sensor_name LIKE ? ESCAPE '\\'");
+            params.push(Value::Text(format!("%{}%", escaped)));
+        }
+        
+        if let Some(ref tag) = query.tag {
+            sql.push_str(" AND sensor_id IN (SELECT sensor_id FROM sensor_tags WHERE tag = ?)");
+            params.push(Value::Text(Self::normalize_tag(tag)?));
+        }
+        
+        if let Some(created_after) = query.created_after {
+            sql.push_str(" AND created_at >= ?");
+            params.push(Value::Integer(created_after));
+        }
+        
+        if let Some(created_before) = query.created_before {
+            sql.push_str(" AND created_at <= ?");
+            params.push(Value::Integer(created_before));
+        }
+        
+        if let Some(updated_after) = query.updated_after {
+            sql.push_str(" AND updated_at >= ?");
+            params.push(Value::Integer(updated_after));
+        }
+        
+        match query.sort.as_deref() {
+            // Sensors without a display_order go last
+            Some("display_order") => sql.push_str(" ORDER BY display_order IS NULL, display_order, sensor_id"),
+            Some(other) => {
+                return Err(AppError::BadRequest(format!(
+                    "Unknown sort '{}'; expected display_order",
+                    other
+                ))
+                .into());
+            }
+            None => sql.push_str(" ORDER BY sensor_id"),
         }
         
         let mut stmt = conn.prepare(&sql)?;
@@ -265,54 +1014,522 @@ impl Sensor {
         Ok(sensors)
     }
     
-    /// Update a sensor
-    pub fn update(&self, id: i64) -> Result<()> {
-        let conn = get_connection()?;
+    /// Update only the fields present in `patch`, leaving the rest untouched
+    pub fn patch(id: i64, patch: &SensorPatch) -> Result<()> {
+        if let Some(sensor_type) = &patch.sensor_type {
+            Self::validate_type(sensor_type)?;
+        }
+        Self::validate_color(patch.display_color.as_deref())?;
+        Self::validate_deadband(patch.deadband)?;
+        Self::validate_retention(patch.retention_days)?;
         
-        let result = conn.execute(
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
+        Self::ensure_writable(&tx, id)?;
+        
+        let Some(old) = Self::find_with(&tx, id)? else {
+            return Err(AppError::NotFound(format!("Sensor with ID {} not found", id)).into());
+        };
+        
+        // A patch may move one bound past the other's stored value
+        if patch.valid_min.is_some() || patch.valid_max.is_some() {
+            Self::validate_range(patch.valid_min.or(old.valid_min), patch.valid_max.or(old.valid_max))?;
+        }
+        
+        tx.execute(
             "UPDATE sensors SET 
                 sensor_name = COALESCE(?, sensor_name),
                 sensor_type = COALESCE(?, sensor_type),
-                location = ?,
-                unit = ?,
-                threshold_min = ?,
-                threshold_max = ?,
-                calibration_date = ?,
-                notes = ?
+                location = COALESCE(?, location),
+                unit = COALESCE(?, unit),
+                threshold_min = COALESCE(?, threshold_min),
+                threshold_max = COALESCE(?, threshold_max),
+                calibration_date = COALESCE(?, calibration_date),
+                notes = COALESCE(?, notes),
+                display_color = COALESCE(?, display_color),
+                display_order = COALESCE(?, display_order),
+                deadband = COALESCE(?, deadband),
+                retention_days = COALESCE(?, retention_days),
+                valid_min = COALESCE(?, valid_min),
+                valid_max = COALESCE(?, valid_max)
              WHERE sensor_id = ?",
             params![
-                self.sensor_name, 
-                self.sensor_type, 
-                self.location, 
-                self.unit,
-                self.threshold_min, 
-                self.threshold_max, 
-                self.calibration_date, 
-                self.notes,
+                patch.sensor_name, 
+                patch.sensor_type, 
+                patch.location, 
+                patch.unit,
+                patch.threshold_min, 
+                patch.threshold_max, 
+                patch.calibration_date, 
+                patch.notes,
+                patch.display_color,
+                patch.display_order,
+                patch.deadband,
+                patch.retention_days,
+                patch.valid_min,
+                patch.valid_max,
                 id
             ],
         )?;
         
-        if result == 0 {
-            return Err(anyhow::anyhow!("Sensor not found or no changes made"));
+        let new = Self::get_by_id_with(&tx, id)?;
+        SensorAuditEntry::record(&tx, id, AuditAction::Update, Some(&old), Some(&new))?;
+        tx.commit()?;
+        // Cached results carry the sensor's name and unit
+        cache::invalidate_sensor(id);
+        
+        Ok(())
+    }
+    
+    /// Export the configuration of every sensor, ordered by name
+    pub fn export_config() -> Result<SensorConfigDocument> {
+        let conn = get_connection()?;
+        
+        let mut stmt = conn.prepare(
+            "SELECT sensor_name, sensor_type, location, unit, threshold_min,
+                    threshold_max, calibration_date, notes, display_color, display_order, deadband,
+                    retention_days, valid_min, valid_max
+             FROM sensors
+             ORDER BY sensor_name, sensor_id"
+        )?;
+        let sensors = stmt
+            .query_map([], |row| {
+                Ok(SensorConfig {
+                    sensor_name: row.get(0)?,
+                    sensor_type: row.get(1)?,
+                    location: row.get(2)?,
+                    unit: row.get(3)?,
+                    threshold_min: row.get(4)?,
+                    threshold_max: row.get(5)?,
+                    calibration_date: row.get(6)?,
+                    notes: row.get(7)?,
+                    display_color: row.get(8)?,
+                    display_order: row.get(9)?,
+                    deadband: row.get(10)?,
+                    retention_days: row.get(11)?,
+                    valid_min: row.get(12)?,
+                    valid_max: row.get(13)?,
+                })
+            })?
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        Ok(SensorConfigDocument {
+            version: SENSOR_CONFIG_VERSION,
+            sensors,
+        })
+    }
+    
+    /// Import a configuration document, upserting sensors by name
+    ///
+    /// The whole document is validated first and applied in one transaction,
+    /// so a rejected document leaves the sensor table untouched.
+    pub fn import_config(document: &SensorConfigDocument) -> Result<SensorConfigImport> {
+        Self::validate_config(document)?;
+        
+        let mut conn = get_connection()?;
+        // Names are looked up before anything is written, so take the write
+        // lock first or a concurrent create would invalidate the lookup
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        
+        let now = crate::utils::current_timestamp();
+        let mut import = SensorConfigImport { created: 0, updated: 0 };
+        
+        for sensor in &document.sensors {
+            let existing: Vec<i64> = {
+                let mut stmt = tx.prepare("SELECT sensor_id FROM sensors WHERE sensor_name = ?")?;
+                let ids = stmt.query_map(params![sensor.sensor_name], |row| row.get(0))?;
+                ids.collect::<Result<_, _>>()?
+            };
+            
+            match existing.as_slice() {
+                [] => {
+                    tx.execute(
+                        INSERT_SENSOR,
+                        params![
+                            sensor.sensor_name,
+                            sensor.sensor_type,
+                            sensor.location,
+                            sensor.unit,
+                            sensor.threshold_min,
+                            sensor.threshold_max,
+                            sensor.calibration_date,
+                            sensor.notes,
+                            sensor.display_color,
+                            sensor.display_order,
+                            sensor.deadband,
+                            sensor.retention_days,
+                            sensor.valid_min,
+                            sensor.valid_max,
+                            now,
+                            now
+                        ],
+                    )?;
+                    let id = tx.last_insert_rowid();
+                    let new = Self::get_by_id_with(&tx, id)?;
+                    SensorAuditEntry::record(&tx, id, AuditAction::Create, None, Some(&new))?;
+                    import.created += 1;
+                }
+                [id] => {
+                    Self::ensure_writable(&tx, *id)?;
+                    let old = Self::get_by_id_with(&tx, *id)?;
+                    tx.execute(
+                        "UPDATE sensors SET
+                            sensor_type = ?,
+                            location = ?,
+                            unit = ?,
+                            threshold_min = ?,
+                            threshold_max = ?,
+                            calibration_date = ?,
+                            notes = ?,
+                            display_color = ?,
+                            display_order = ?,
+                            deadband = ?,
+                            retention_days = ?,
+                            valid_min = ?,
+                            valid_max = ?
+                         WHERE sensor_id = ?",
+                        params![
+                            sensor.sensor_type,
+                            sensor.location,
+                            sensor.unit,
+                            sensor.threshold_min,
+                            sensor.threshold_max,
+                            sensor.calibration_date,
+                            sensor.notes,
+                            sensor.display_color,
+                            sensor.display_order,
+                            sensor.deadband,
+                            sensor.retention_days,
+                            sensor.valid_min,
+                            sensor.valid_max,
+                            id
+                        ],
+                    )?;
+                    let new = Self::get_by_id_with(&tx, *id)?;
+                    SensorAuditEntry::record(&tx, *id, AuditAction::Update, Some(&old), Some(&new))?;
+                    import.updated += 1;
+                }
+                _ => {
+                    return Err(AppError::Conflict(format!(
+                        "Sensor name '{}' matches several sensors",
+                        sensor.sensor_name
+                    ))
+                    .into());
+                }
+            }
+        }
+        
+        tx.commit()?;
+        cache::clear();
+        
+        Ok(import)
+    }
+    
+    /// Check a configuration document before anything is written
+    fn validate_config(document: &SensorConfigDocument) -> Result<()> {
+        if document.version != SENSOR_CONFIG_VERSION {
+            return Err(AppError::BadRequest(format!(
+                "Unsupported config version {}; expected {}",
+                document.version, SENSOR_CONFIG_VERSION
+            ))
+            .into());
+        }
+        
+        let mut names = std::collections::HashSet::new();
+        
+        for sensor in &document.sensors {
+            if sensor.sensor_name.trim().is_empty() {
+                return Err(AppError::BadRequest("sensor_name must not be empty".to_string()).into());
+            }
+            
+            if !names.insert(sensor.sensor_name.as_str()) {
+                return Err(AppError::BadRequest(format!(
+                    "Sensor name '{}' appears more than once",
+                    sensor.sensor_name
+                ))
+                .into());
+            }
+            
+            Self::validate_type(&sensor.sensor_type)?;
+            Self::validate_color(sensor.display_color.as_deref())?;
+            Self::validate_deadband(sensor.deadband)?;
+            Self::validate_retention(sensor.retention_days)?;
+            Self::validate_range(sensor.valid_min, sensor.valid_max)?;
+            
+            if let (Some(min), Some(max)) = (sensor.threshold_min, sensor.threshold_max) {
+                if min > max {
+                    return Err(AppError::BadRequest(format!(
+                        "threshold_min exceeds threshold_max for sensor '{}'",
+                        sensor.sensor_name
+                    ))
+                    .into());
+                }
+            }
         }
         
         Ok(())
     }
     
-    /// Delete a sensor
-    pub fn delete(id: i64) -> Result<()> {
+    /// Archive a sensor, freezing it and its readings
+    pub fn archive(id: i64) -> Result<()> {
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
+        
+        let Some(old) = Self::find_with(&tx, id)? else {
+            return Err(AppError::NotFound(format!("Sensor {} not found", id)).into());
+        };
+        
+        tx.execute("UPDATE sensors SET archived = 1 WHERE sensor_id = ?", params![id])?;
+        
+        let new = Self::get_by_id_with(&tx, id)?;
+        SensorAuditEntry::record(&tx, id, AuditAction::Update, Some(&old), Some(&new))?;
+        tx.commit()?;
+        
+        Ok(())
+    }
+    
+    /// Whether a sensor with this id exists
+    pub fn exists(conn: &Connection, id: i64) -> Result<bool> {
+        let exists = conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM sensors WHERE sensor_id = ?)",
+            params![id],
+            |row| row.get(0),
+        )?;
+        
+        Ok(exists)
+    }
+    
+    /// Reject writes that target an archived or disabled sensor
+    ///
+    /// Unknown sensors pass, so the caller's own not-found or foreign key
+    /// handling still applies.
+    pub fn ensure_writable(conn: &Connection, id: i64) -> Result<()> {
+        let state: Option<(bool, bool)> = conn
+            .query_row(
+                "SELECT archived, disabled_at IS NOT NULL FROM sensors WHERE sensor_id = ?",
+                params![id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        
+        match state {
+            Some((_, true)) => Err(AppError::Conflict(format!("Sensor {} is disabled", id)).into()),
+            Some((true, _)) => Err(AppError::Conflict(format!("Sensor {} is archived", id)).into()),
+            _ => Ok(()),
+        }
+    }
+    
+    /// Soft-delete a sensor, hiding it from listings while keeping its readings
+    ///
+    /// Disabling twice keeps the original `disabled_at`.
+    pub fn disable(id: i64) -> Result<()> {
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
+        
+        if !Self::disable_with(&tx, id)? {
+            return Err(AppError::NotFound(format!("Sensor {} not found", id)).into());
+        }
+        tx.commit()?;
+        
+        cache::invalidate_sensor(id);
+        
+        Ok(())
+    }
+    
+    /// Disable a sensor and audit it on the caller's transaction, reporting whether it existed
+    fn disable_with(conn: &Connection, id: i64) -> Result<bool> {
+        let Some(old) = Self::find_with(conn, id)? else {
+            return Ok(false);
+        };
+        
+        conn.execute(
+            "UPDATE sensors SET disabled_at = COALESCE(disabled_at, ?) WHERE sensor_id = ?",
+            params![crate::utils::current_timestamp(), id],
+        )?;
+        
+        let new = Self::get_by_id_with(conn, id)?;
+        SensorAuditEntry::record(conn, id, AuditAction::Delete, Some(&old), Some(&new))?;
+        
+        Ok(true)
+    }
+    
+    /// Tag a sensor, returning all of its tags
+    ///
+    /// Tags are stored lowercase, and adding one the sensor already has is a no-op.
+    pub fn add_tag(id: i64, tag: &str) -> Result<Vec<String>> {
+        let tag = Self::normalize_tag(tag)?;
         let conn = get_connection()?;
         
-        let result = conn.execute("DELETE FROM sensors WHERE sensor_id = ?", params![id])?;
+        if !Self::exists(&conn, id)? {
+            return Err(AppError::NotFound(format!("Sensor {} not found", id)).into());
+        }
+        
+        conn.execute(
+            "INSERT OR IGNORE INTO sensor_tags (sensor_id, tag) VALUES (?, ?)",
+            params![id, tag],
+        )?;
+        
+        Self::tags_with(&conn, id)
+    }
+    
+    /// Remove a tag from a sensor, returning the tags it has left
+    pub fn remove_tag(id: i64, tag: &str) -> Result<Vec<String>> {
+        let tag = Self::normalize_tag(tag)?;
+        let conn = get_connection()?;
+        
+        let result = conn.execute(
+            "DELETE FROM sensor_tags WHERE sensor_id = ? AND tag = ?",
+            params![id, tag],
+        )?;
         
         if result == 0 {
+            let message = if Self::exists(&conn, id)? {
+                format!("Sensor {} has no tag '{}'", id, tag)
+            } else {
+                format!("Sensor {} not found", id)
+            };
+            return Err(AppError::NotFound(message).into());
+        }
+        
+        Self::tags_with(&conn, id)
+    }
+    
+    /// Get a sensor's tags in alphabetical order
+    pub fn tags_with(conn: &Connection, id: i64) -> Result<Vec<String>> {
+        let mut stmt = conn.prepare("SELECT tag FROM sensor_tags WHERE sensor_id = ? ORDER BY tag")?;
+        let tags = stmt
+            .query_map(params![id], |row| row.get(0))?
+            .collect::<Result<Vec<String>, _>>()?;
+        
+        Ok(tags)
+    }
+    
+    /// Trim and lowercase a tag, rejecting blank, overlong or spaced ones
+    fn normalize_tag(tag: &str) -> Result<String, AppError> {
+        let tag = tag.trim().to_lowercase();
+        if tag.is_empty() || tag.len() > MAX_TAG_LENGTH || tag.contains(|c: char| c.is_whitespace() || c == ',') {
+            return Err(AppError::BadRequest(format!(
+                "Invalid tag '{}': expected 1 to {} characters without spaces or commas",
+                tag, MAX_TAG_LENGTH
+            )));
+        }
+        
+        Ok(tag)
+    }
+    
+    /// Delete a sensor
+    pub fn delete(id: i64) -> Result<()> {
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
+        
+        if !Self::delete_with(&tx, id)? {
             return Err(anyhow::anyhow!("Sensor not found"));
         }
         
+        tx.commit()?;
+        cache::clear();
+        
         Ok(())
     }
     
+    /// Delete several sensors in one transaction, reporting the outcome for each
+    ///
+    /// With `hard`, as with `delete`, readings, sessions and tokens of a
+    /// deleted sensor go with it; otherwise the sensors are disabled as with
+    /// `disable`. Unknown IDs are reported rather than failing the whole
+    /// batch, unless `atomic` is set: then they are not found and nothing is
+    /// deleted.
+    pub fn delete_many(ids: &[i64], hard: bool, atomic: bool) -> Result<Vec<SensorDeleteResult>> {
+        let mut conn = get_connection()?;
+        let tx = conn.transaction()?;
+        
+        let mut results = Vec::with_capacity(ids.len());
+        for &id in ids {
+            let deleted = if hard { Self::delete_with(&tx, id)? } else { Self::disable_with(&tx, id)? };
+            results.push(SensorDeleteResult {
+                sensor_id: id,
+                deleted,
+                error: (!deleted).then(|| "Sensor not found".to_string()),
+            });
+        }
+        
+        let missing: Vec<String> = results
+            .iter()
+            .filter(|result| !result.deleted)
+            .map(|result| result.sensor_id.to_string())
+            .collect();
+        if atomic && !missing.is_empty() {
+            // Dropping the transaction rolls back the deletes already made
+            return Err(AppError::NotFound(format!("Sensors not found: {}", missing.join(", "))).into());
+        }
+        
+        tx.commit()?;
+        cache::clear();
+        
+        Ok(results)
+    }
+    
+    /// Delete a sensor and audit it on the caller's transaction, reporting whether it existed
+    fn delete_with(conn: &Connection, id: i64) -> Result<bool> {
+        let Some(old) = Self::find_with(conn, id)? else {
+            return Ok(false);
+        };
+        
+        conn.execute("DELETE FROM sensors WHERE sensor_id = ?", params![id])?;
+        SensorAuditEntry::record(conn, id, AuditAction::Delete, Some(&old), None)?;
+        
+        Ok(true)
+    }
+    
+    /// Get ingest statistics for a sensor, including daily counts for the last week
+    pub fn stats(id: i64) -> Result<SensorStats> {
+        let conn = get_connection()?;
+        Self::stats_with(&conn, id)
+    }
+    
+    /// Get ingest statistics for a sensor on a connection the caller already holds
+    pub fn stats_with(conn: &Connection, id: i64) -> Result<SensorStats> {
+        // Make sure the sensor exists so unknown IDs surface as not found
+        Self::get_by_id_with(conn, id)?;
+        
+        let (ingest_count, last_ingest): (i64, Option<i64>) = conn.query_row(
+            "SELECT COUNT(*), MAX(timestamp) FROM readings WHERE sensor_id = ?",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        
+        // Count readings per UTC day over the last seven days, today included
+        let today = crate::utils::current_timestamp() / SECONDS_PER_DAY;
+        let first_day = today - 6;
+        
+        let mut stmt = conn.prepare(
+            "SELECT timestamp / 86400 AS day, COUNT(*) FROM readings
+             WHERE sensor_id = ? AND timestamp >= ?
+             GROUP BY day"
+        )?;
+        let counts = stmt
+            .query_map(params![id, first_day * SECONDS_PER_DAY], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+            })?
+            .collect::<Result<std::collections::HashMap<_, _>, _>>()?;
+        
+        let readings_per_day = (first_day..=today)
+            .map(|day| DailyReadingCount {
+                date: DateTime::from_timestamp(day * SECONDS_PER_DAY, 0)
+                    .map(|date| date.format("%Y-%m-%d").to_string())
+                    .unwrap_or_default(),
+                count: counts.get(&day).copied().unwrap_or(0),
+            })
+            .collect();
+        
+        Ok(SensorStats {
+            sensor_id: id,
+            ingest_count,
+            last_ingest: last_ingest.and_then(|ts| DateTime::from_timestamp(ts, 0)),
+            readings_per_day,
+        })
+    }
+    
     /// Convert a database row to a SensorResponse
     fn from_row(row: &Row) -> Result<SensorResponse, rusqlite::Error> {
         let sensor_id: i64 = row.get("sensor_id")?;
@@ -322,20 +1539,18 @@ impl Sensor {
         let unit: Option<String> = row.get("unit")?;
         let threshold_min: Option<f64> = row.get("threshold_min")?;
         let threshold_max: Option<f64> = row.get("threshold_max")?;
-        let calibration_date: Option<i64> = row.get("calibration_date")?;
+        let calibration_date = get_optional_datetime(row, "calibration_date")?;
         let notes: Option<String> = row.get("notes")?;
-        let created_at: i64 = row.get("created_at")?;
-        let updated_at: i64 = row.get("updated_at")?;
-        
-        let calibration_date = calibration_date.map(|ts| {
-            DateTime::from_timestamp(ts, 0).expect("Invalid timestamp")
-        });
-        
-        let created_at = DateTime::from_timestamp(created_at, 0)
-            .expect("Invalid timestamp");
-        
-        let updated_at = DateTime::from_timestamp(updated_at, 0)
-            .expect("Invalid timestamp");
+        let display_color: Option<String> = row.get("display_color")?;
+        let display_order: Option<i64> = row.get("display_order")?;
+        let deadband: Option<f64> = row.get("deadband")?;
+        let retention_days: Option<i64> = row.get("retention_days")?;
+        let valid_min: Option<f64> = row.get("valid_min")?;
+        let valid_max: Option<f64> = row.get("valid_max")?;
+        let created_at = get_datetime(row, "created_at")?;
+        let updated_at = get_datetime(row, "updated_at")?;
+        let archived: bool = row.get("archived")?;
+        let disabled_at = get_optional_datetime(row, "disabled_at")?;
         
         Ok(SensorResponse {
             sensor_id,
@@ -347,8 +1562,16 @@ impl Sensor {
             threshold_max,
             calibration_date,
             notes,
+            display_color,
+            display_order,
+            deadband,
+            retention_days,
+            valid_min,
+            valid_max,
             created_at,
             updated_at,
+            archived,
+            disabled_at,
         })
     }
 }
\ No newline at end of file
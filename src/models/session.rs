@@ -1,10 +1,158 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, Connection, Row};
+use rusqlite::{params, Connection, ErrorCode, Row, TransactionBehavior};
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
-use crate::db::get_connection;
+use crate::db::{get_connection, get_datetime, get_optional_datetime};
+use crate::models::Sensor;
+use crate::utils::error::AppError;
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use std::sync::{Arc, Barrier};
+    use crate::{
+        db::share_test_pool,
+        models::{LoggingSession, Reading},
+        utils::{
+            current_timestamp,
+            error::AppError,
+            test_utils::{setup_test_db, create_test_sensor},
+        },
+    };
+
+    #[test]
+    fn test_concurrent_starts_create_one_session() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        // Release both starts at once so their check-then-insert steps overlap
+        let barrier = Arc::new(Barrier::new(2));
+        let starts: Vec<_> = (0..2)
+            .map(|_| {
+                let (pool, barrier) = (pool.clone(), barrier.clone());
+                std::thread::spawn(move || {
+                    share_test_pool(pool);
+                    barrier.wait();
+                    LoggingSession {
+                        session_id: None,
+                        sensor_id,
+                        start_time: None,
+                        end_time: None,
+                        sample_rate: None,
+                        notes: None,
+                    }
+                    .start()
+                })
+            })
+            .collect();
+        let results: Vec<Result<i64>> = starts.into_iter().map(|start| start.join().unwrap()).collect();
+        
+        assert_eq!(results.iter().filter(|result| result.is_ok()).count(), 1);
+        let err = results.into_iter().find_map(Result::err).expect("one start fails");
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::Conflict(_))));
+        
+        let active: i64 = conn.query_row(
+            "SELECT COUNT(*) FROM logging_sessions WHERE sensor_id = ? AND end_time IS NULL",
+            [sensor_id],
+            |row| row.get(0),
+        )?;
+        assert_eq!(active, 1);
+        
+        // The index refuses a second open session even when the check is bypassed
+        let err = conn
+            .execute("INSERT INTO logging_sessions (sensor_id, start_time) VALUES (?, 0)", [sensor_id])
+            .unwrap_err();
+        assert_eq!(err.sqlite_error_code(), Some(rusqlite::ErrorCode::ConstraintViolation));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_start_rejects_non_positive_sample_rate() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let session = |sample_rate| LoggingSession {
+            session_id: None,
+            sensor_id,
+            start_time: None,
+            end_time: None,
+            sample_rate,
+            notes: None,
+        };
+        
+        for sample_rate in [0, -5] {
+            let err = session(Some(sample_rate)).start().unwrap_err();
+            assert!(
+                matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))),
+                "sample_rate {} should be rejected, got {:?}",
+                sample_rate,
+                err
+            );
+        }
+        assert!(LoggingSession::get_active(sensor_id)?.is_none());
+        
+        let session_id = session(Some(10)).start()?;
+        let active = LoggingSession::get_active(sensor_id)?.expect("session should be active");
+        assert_eq!(active.session_id, session_id);
+        assert_eq!(active.sample_rate, Some(10));
+        
+        Ok(())
+    }
+    
+    #[test]
+    fn test_gaps_in_ongoing_session() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        let now = current_timestamp();
+        
+        // Started ten minutes ago at one reading a minute, but silent for the last five
+        let session_id = LoggingSession {
+            session_id: None,
+            sensor_id,
+            start_time: Some(now - 600),
+            end_time: None,
+            sample_rate: Some(60),
+            notes: None,
+        }
+        .start()?;
+        let readings: Vec<Reading> = (0..5)
+            .map(|minute| Reading {
+                timestamp: Some(now - 600 + minute * 60),
+                sensor_id,
+                value: Some(20.0),
+                ..Default::default()
+            })
+            .collect();
+        Reading::bulk_insert(&readings)?;
+        
+        let gaps = LoggingSession::gaps(session_id)?;
+        assert_eq!(gaps.len(), 1);
+        assert_eq!(gaps[0].start.timestamp(), now - 360);
+        assert!(gaps[0].duration_seconds >= 360);
+        
+        // Without a sample rate there is nothing to measure against
+        LoggingSession::end(sensor_id)?;
+        let session_id = LoggingSession {
+            session_id: None,
+            sensor_id,
+            start_time: None,
+            end_time: None,
+            sample_rate: None,
+            notes: None,
+        }
+        .start()?;
+        let err = LoggingSession::gaps(session_id).unwrap_err();
+        assert!(matches!(err.downcast_ref::<AppError>(), Some(AppError::BadRequest(_))));
+        
+        Ok(())
+    }
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct LoggingSession {
@@ -16,6 +164,17 @@ pub struct LoggingSession {
     pub notes: Option<String>,
 }
 
+/// How many sample periods may pass between readings before it counts as a gap
+const GAP_FACTOR: i64 = 2;
+
+/// Stretch of a session with no readings for longer than `GAP_FACTOR` sample periods
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionGap {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub duration_seconds: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LoggingSessionResponse {
     pub session_id: i64,
@@ -29,11 +188,28 @@ pub struct LoggingSessionResponse {
 
 impl LoggingSession {
     /// Start a new logging session
+    ///
+    /// The check for an active session and the insert share an immediate
+    /// transaction, so concurrent starts for a sensor queue up and only the
+    /// first succeeds. The partial unique index on open sessions backs this up.
     pub fn start(&self) -> Result<i64> {
-        let conn = get_connection()?;
+        // Coverage and gap calculations divide by the sample rate
+        if let Some(sample_rate) = self.sample_rate {
+            if sample_rate <= 0 {
+                return Err(AppError::BadRequest(format!(
+                    "sample_rate must be a positive number of seconds, got {}",
+                    sample_rate
+                ))
+                .into());
+            }
+        }
+        
+        let mut conn = get_connection()?;
+        let tx = conn.transaction_with_behavior(TransactionBehavior::Immediate)?;
+        Sensor::ensure_writable(&tx, self.sensor_id)?;
         
         // Check if there's already an active session for this sensor
-        let active_count: i64 = conn.query_row(
+        let active_count: i64 = tx.query_row(
             "SELECT COUNT(*) FROM logging_sessions 
              WHERE sensor_id = ? AND end_time IS NULL",
             params![self.sensor_id],
@@ -41,7 +217,7 @@ impl LoggingSession {
         )?;
         
         if active_count > 0 {
-            return Err(anyhow::anyhow!("Sensor already has an active logging session"));
+            return Err(self.already_active().into());
         }
         
         // Use current time if start_time is not provided
@@ -52,27 +228,37 @@ impl LoggingSession {
                 .as_secs() as i64
         });
         
-        let result = conn.execute(
-            "INSERT INTO logging_sessions (
-                sensor_id, start_time, end_time, sample_rate, notes
-            ) VALUES (?, ?, ?, ?, ?)",
-            params![
-                self.sensor_id,
-                start_time,
-                self.end_time,
-                self.sample_rate,
-                self.notes
-            ],
-        )?;
+        let result = tx
+            .execute(
+                "INSERT INTO logging_sessions (
+                    sensor_id, start_time, end_time, sample_rate, notes
+                ) VALUES (?, ?, ?, ?, ?)",
+                params![
+                    self.sensor_id,
+                    start_time,
+                    self.end_time,
+                    self.sample_rate,
+                    self.notes
+                ],
+            )
+            .map_err(|err| match err.sqlite_error_code() {
+                Some(ErrorCode::ConstraintViolation) if err.to_string().contains("UNIQUE") => self.already_active(),
+                _ => AppError::Database(err),
+            })?;
         
         if result == 0 {
             return Err(anyhow::anyhow!("Failed to start logging session"));
         }
         
-        let id = conn.last_insert_rowid();
+        let id = tx.last_insert_rowid();
+        tx.commit()?;
         Ok(id)
     }
     
+    fn already_active(&self) -> AppError {
+        AppError::Conflict(format!("Sensor {} already has an active logging session", self.sensor_id))
+    }
+    
     /// End an active logging session
     pub fn end(sensor_id: i64) -> Result<()> {
         let conn = get_connection()?;
@@ -118,16 +304,84 @@ impl LoggingSession {
         Ok(sessions)
     }
     
+    /// Get a session by id
+    pub fn get_by_id(session_id: i64) -> Result<LoggingSessionResponse> {
+        let conn = get_connection()?;
+        
+        let session = conn.query_row(
+            "SELECT * FROM logging_sessions WHERE session_id = ?",
+            params![session_id],
+            Self::from_row,
+        )?;
+        
+        Ok(session)
+    }
+    
+    /// Find stretches of a session where readings were missing
+    ///
+    /// A gap is any interval longer than `GAP_FACTOR` times the session's
+    /// sample_rate between consecutive readings. The session start and end
+    /// (now, for an ongoing session) count as boundaries, so a sensor that
+    /// was late to report or has gone quiet shows up too.
+    pub fn gaps(session_id: i64) -> Result<Vec<SessionGap>> {
+        let session = Self::get_by_id(session_id)?;
+        let sample_rate = session.sample_rate.ok_or_else(|| {
+            AppError::BadRequest(format!("Session {} has no sample_rate to detect gaps against", session_id))
+        })?;
+        
+        let start = session.start_time.timestamp();
+        let end = match session.end_time {
+            Some(end_time) => end_time.timestamp(),
+            None => SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("Time went backwards")?
+                .as_secs() as i64,
+        };
+        
+        let conn = get_connection()?;
+        let mut stmt = conn.prepare(
+            "SELECT timestamp FROM readings 
+             WHERE sensor_id = ? AND timestamp >= ? AND timestamp <= ? 
+             ORDER BY timestamp ASC"
+        )?;
+        let timestamps = stmt.query_map(params![session.sensor_id, start, end], |row| row.get::<_, i64>(0))?;
+        
+        let mut gaps = Vec::new();
+        let mut previous = start;
+        let mut check = |from: i64, to: i64| {
+            if to - from > sample_rate * GAP_FACTOR {
+                gaps.push(SessionGap {
+                    start: DateTime::from_timestamp(from, 0).unwrap_or_default(),
+                    end: DateTime::from_timestamp(to, 0).unwrap_or_default(),
+                    duration_seconds: to - from,
+                });
+            }
+        };
+        
+        for timestamp in timestamps {
+            let timestamp = timestamp?;
+            check(previous, timestamp);
+            previous = timestamp;
+        }
+        check(previous, end);
+        
+        Ok(gaps)
+    }
+    
     /// Get active session for a sensor (if any)
     pub fn get_active(sensor_id: i64) -> Result<Option<LoggingSessionResponse>> {
         let conn = get_connection()?;
-        
+        Self::get_active_with(&conn, sensor_id)
+    }
+    
+    /// Get active session for a sensor (if any) on a connection the caller already holds
+    pub fn get_active_with(conn: &Connection, sensor_id: i64) -> Result<Option<LoggingSessionResponse>> {
         let session = conn.query_row(
             "SELECT * FROM logging_sessions 
              WHERE sensor_id = ? AND end_time IS NULL 
              LIMIT 1",
             params![sensor_id],
-            |row| Self::from_row(row),
+            Self::from_row,
         );
         
         match session {
@@ -163,23 +417,16 @@ impl LoggingSession {
     fn from_row(row: &Row) -> Result<LoggingSessionResponse, rusqlite::Error> {
         let session_id: i64 = row.get("session_id")?;
         let sensor_id: i64 = row.get("sensor_id")?;
-        let start_time: i64 = row.get("start_time")?;
-        let end_time: Option<i64> = row.get("end_time")?;
+        let start_time = get_datetime(row, "start_time")?;
+        let end_time = get_optional_datetime(row, "end_time")?;
         let sample_rate: Option<i64> = row.get("sample_rate")?;
         let notes: Option<String> = row.get("notes")?;
         
-        let start_time_dt = DateTime::from_timestamp(start_time, 0)
-            .expect("Invalid timestamp");
-        
-        let end_time_dt = end_time.map(|ts| {
-            DateTime::from_timestamp(ts, 0).expect("Invalid timestamp")
-        });
-        
         Ok(LoggingSessionResponse {
             session_id,
             sensor_id,
-            start_time: start_time_dt,
-            end_time: end_time_dt,
+            start_time,
+            end_time,
             sample_rate,
             notes,
             is_active: end_time.is_none(),
@@ -0,0 +1,92 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rand::{distributions::Alphanumeric, Rng};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+
+use crate::db::get_connection;
+use crate::models::Sensor;
+use crate::utils::current_timestamp;
+
+/// Length of generated tokens
+const TOKEN_LENGTH: usize = 40;
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use crate::{
+        models::{ApiToken, Sensor},
+        utils::test_utils::{setup_test_db, create_test_sensor},
+    };
+
+    #[test]
+    fn test_token_resolves_to_its_sensor() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        let sensor_id = create_test_sensor(&conn)?;
+        
+        let token = ApiToken::create(sensor_id)?;
+        assert_eq!(token.sensor_id, sensor_id);
+        assert_eq!(token.token.len(), 40);
+        assert_eq!(ApiToken::sensor_for(&token.token)?, Some(sensor_id));
+        assert_eq!(ApiToken::sensor_for("not-a-token")?, None);
+        
+        // Tokens can't be issued for unknown sensors, and go away with their sensor
+        assert!(ApiToken::create(9999).is_err());
+        Sensor::delete(sensor_id)?;
+        assert_eq!(ApiToken::sensor_for(&token.token)?, None);
+        
+        Ok(())
+    }
+}
+
+/// Token that may only write readings for a single sensor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub token: String,
+    pub sensor_id: i64,
+    pub created_at: DateTime<Utc>,
+}
+
+impl ApiToken {
+    /// Issue a new token bound to a sensor
+    pub fn create(sensor_id: i64) -> Result<ApiToken> {
+        let conn = get_connection()?;
+        // 404 for an unknown sensor rather than a foreign key failure
+        Sensor::get_by_id_with(&conn, sensor_id)?;
+        Sensor::ensure_writable(&conn, sensor_id)?;
+        
+        let token: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .map(char::from)
+            .collect();
+        let now = current_timestamp();
+        
+        conn.execute(
+            "INSERT INTO api_tokens (token, sensor_id, created_at) VALUES (?, ?, ?)",
+            params![token, sensor_id, now],
+        )?;
+        
+        Ok(ApiToken {
+            token,
+            sensor_id,
+            created_at: DateTime::from_timestamp(now, 0).unwrap_or_default(),
+        })
+    }
+    
+    /// The sensor a token is bound to, or None for an unknown token
+    pub fn sensor_for(token: &str) -> Result<Option<i64>> {
+        let conn = get_connection()?;
+        
+        let sensor_id = conn
+            .query_row(
+                "SELECT sensor_id FROM api_tokens WHERE token = ?",
+                params![token],
+                |row| row.get(0),
+            )
+            .optional()?;
+        
+        Ok(sensor_id)
+    }
+}
@@ -0,0 +1,183 @@
+use rand::Rng;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// Exponential backoff policy with jitter for retrying calls and reconnecting long-lived tasks
+#[derive(Debug, Clone)]
+pub struct Backoff {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound for any single delay
+    pub max_delay: Duration,
+    /// Growth factor applied per consecutive failure
+    pub multiplier: f64,
+    /// Fraction of the delay randomly added or removed (0.0 disables jitter)
+    pub jitter: f64,
+    /// Give up after this many consecutive failures (None retries forever)
+    pub max_retries: Option<u32>,
+}
+
+impl Default for Backoff {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_retries: None,
+        }
+    }
+}
+
+impl Backoff {
+    /// Delay before the given retry attempt (0-based), capped but without jitter
+    pub fn base_delay(&self, attempt: u32) -> Duration {
+        let factor = self.multiplier.powi(attempt.min(i32::MAX as u32) as i32);
+        let delay = self.initial_delay.as_secs_f64() * factor;
+
+        Duration::from_secs_f64(delay.min(self.max_delay.as_secs_f64()))
+    }
+
+    /// Delay before the given retry attempt with jitter applied
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let base = self.base_delay(attempt).as_secs_f64();
+
+        if self.jitter <= 0.0 {
+            return Duration::from_secs_f64(base);
+        }
+
+        let spread = base * self.jitter.min(1.0);
+        let jittered = base + rand::thread_rng().gen_range(-spread..=spread);
+
+        Duration::from_secs_f64(jittered.clamp(0.0, self.max_delay.as_secs_f64()))
+    }
+}
+
+/// Run a long-lived task, restarting it with backoff whenever it fails
+///
+/// `run` should connect and process until the connection drops, returning
+/// `Ok(())` only for a deliberate shutdown. A run that stays up longer than
+/// `max_delay` counts as a healthy connection and resets the backoff.
+pub async fn reconnect_loop<F, Fut, E>(name: &str, backoff: &Backoff, mut run: F) -> Result<(), E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<(), E>>,
+    E: Display,
+{
+    let mut attempt: u32 = 0;
+
+    loop {
+        let started = Instant::now();
+
+        let err = match run().await {
+            Ok(()) => {
+                tracing::info!("{} stopped", name);
+                return Ok(());
+            }
+            Err(err) => err,
+        };
+
+        if started.elapsed() > backoff.max_delay {
+            attempt = 0;
+        }
+
+        if backoff.max_retries.is_some_and(|max| attempt >= max) {
+            tracing::error!("{} failed after {} retries: {}", name, attempt, err);
+            return Err(err);
+        }
+
+        let delay = backoff.delay(attempt);
+        tracing::warn!("{} disconnected: {}; reconnecting in {:?}", name, err, delay);
+
+        tokio::time::sleep(delay).await;
+        attempt = attempt.saturating_add(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_delays_grow_and_cap() {
+        let backoff = Backoff {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_retries: None,
+        };
+
+        let delays: Vec<u64> = (0..6).map(|attempt| backoff.delay(attempt).as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 10, 10]);
+
+        // Jitter stays within the configured spread
+        let jittered = Backoff { jitter: 0.5, ..backoff };
+        for _ in 0..100 {
+            let delay = jittered.delay(2).as_secs_f64();
+            assert!((2.0..=6.0).contains(&delay), "delay {} out of range", delay);
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_loop_retries_with_increasing_delays() {
+        let backoff = Backoff {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.0,
+            max_retries: None,
+        };
+
+        let attempts = Arc::new(Mutex::new(Vec::new()));
+
+        // The connection drops three times, then shuts down cleanly
+        let result: Result<(), String> = reconnect_loop("test consumer", &backoff, || {
+            let attempts = attempts.clone();
+            async move {
+                let mut attempts = attempts.lock().unwrap();
+                attempts.push(Instant::now());
+                if attempts.len() < 4 {
+                    Err("connection dropped".to_string())
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .await;
+
+        assert!(result.is_ok());
+
+        let attempts = attempts.lock().unwrap();
+        assert_eq!(attempts.len(), 4);
+
+        let gaps: Vec<u128> = attempts
+            .windows(2)
+            .map(|pair| (pair[1] - pair[0]).as_millis())
+            .collect();
+        assert_eq!(gaps, vec![100, 200, 400]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_reconnect_loop_gives_up_after_max_retries() {
+        let backoff = Backoff {
+            initial_delay: Duration::from_millis(10),
+            jitter: 0.0,
+            max_retries: Some(2),
+            ..Backoff::default()
+        };
+
+        let mut calls = 0;
+        let result: Result<(), String> = reconnect_loop("flaky consumer", &backoff, || {
+            calls += 1;
+            async { Err("broker unavailable".to_string()) }
+        })
+        .await;
+
+        assert_eq!(result, Err("broker unavailable".to_string()));
+        assert_eq!(calls, 3);
+    }
+}
@@ -0,0 +1,184 @@
+use anyhow::Result;
+#[cfg(not(test))]
+use once_cell::sync::Lazy;
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Mutex,
+};
+use std::time::{Duration, Instant};
+
+use crate::config;
+
+#[cfg(not(test))]
+static CACHE: Lazy<QueryCache> = Lazy::new(QueryCache::default);
+
+#[cfg(test)]
+thread_local! {
+    /// Per-thread cache, so parallel tests with their own databases never share results
+    static TEST_CACHE: QueryCache = QueryCache::default();
+}
+
+/// Short-lived cache for aggregation results that dashboards request repeatedly
+#[derive(Default)]
+pub struct QueryCache {
+    entries: Mutex<HashMap<String, CacheEntry>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+struct CacheEntry {
+    /// Sensor the result depends on; None for results spanning all sensors
+    sensor_id: Option<i64>,
+    expires_at: Instant,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+impl QueryCache {
+    /// Return the cached result for `key`, or compute and cache it for `ttl`
+    ///
+    /// Once `max_entries` results are held, expired ones are dropped and then,
+    /// if that wasn't enough, the one closest to expiring.
+    pub fn get_or_compute<T, F>(
+        &self,
+        key: &str,
+        sensor_id: Option<i64>,
+        ttl: Duration,
+        max_entries: usize,
+        compute: F,
+    ) -> Result<Arc<T>>
+    where
+        T: Send + Sync + 'static,
+        F: FnOnce() -> Result<T>,
+    {
+        let now = Instant::now();
+
+        if let Some(entry) = self.entries.lock().unwrap().get(key) {
+            if entry.expires_at > now {
+                if let Ok(value) = entry.value.clone().downcast::<T>() {
+                    self.hits.fetch_add(1, Ordering::Relaxed);
+                    return Ok(value);
+                }
+            }
+        }
+
+        // Compute without holding the lock so other keys aren't blocked behind SQLite
+        self.misses.fetch_add(1, Ordering::Relaxed);
+        let value = Arc::new(compute()?);
+
+        let mut entries = self.entries.lock().unwrap();
+        if entries.len() >= max_entries && !entries.contains_key(key) {
+            entries.retain(|_, entry| entry.expires_at > now);
+            while entries.len() >= max_entries {
+                let Some(oldest) = entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.expires_at)
+                    .map(|(key, _)| key.clone())
+                else {
+                    break;
+                };
+                entries.remove(&oldest);
+            }
+        }
+        entries.insert(
+            key.to_string(),
+            CacheEntry {
+                sensor_id,
+                expires_at: now + ttl,
+                value: value.clone(),
+            },
+        );
+
+        Ok(value)
+    }
+
+    /// Drop results for a sensor, along with results spanning all sensors
+    pub fn invalidate_sensor(&self, sensor_id: i64) {
+        self.entries
+            .lock()
+            .unwrap()
+            .retain(|_, entry| entry.sensor_id.is_some_and(|id| id != sensor_id));
+    }
+
+    /// Drop every cached result
+    pub fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+
+    /// Number of (hits, misses) served so far
+    #[cfg(test)]
+    pub fn stats(&self) -> (u64, u64) {
+        (self.hits.load(Ordering::Relaxed), self.misses.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(not(test))]
+fn with_cache<R>(f: impl FnOnce(&QueryCache) -> R) -> R {
+    f(&CACHE)
+}
+
+#[cfg(test)]
+fn with_cache<R>(f: impl FnOnce(&QueryCache) -> R) -> R {
+    TEST_CACHE.with(f)
+}
+
+/// Serve a result from the cache within the configured TTL (`QUERY_CACHE_TTL_SECS`)
+///
+/// `key` must capture every parameter the result depends on. A TTL of zero
+/// disables caching.
+pub fn cached<T, F>(key: &str, sensor_id: Option<i64>, compute: F) -> Result<Arc<T>>
+where
+    T: Send + Sync + 'static,
+    F: FnOnce() -> Result<T>,
+{
+    let config = config::get();
+    if config.query_cache_ttl_secs == 0 {
+        return compute().map(Arc::new);
+    }
+
+    let ttl = Duration::from_secs(config.query_cache_ttl_secs);
+    with_cache(|cache| cache.get_or_compute(key, sensor_id, ttl, config.query_cache_max_entries, compute))
+}
+
+/// Drop cached results affected by new readings for a sensor
+pub fn invalidate_sensor(sensor_id: i64) {
+    with_cache(|cache| cache.invalidate_sensor(sensor_id));
+}
+
+/// Drop every cached result, for changes that can touch any sensor
+pub fn clear() {
+    with_cache(|cache| cache.clear());
+}
+
+/// Number of (hits, misses) served by the current test thread's cache
+#[cfg(test)]
+pub fn stats() -> (u64, u64) {
+    with_cache(|cache| cache.stats())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_evicts_oldest_when_full() -> Result<()> {
+        let cache = QueryCache::default();
+        let ttl = Duration::from_secs(60);
+
+        for key in ["a", "b", "c"] {
+            cache.get_or_compute(key, None, ttl, 2, || Ok(key.to_string()))?;
+        }
+        assert_eq!(cache.entries.lock().unwrap().len(), 2);
+
+        // "a" was evicted to make room for "c", so only it is computed again
+        cache.get_or_compute("b", None, ttl, 2, || Ok("b".to_string()))?;
+        cache.get_or_compute("c", None, ttl, 2, || Ok("c".to_string()))?;
+        assert_eq!(cache.stats(), (2, 3));
+        let value = cache.get_or_compute("a", None, ttl, 2, || Ok("recomputed".to_string()))?;
+        assert_eq!(*value, "recomputed");
+        assert_eq!(cache.stats(), (2, 4));
+
+        Ok(())
+    }
+}
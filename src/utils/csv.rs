@@ -1,8 +1,10 @@
+#![allow(dead_code)]
+
 use anyhow::Result;
-use chrono::{DateTime, NaiveDateTime, Utc};
 use std::io::{Read, Write};
 
 use crate::models::{Reading, Sensor};
+use crate::utils::normalize_timestamp;
 
 /// Format for timestamp representation in CSV
 const TIMESTAMP_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
@@ -19,7 +21,7 @@ pub fn export_readings_to_csv<W: Write>(
     
     // Write headers
     if include_headers {
-        wtr.write_record(&[
+        wtr.write_record([
             "reading_id",
             "timestamp",
             "formatted_time",
@@ -27,6 +29,7 @@ pub fn export_readings_to_csv<W: Write>(
             "value",
             "state",
             "change_type",
+            "annotation",
         ])?;
     }
     
@@ -40,9 +43,10 @@ pub fn export_readings_to_csv<W: Write>(
             timestamp.to_string(),
             formatted_time,
             reading.sensor_id.to_string(),
-            reading.value.map(|v| v.to_string()).unwrap_or_default(),
+            reading.value.map(|v| format!("{:?}", v)).unwrap_or_default(),
             reading.state.map(|s| s.to_string()).unwrap_or_default(),
             reading.change_type.clone().unwrap_or_default(),
+            reading.annotation.clone().unwrap_or_default(),
         ])?;
     }
     
@@ -62,7 +66,7 @@ pub fn export_sensors_to_csv<W: Write>(
     
     // Write headers
     if include_headers {
-        wtr.write_record(&[
+        wtr.write_record([
             "sensor_id",
             "sensor_name",
             "sensor_type",
@@ -125,6 +129,7 @@ pub fn import_readings_from_csv<R: Read>(reader: R) -> Result<Vec<Reading>> {
         let value_pos = headers.iter().position(|h| h.to_lowercase() == "value");
         let state_pos = headers.iter().position(|h| h.to_lowercase() == "state");
         let change_type_pos = headers.iter().position(|h| h.to_lowercase() == "change_type");
+        let annotation_pos = headers.iter().position(|h| h.to_lowercase() == "annotation");
         
         // Required field: sensor_id
         let sensor_id = if let Some(pos) = sensor_id_pos {
@@ -135,10 +140,11 @@ pub fn import_readings_from_csv<R: Read>(reader: R) -> Result<Vec<Reading>> {
             return Err(anyhow::anyhow!("Missing sensor_id column"));
         };
         
-        // Optional fields
+        // Optional fields; millisecond timestamps are converted to seconds
         let timestamp = timestamp_pos
             .and_then(|pos| record.get(pos))
-            .and_then(|s| s.parse::<i64>().ok());
+            .and_then(|s| s.parse::<i64>().ok())
+            .map(normalize_timestamp);
         
         let value = value_pos
             .and_then(|pos| record.get(pos))
@@ -153,6 +159,11 @@ pub fn import_readings_from_csv<R: Read>(reader: R) -> Result<Vec<Reading>> {
             .map(|s| s.to_string())
             .filter(|s| !s.is_empty());
         
+        let annotation = annotation_pos
+            .and_then(|pos| record.get(pos))
+            .map(|s| s.to_string())
+            .filter(|s| !s.is_empty());
+        
         // Require either value or state
         if value.is_none() && state.is_none() {
             return Err(anyhow::anyhow!("Reading must have either value or state"));
@@ -165,6 +176,7 @@ pub fn import_readings_from_csv<R: Read>(reader: R) -> Result<Vec<Reading>> {
             value,
             state,
             change_type,
+            annotation,
         };
         
         readings.push(reading);
@@ -251,6 +263,12 @@ pub fn import_sensors_from_csv<R: Read>(reader: R) -> Result<Vec<Sensor>> {
             threshold_max,
             calibration_date: None,
             notes,
+            display_color: None,
+            display_order: None,
+            deadband: None,
+            retention_days: None,
+            valid_min: None,
+            valid_max: None,
             created_at: None,
             updated_at: None,
         };
@@ -264,7 +282,7 @@ pub fn import_sensors_from_csv<R: Read>(reader: R) -> Result<Vec<Sensor>> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use chrono::TimeZone;
+    use chrono::{TimeZone, Utc};
     use std::io::Cursor;
     
     #[test]
@@ -278,6 +296,9 @@ mod tests {
                 value: Some(21.5),
                 state: None,
                 change_type: Some("periodic".to_string()),
+                annotation: None,
+                unit: None,
+                out_of_range: false,
             },
             crate::models::ReadingResponse {
                 reading_id: 2,
@@ -286,6 +307,9 @@ mod tests {
                 value: Some(22.0),
                 state: None,
                 change_type: Some("periodic".to_string()),
+                annotation: Some("door opened".to_string()),
+                unit: None,
+                out_of_range: false,
             },
         ];
         
@@ -303,6 +327,8 @@ mod tests {
         assert!(csv_output.contains("21.5"));
         assert!(csv_output.contains("22.0"));
         assert!(csv_output.contains("periodic"));
+        assert!(csv_output.contains(",change_type,annotation"));
+        assert!(csv_output.contains("periodic,door opened"));
         
         Ok(())
     }
@@ -310,10 +336,10 @@ mod tests {
     #[test]
     fn test_import_readings_from_csv() -> Result<()> {
         // Sample CSV data
-        let csv_data = r#"sensor_id,timestamp,value,change_type
-1,1712921800,21.5,periodic
-1,1712922100,22.0,periodic
-2,1712921800,15.2,periodic
+        let csv_data = r#"sensor_id,timestamp,value,change_type,annotation
+1,1712921800,21.5,periodic,
+1,1712922100,22.0,periodic,calibration run
+2,1712921800000,15.2,periodic,
 "#;
         
         // Import readings from CSV
@@ -324,7 +350,10 @@ mod tests {
         assert_eq!(readings[0].sensor_id, 1);
         assert_eq!(readings[0].value, Some(21.5));
         assert_eq!(readings[1].value, Some(22.0));
+        assert_eq!(readings[0].annotation, None);
+        assert_eq!(readings[1].annotation.as_deref(), Some("calibration run"));
         assert_eq!(readings[2].sensor_id, 2);
+        assert_eq!(readings[2].timestamp, readings[0].timestamp);
         
         Ok(())
     }
@@ -1,18 +1,22 @@
 use axum::{
-    http::StatusCode,
+    http::{header, HeaderValue, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use thiserror::Error;
 
+/// Seconds clients are told to wait before retrying when the database is busy
+const BUSY_RETRY_AFTER_SECS: u32 = 1;
+
 #[derive(Error, Debug)]
+#[allow(dead_code)]
 pub enum AppError {
     #[error("Database error: {0}")]
     Database(#[from] rusqlite::Error),
     
     #[error("Internal error: {0}")]
-    Internal(#[from] anyhow::Error),
+    Internal(anyhow::Error),
     
     #[error("Not found: {0}")]
     NotFound(String),
@@ -22,11 +26,36 @@ pub enum AppError {
     
     #[error("Conflict: {0}")]
     Conflict(String),
+    
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    
+    #[error("Payload too large: {0}")]
+    PayloadTooLarge(String),
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        let (status, message) = match self {
+impl From<anyhow::Error> for AppError {
+    fn from(err: anyhow::Error) -> Self {
+        // Model code returns anyhow errors; recover the typed error underneath if there is one
+        match err.downcast::<AppError>() {
+            Ok(app_err) => app_err,
+            Err(err) => match err.downcast::<rusqlite::Error>() {
+                Ok(db_err) => AppError::Database(db_err),
+                Err(err) => AppError::Internal(err),
+            },
+        }
+    }
+}
+
+impl AppError {
+    /// The status and client-facing message this error is answered with
+    ///
+    /// Server-side failures are logged here and described only generically.
+    pub fn status_and_message(self) -> (StatusCode, String) {
+        match self {
             AppError::Database(err) => {
                 if err.to_string().contains("UNIQUE constraint failed") {
                     (StatusCode::CONFLICT, format!("Resource already exists: {}", err))
@@ -34,6 +63,22 @@ impl IntoResponse for AppError {
                     (StatusCode::BAD_REQUEST, format!("Referenced resource does not exist: {}", err))
                 } else if err == rusqlite::Error::QueryReturnedNoRows {
                     (StatusCode::NOT_FOUND, "Resource not found".to_string())
+                } else if matches!(
+                    err.sqlite_error_code(),
+                    Some(rusqlite::ErrorCode::DatabaseBusy | rusqlite::ErrorCode::DatabaseLocked)
+                ) {
+                    // Still locked after the busy timeout; worth retrying shortly
+                    tracing::warn!("Database busy: {:?}", err);
+                    (
+                        StatusCode::SERVICE_UNAVAILABLE,
+                        "Database is busy; retry shortly".to_string(),
+                    )
+                } else if err.sqlite_error_code() == Some(rusqlite::ErrorCode::DiskFull) {
+                    tracing::error!("Database disk is full: {:?}", err);
+                    (
+                        StatusCode::INSUFFICIENT_STORAGE,
+                        "Insufficient storage: the database disk is full".to_string(),
+                    )
                 } else {
                     tracing::error!("Database error: {:?}", err);
                     (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
@@ -46,12 +91,56 @@ impl IntoResponse for AppError {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg),
             AppError::Conflict(msg) => (StatusCode::CONFLICT, msg),
-        };
+            AppError::Unauthorized(msg) => (StatusCode::UNAUTHORIZED, msg),
+            AppError::Forbidden(msg) => (StatusCode::FORBIDDEN, msg),
+            AppError::PayloadTooLarge(msg) => (StatusCode::PAYLOAD_TOO_LARGE, msg),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        let (status, message) = self.status_and_message();
         
         let body = Json(json!({
             "error": message
         }));
         
-        (status, body).into_response()
+        let mut response = (status, body).into_response();
+        if status == StatusCode::SERVICE_UNAVAILABLE {
+            response
+                .headers_mut()
+                .insert(header::RETRY_AFTER, HeaderValue::from(BUSY_RETRY_AFTER_SECS));
+        }
+        response
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::ffi;
+
+    #[test]
+    fn test_disk_full_maps_to_insufficient_storage() {
+        let disk_full = rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_FULL), None);
+
+        // Model code hands it over wrapped in anyhow
+        let err = AppError::from(anyhow::Error::from(disk_full));
+        assert!(matches!(err, AppError::Database(_)));
+        assert_eq!(err.into_response().status(), StatusCode::INSUFFICIENT_STORAGE);
+
+        // Other SQLite failures stay generic server errors
+        let corrupt = rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_CORRUPT), None);
+        assert_eq!(AppError::from(corrupt).into_response().status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_busy_database_maps_to_retryable_unavailable() {
+        let busy = rusqlite::Error::SqliteFailure(ffi::Error::new(ffi::SQLITE_BUSY), None);
+        let response = AppError::from(anyhow::Error::from(busy)).into_response();
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "1");
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,116 @@
+#[cfg(not(test))]
+use once_cell::sync::Lazy;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+use crate::utils::current_timestamp;
+
+/// Seconds of history the ingest rate is averaged over
+pub const RATE_WINDOW_SECS: i64 = 60;
+
+#[cfg(not(test))]
+static COUNTER: Lazy<IngestCounter> = Lazy::new(IngestCounter::default);
+
+#[cfg(test)]
+thread_local! {
+    /// Per-thread counter, so parallel tests only see their own inserts
+    static TEST_COUNTER: IngestCounter = IngestCounter::default();
+}
+
+/// Readings written by this process, counted when they are inserted
+///
+/// Unlike rates derived from reading timestamps, this measures actual
+/// throughput: a backfill of last year's data counts as it arrives.
+#[derive(Default)]
+pub struct IngestCounter {
+    counts: Mutex<Counts>,
+}
+
+#[derive(Default)]
+struct Counts {
+    total: u64,
+    /// (second, readings inserted in it), oldest first
+    per_second: VecDeque<(i64, u64)>,
+}
+
+impl Counts {
+    /// Forget seconds that have left the rate window
+    fn prune(&mut self, now: i64) {
+        while self.per_second.front().is_some_and(|&(second, _)| second <= now - RATE_WINDOW_SECS) {
+            self.per_second.pop_front();
+        }
+    }
+}
+
+impl IngestCounter {
+    /// Count `readings` inserted at `now`
+    pub fn record_at(&self, now: i64, readings: u64) {
+        let mut counts = self.counts.lock().unwrap();
+        counts.total += readings;
+
+        // A clock stepping backwards adds to the latest second rather than reordering
+        match counts.per_second.back_mut() {
+            Some((second, count)) if *second >= now => *count += readings,
+            _ => counts.per_second.push_back((now, readings)),
+        }
+        counts.prune(now);
+    }
+
+    /// Readings per second inserted over the window ending at `now`
+    pub fn rate_at(&self, now: i64) -> f64 {
+        let mut counts = self.counts.lock().unwrap();
+        counts.prune(now);
+
+        let inserted: u64 = counts.per_second.iter().map(|&(_, count)| count).sum();
+        inserted as f64 / RATE_WINDOW_SECS as f64
+    }
+
+    /// Readings inserted since the counter was created
+    pub fn total(&self) -> u64 {
+        self.counts.lock().unwrap().total
+    }
+}
+
+#[cfg(not(test))]
+fn with_counter<R>(f: impl FnOnce(&IngestCounter) -> R) -> R {
+    f(&COUNTER)
+}
+
+#[cfg(test)]
+fn with_counter<R>(f: impl FnOnce(&IngestCounter) -> R) -> R {
+    TEST_COUNTER.with(f)
+}
+
+/// Count readings that were just inserted
+pub fn record(readings: usize) {
+    with_counter(|counter| counter.record_at(current_timestamp(), readings as u64));
+}
+
+/// Readings per second inserted over the last `RATE_WINDOW_SECS`
+pub fn rate() -> f64 {
+    with_counter(|counter| counter.rate_at(current_timestamp()))
+}
+
+/// Readings inserted since the server started
+pub fn total() -> u64 {
+    with_counter(|counter| counter.total())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rate_covers_only_the_window() {
+        let counter = IngestCounter::default();
+        counter.record_at(1_000, 30);
+        counter.record_at(1_030, 10);
+        counter.record_at(1_030, 20);
+
+        assert_eq!(counter.rate_at(1_030), 1.0);
+        // The first batch ages out after a minute
+        assert_eq!(counter.rate_at(1_060), 0.5);
+        assert_eq!(counter.rate_at(1_090), 0.0);
+        assert_eq!(counter.total(), 60);
+    }
+}
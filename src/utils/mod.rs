@@ -1,14 +1,37 @@
 pub mod error;
 pub mod csv;
+pub mod backoff;
+pub mod cache;
+pub mod ingest;
+pub mod stats;
+pub mod units;
+pub mod webhook;
+pub mod xlsx;
 #[cfg(test)]
 pub mod test_utils;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
+/// Largest timestamp taken as seconds; 10^11 seconds is in the year 5138
+const MAX_SECONDS_TIMESTAMP: i64 = 100_000_000_000;
+
 /// Get current Unix timestamp
 pub fn current_timestamp() -> i64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .expect("Time went backwards")
         .as_secs() as i64
+}
+
+/// Convert a timestamp that was sent in milliseconds to seconds
+///
+/// Anything beyond `MAX_SECONDS_TIMESTAMP` is too far in the future to be
+/// seconds, while every millisecond timestamp after March 1973 exceeds it,
+/// so those are divided by 1000 (dropping the fraction of a second).
+pub fn normalize_timestamp(timestamp: i64) -> i64 {
+    if timestamp.abs() > MAX_SECONDS_TIMESTAMP {
+        timestamp / 1000
+    } else {
+        timestamp
+    }
 }
\ No newline at end of file
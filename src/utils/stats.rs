@@ -0,0 +1,153 @@
+/// Percentile of an ascending slice, interpolating linearly between ranks
+///
+/// `p` is in percent (0-100). Returns None for an empty slice.
+pub fn percentile(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = (p.clamp(0.0, 100.0) / 100.0) * (sorted.len() - 1) as f64;
+    let lower = rank.floor() as usize;
+    let upper = rank.ceil() as usize;
+    let fraction = rank - lower as f64;
+
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * fraction)
+}
+
+/// Percentile of an ascending slice by the nearest-rank method
+///
+/// Always returns one of the values, so results are exact and reproducible.
+/// `p` is in percent (0-100). Returns None for an empty slice.
+pub fn nearest_rank(sorted: &[f64], p: f64) -> Option<f64> {
+    if sorted.is_empty() {
+        return None;
+    }
+
+    let rank = ((p.clamp(0.0, 100.0) / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.max(1) - 1])
+}
+
+/// Parse a clip range like `p1,p99` into (low, high) percentiles
+pub fn parse_clip(raw: &str) -> Option<(f64, f64)> {
+    let (low, high) = raw.split_once(',')?;
+    let parse = |bound: &str| -> Option<f64> {
+        let bound = bound.trim();
+        bound.strip_prefix('p').unwrap_or(bound).parse().ok()
+    };
+
+    let (low, high) = (parse(low)?, parse(high)?);
+    if (0.0..=100.0).contains(&low) && (0.0..=100.0).contains(&high) && low < high {
+        Some((low, high))
+    } else {
+        None
+    }
+}
+
+/// Width in seconds of a named bucket interval (`minute`, `hour` or `day`)
+pub fn parse_interval(raw: &str) -> Option<i64> {
+    match raw {
+        "minute" => Some(60),
+        "hour" => Some(3600),
+        "day" => Some(86400),
+        _ => None,
+    }
+}
+
+/// Trailing simple moving average over `window` buckets
+///
+/// Each point averages the values present in its bucket and the `window - 1`
+/// before it, so early buckets and gaps use whatever points are available.
+/// A point is None only when its whole window is empty.
+pub fn moving_average(values: &[Option<f64>], window: usize) -> Vec<Option<f64>> {
+    (0..values.len())
+        .map(|i| {
+            let present: Vec<f64> = values[(i + 1).saturating_sub(window)..=i].iter().flatten().copied().collect();
+            if present.is_empty() {
+                None
+            } else {
+                Some(present.iter().sum::<f64>() / present.len() as f64)
+            }
+        })
+        .collect()
+}
+
+/// Share of the threshold band treated as close enough to warn about
+const WARNING_MARGIN: f64 = 0.1;
+
+/// Classify a value as 'critical' outside its thresholds, 'warning' within
+/// 10% of one, or 'healthy'
+///
+/// The margin is 10% of the band between the thresholds, or of the threshold
+/// itself when only one is set.
+pub fn classify_value(value: f64, threshold_min: Option<f64>, threshold_max: Option<f64>) -> &'static str {
+    if threshold_min.is_some_and(|min| value < min) || threshold_max.is_some_and(|max| value > max) {
+        return "critical";
+    }
+
+    let margin = |threshold: f64| match (threshold_min, threshold_max) {
+        (Some(min), Some(max)) => (max - min) * WARNING_MARGIN,
+        _ => threshold.abs() * WARNING_MARGIN,
+    };
+
+    let near_min = threshold_min.is_some_and(|min| value <= min + margin(min));
+    let near_max = threshold_max.is_some_and(|max| value >= max - margin(max));
+    if near_min || near_max {
+        "warning"
+    } else {
+        "healthy"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_and_clip_parsing() {
+        let values = [1.0, 2.0, 3.0, 4.0, 5.0];
+        assert_eq!(percentile(&values, 0.0), Some(1.0));
+        assert_eq!(percentile(&values, 50.0), Some(3.0));
+        assert_eq!(percentile(&values, 100.0), Some(5.0));
+        assert_eq!(percentile(&values, 12.5), Some(1.5));
+        assert_eq!(percentile(&[], 50.0), None);
+
+        assert_eq!(nearest_rank(&[15.0, 20.0, 35.0, 40.0, 50.0], 30.0), Some(20.0));
+        assert_eq!(nearest_rank(&[15.0, 20.0, 35.0, 40.0, 50.0], 0.0), Some(15.0));
+        assert_eq!(nearest_rank(&[], 50.0), None);
+
+        assert_eq!(parse_clip("p1,p99"), Some((1.0, 99.0)));
+        assert_eq!(parse_clip("5, 95"), Some((5.0, 95.0)));
+        assert_eq!(parse_clip("p99,p1"), None);
+        assert_eq!(parse_clip("p1"), None);
+        assert_eq!(parse_clip("p-1,p101"), None);
+
+        assert_eq!(parse_interval("hour"), Some(3600));
+        assert_eq!(parse_interval("fortnight"), None);
+    }
+
+    #[test]
+    fn test_moving_average_uses_available_points() {
+        let values = [Some(2.0), Some(4.0), Some(6.0), None, Some(12.0), None, None, None];
+        assert_eq!(
+            moving_average(&values, 3),
+            vec![Some(2.0), Some(3.0), Some(4.0), Some(5.0), Some(9.0), Some(12.0), Some(12.0), None]
+        );
+        assert_eq!(moving_average(&values, 1), values.to_vec());
+        assert!(moving_average(&[], 3).is_empty());
+    }
+
+    #[test]
+    fn test_classify_value_margins() {
+        // Band of 18..25, so the warning margin is 0.7 on either side
+        assert_eq!(classify_value(21.5, Some(18.0), Some(25.0)), "healthy");
+        assert_eq!(classify_value(18.5, Some(18.0), Some(25.0)), "warning");
+        assert_eq!(classify_value(24.5, Some(18.0), Some(25.0)), "warning");
+        assert_eq!(classify_value(17.9, Some(18.0), Some(25.0)), "critical");
+        assert_eq!(classify_value(25.1, Some(18.0), Some(25.0)), "critical");
+
+        // A single threshold uses 10% of itself; none means always healthy
+        assert_eq!(classify_value(95.0, None, Some(100.0)), "warning");
+        assert_eq!(classify_value(85.0, None, Some(100.0)), "healthy");
+        assert_eq!(classify_value(1e9, None, None), "healthy");
+    }
+}
@@ -1,22 +1,31 @@
 #![cfg(test)]
+#![allow(dead_code)]
 
 use anyhow::Result;
+use axum::{
+    body::{Body, Bytes},
+    http::{HeaderMap, Request, StatusCode},
+};
 use rusqlite::Connection;
 use std::sync::Once;
 use tempfile::TempDir;
+use tower::ServiceExt;
 
-use crate::db::{init_pool, migrations};
+use crate::db::migrations;
 
 static INIT: Once = Once::new();
 
-/// Initialize the in-memory test database
-pub fn setup_test_db() -> Result<&'static r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>> {
+/// Initialize an isolated test database for the current test
+pub fn setup_test_db() -> Result<r2d2::Pool<r2d2_sqlite::SqliteConnectionManager>> {
     INIT.call_once(|| {
         let _ = tracing_subscriber::fmt()
             .with_env_filter("sensor_monitoring_api=debug")
             .try_init();
     });
     
+    // Results cached against a previous test's database would be stale
+    crate::utils::cache::clear();
+    
     crate::db::init_test_pool()
 }
 
@@ -25,8 +34,8 @@ pub fn setup_temp_db_file() -> Result<(TempDir, Connection)> {
     let temp_dir = TempDir::new()?;
     let db_path = temp_dir.path().join("test.db");
     
-    let conn = Connection::open(&db_path)?;
-    migrations::run_migrations(&conn)?;
+    let mut conn = Connection::open(&db_path)?;
+    migrations::run_migrations(&mut conn)?;
     
     Ok((temp_dir, conn))
 }
@@ -60,6 +69,9 @@ pub fn create_test_sensor(conn: &Connection) -> Result<i64> {
 }
 
 /// Create a test reading in the database
+///
+/// A sensor has at most one reading per second, so a reading created in
+/// the same second as the sensor's latest goes one second after it.
 pub fn create_test_reading(conn: &Connection, sensor_id: i64) -> Result<i64> {
     let now = std::time::SystemTime::now()
         .duration_since(std::time::UNIX_EPOCH)?
@@ -68,7 +80,10 @@ pub fn create_test_reading(conn: &Connection, sensor_id: i64) -> Result<i64> {
     conn.execute(
         "INSERT INTO readings (
             timestamp, sensor_id, value, change_type
-        ) VALUES (?, ?, ?, ?)",
+        ) VALUES (
+            MAX(?1, COALESCE((SELECT MAX(timestamp) + 1 FROM readings WHERE sensor_id = ?2), ?1)),
+            ?2, ?3, ?4
+        )",
         rusqlite::params![
             now,
             sensor_id,
@@ -106,4 +121,20 @@ pub fn create_test_session(conn: &Connection, sensor_id: i64, active: bool) -> R
     )?;
     
     Ok(conn.last_insert_rowid())
-}
\ No newline at end of file
+}
+
+/// Send a request through the API router and collect the response
+pub async fn send_request(request: Request<Body>) -> Result<(StatusCode, HeaderMap, Bytes)> {
+    send_request_with(crate::api::AppState::default(), request).await
+}
+
+/// Send a request through an API router sharing the given state
+pub async fn send_request_with(state: crate::api::AppState, request: Request<Body>) -> Result<(StatusCode, HeaderMap, Bytes)> {
+    let response = crate::api::create_router(state).oneshot(request).await?;
+    
+    let status = response.status();
+    let headers = response.headers().clone();
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await?;
+    
+    Ok((status, headers, body))
+}
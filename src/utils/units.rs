@@ -0,0 +1,108 @@
+use crate::utils::error::AppError;
+
+/// A unit symbol, its dimension, and how it relates to the dimension's base unit
+///
+/// A value `v` in this unit is `(v + offset) * scale` in the base unit.
+struct UnitDef {
+    symbol: &'static str,
+    dimension: &'static str,
+    scale: f64,
+    offset: f64,
+}
+
+const fn unit(symbol: &'static str, dimension: &'static str, scale: f64, offset: f64) -> UnitDef {
+    UnitDef { symbol, dimension, scale, offset }
+}
+
+/// Units that readings can be converted between, grouped by dimension
+const UNITS: &[UnitDef] = &[
+    // Temperature, based on Celsius
+    unit("C", "temperature", 1.0, 0.0),
+    unit("°C", "temperature", 1.0, 0.0),
+    unit("F", "temperature", 5.0 / 9.0, -32.0),
+    unit("°F", "temperature", 5.0 / 9.0, -32.0),
+    unit("K", "temperature", 1.0, -273.15),
+    // Power, based on watts
+    unit("W", "power", 1.0, 0.0),
+    unit("kW", "power", 1_000.0, 0.0),
+    unit("MW", "power", 1_000_000.0, 0.0),
+    // Energy, based on watt-hours
+    unit("Wh", "energy", 1.0, 0.0),
+    unit("kWh", "energy", 1_000.0, 0.0),
+    unit("MWh", "energy", 1_000_000.0, 0.0),
+    // Flow, based on litres per minute
+    unit("L/min", "flow", 1.0, 0.0),
+    unit("L/s", "flow", 60.0, 0.0),
+    unit("L/h", "flow", 1.0 / 60.0, 0.0),
+    unit("m3/h", "flow", 1_000.0 / 60.0, 0.0),
+    unit("gal/min", "flow", 3.785_411_784, 0.0),
+    // Illuminance, based on lux
+    unit("lux", "light", 1.0, 0.0),
+    unit("lx", "light", 1.0, 0.0),
+    unit("fc", "light", 10.763_910_416_709_722, 0.0),
+];
+
+/// Mapping of values from one unit to another of the same dimension
+#[derive(Clone, Copy)]
+pub struct Conversion {
+    from: &'static UnitDef,
+    to: &'static UnitDef,
+}
+
+impl Conversion {
+    /// Convert a value expressed in the source unit
+    ///
+    /// Going through the base unit in two steps keeps round values exact,
+    /// so 100 C comes out as 212 F rather than 211.99999999999997.
+    pub fn apply(&self, value: f64) -> f64 {
+        let base = (value + self.from.offset) * self.from.scale;
+        base / self.to.scale - self.to.offset
+    }
+}
+
+fn lookup(symbol: &str) -> Result<&'static UnitDef, AppError> {
+    UNITS
+        .iter()
+        .find(|unit| unit.symbol == symbol.trim())
+        .ok_or_else(|| AppError::BadRequest(format!("Unknown unit '{}'", symbol)))
+}
+
+/// Build the conversion from `from` to `to`
+///
+/// Fails for units missing from the table and for units measuring
+/// different things, such as Celsius and kilowatts.
+pub fn conversion(from: &str, to: &str) -> Result<Conversion, AppError> {
+    let (from_unit, to_unit) = (lookup(from)?, lookup(to)?);
+    if from_unit.dimension != to_unit.dimension {
+        return Err(AppError::BadRequest(format!(
+            "Can't convert {} ({}) to {} ({})",
+            from, from_unit.dimension, to, to_unit.dimension
+        )));
+    }
+
+    Ok(Conversion { from: from_unit, to: to_unit })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn convert(value: f64, from: &str, to: &str) -> f64 {
+        conversion(from, to).unwrap().apply(value)
+    }
+
+    #[test]
+    fn test_conversions_and_rejections() {
+        assert_eq!(convert(100.0, "C", "F"), 212.0);
+        assert_eq!(convert(212.0, "F", "C"), 100.0);
+        assert!((convert(-40.0, "°F", "C") - -40.0).abs() < 1e-9);
+        assert!((convert(0.0, "C", "K") - 273.15).abs() < 1e-9);
+        assert!((convert(2.5, "kW", "W") - 2_500.0).abs() < 1e-9);
+        assert!((convert(750.0, "W", "kW") - 0.75).abs() < 1e-9);
+        assert!((convert(1.0, "L/s", "L/min") - 60.0).abs() < 1e-9);
+        assert_eq!(convert(21.5, "C", "C"), 21.5);
+
+        assert!(matches!(conversion("C", "kW"), Err(AppError::BadRequest(_))));
+        assert!(matches!(conversion("C", "furlongs"), Err(AppError::BadRequest(_))));
+    }
+}
@@ -0,0 +1,305 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+use std::time::Duration;
+use tokio::sync::mpsc::{self, error::TrySendError};
+
+use crate::utils::backoff::{reconnect_loop, Backoff};
+
+/// How long to keep collecting breaches before sending them as one call
+const BATCH_WINDOW: Duration = Duration::from_millis(500);
+
+/// Timeout for a single webhook request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Breach events held while the webhook is slow or down; later ones are dropped
+const QUEUE_CAPACITY: usize = 1024;
+
+static NOTIFIER: OnceCell<WebhookNotifier> = OnceCell::new();
+
+#[cfg(test)]
+thread_local! {
+    /// Per-thread notifier so tests can point the webhook at their own mock server
+    static TEST_NOTIFIER: std::cell::RefCell<Option<WebhookNotifier>> = const { std::cell::RefCell::new(None) };
+}
+
+/// A reading whose value fell outside its sensor's thresholds
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreachEvent {
+    pub sensor_id: i64,
+    pub reading_id: i64,
+    pub timestamp: i64,
+    pub value: f64,
+    pub threshold_min: Option<f64>,
+    pub threshold_max: Option<f64>,
+    pub breach: String,  // 'below_min' or 'above_max'
+}
+
+impl BreachEvent {
+    /// Build an event if `value` breaches the given thresholds
+    pub fn check(
+        sensor_id: i64,
+        reading_id: i64,
+        timestamp: i64,
+        value: f64,
+        threshold_min: Option<f64>,
+        threshold_max: Option<f64>,
+    ) -> Option<Self> {
+        let breach = if threshold_min.is_some_and(|min| value < min) {
+            "below_min"
+        } else if threshold_max.is_some_and(|max| value > max) {
+            "above_max"
+        } else {
+            return None;
+        };
+
+        Some(Self {
+            sensor_id,
+            reading_id,
+            timestamp,
+            value,
+            threshold_min,
+            threshold_max,
+            breach: breach.to_string(),
+        })
+    }
+}
+
+/// Handle to the background task that delivers breach events
+#[derive(Debug, Clone)]
+pub struct WebhookNotifier {
+    sender: mpsc::Sender<BreachEvent>,
+    dropped: Arc<AtomicU64>,  // Events turned away because the queue was full
+}
+
+impl WebhookNotifier {
+    /// Spawn the delivery task for `url` on the current Tokio runtime
+    ///
+    /// At most `capacity` events wait for delivery, so a webhook that is down
+    /// costs bounded memory; events beyond that are dropped and logged.
+    pub fn spawn(url: String, batch_window: Duration, backoff: Backoff, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::channel(capacity);
+        tokio::spawn(supervise(url, receiver, batch_window, backoff));
+
+        Self { sender, dropped: Arc::default() }
+    }
+
+    /// Queue an event for delivery without waiting for room in the queue
+    pub fn notify(&self, event: BreachEvent) {
+        match self.sender.try_send(event) {
+            Ok(()) => {}
+            Err(TrySendError::Full(event)) => {
+                let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+                tracing::warn!(
+                    "Webhook queue is full; dropping breach event for reading {} ({} dropped so far)",
+                    event.reading_id,
+                    dropped
+                );
+            }
+            Err(TrySendError::Closed(_)) => {
+                tracing::warn!("Webhook delivery task has stopped; dropping breach event");
+            }
+        }
+    }
+}
+
+/// Start delivering breach events to the configured URL, if any
+pub fn init(url: Option<&str>) {
+    if let Some(url) = url {
+        let backoff = Backoff {
+            max_retries: Some(3),
+            max_delay: Duration::from_secs(10),
+            ..Backoff::default()
+        };
+        NOTIFIER.get_or_init(|| WebhookNotifier::spawn(url.to_string(), BATCH_WINDOW, backoff, QUEUE_CAPACITY));
+    }
+}
+
+/// Whether breach events are delivered anywhere
+pub fn is_enabled() -> bool {
+    #[cfg(test)]
+    if TEST_NOTIFIER.with(|cell| cell.borrow().is_some()) {
+        return true;
+    }
+
+    NOTIFIER.get().is_some()
+}
+
+/// Queue a breach event; a no-op when no webhook is configured
+pub fn notify(event: BreachEvent) {
+    #[cfg(test)]
+    if let Some(notifier) = TEST_NOTIFIER.with(|cell| cell.borrow().clone()) {
+        notifier.notify(event);
+        return;
+    }
+
+    if let Some(notifier) = NOTIFIER.get() {
+        notifier.notify(event);
+    }
+}
+
+/// Use a notifier for the current test thread
+#[cfg(test)]
+pub fn set_test_notifier(notifier: WebhookNotifier) {
+    TEST_NOTIFIER.with(|cell| *cell.borrow_mut() = Some(notifier));
+}
+
+/// Keep the delivery task running for as long as events can arrive
+///
+/// Should delivery fail outright, e.g. because the HTTP client can't be
+/// built, it is restarted with backoff on the same queue rather than
+/// leaving breaches to pile up unsent.
+async fn supervise(url: String, receiver: mpsc::Receiver<BreachEvent>, batch_window: Duration, backoff: Backoff) {
+    let receiver = tokio::sync::Mutex::new(receiver);
+    let restart = Backoff::default();
+
+    // Retries forever, so it only returns once the queue has closed
+    let _ = reconnect_loop("Webhook delivery", &restart, || deliver(&url, &receiver, batch_window, &backoff)).await;
+}
+
+/// Collect events into batches and POST each batch, retrying failed calls
+///
+/// Returns once the queue closes, or with an error if the client can't be built.
+async fn deliver(
+    url: &str,
+    receiver: &tokio::sync::Mutex<mpsc::Receiver<BreachEvent>>,
+    batch_window: Duration,
+    backoff: &Backoff,
+) -> Result<(), reqwest::Error> {
+    let client = reqwest::Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+    let mut receiver = receiver.lock().await;
+
+    while let Some(first) = receiver.recv().await {
+        let mut events = vec![first];
+
+        // Anything that arrives within the window rides along in the same call
+        let deadline = tokio::time::Instant::now() + batch_window;
+        while let Ok(Some(event)) = tokio::time::timeout_at(deadline, receiver.recv()).await {
+            events.push(event);
+        }
+
+        let body = json!({ "events": events });
+        let mut attempt: u32 = 0;
+
+        loop {
+            let result = client
+                .post(url)
+                .json(&body)
+                .send()
+                .await
+                .and_then(|response| response.error_for_status());
+
+            match result {
+                Ok(_) => break,
+                Err(err) if backoff.max_retries.is_some_and(|max| attempt >= max) => {
+                    tracing::error!("Dropping {} breach events after {} retries: {}", events.len(), attempt, err);
+                    break;
+                }
+                Err(err) => {
+                    let delay = backoff.delay(attempt);
+                    tracing::warn!("Webhook call failed: {}; retrying in {:?}", err, delay);
+                    tokio::time::sleep(delay).await;
+                    attempt = attempt.saturating_add(1);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use axum::{http::StatusCode, routing::post, Json, Router};
+    use std::sync::{atomic::AtomicUsize, Mutex};
+    use crate::{
+        models::Reading,
+        utils::test_utils::{setup_test_db, create_test_sensor},
+    };
+
+    #[tokio::test]
+    async fn test_breaches_are_delivered_in_one_batch() -> Result<()> {
+        let pool = setup_test_db()?;
+        let conn = pool.get()?;
+        // The test sensor accepts values between 18 and 25
+        let sensor_id = create_test_sensor(&conn)?;
+
+        // Mock receiver that fails the first call to exercise the retry
+        let attempts = Arc::new(AtomicUsize::new(0));
+        let delivered: Arc<Mutex<Vec<serde_json::Value>>> = Arc::default();
+        let app = Router::new().route("/hook", post({
+            let attempts = attempts.clone();
+            let delivered = delivered.clone();
+            move |Json(body): Json<serde_json::Value>| async move {
+                if attempts.fetch_add(1, Ordering::SeqCst) == 0 {
+                    return StatusCode::SERVICE_UNAVAILABLE;
+                }
+                delivered.lock().unwrap().push(body);
+                StatusCode::OK
+            }
+        }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("http://{}/hook", listener.local_addr()?);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+
+        let backoff = Backoff {
+            initial_delay: Duration::from_millis(10),
+            jitter: 0.0,
+            max_retries: Some(3),
+            ..Backoff::default()
+        };
+        set_test_notifier(WebhookNotifier::spawn(url, Duration::from_millis(200), backoff, QUEUE_CAPACITY));
+
+        let reading = |timestamp, value| Reading {
+            timestamp: Some(timestamp),
+            sensor_id,
+            value: Some(value),
+            ..Default::default()
+        };
+        reading(100, 30.0).create()?;
+        reading(200, 21.0).create()?;
+        Reading::bulk_insert(&[reading(300, 10.0)])?;
+
+        for _ in 0..100 {
+            if !delivered.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let delivered = delivered.lock().unwrap();
+        assert_eq!(delivered.len(), 1, "breaches should be batched into one call");
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+
+        let events: Vec<BreachEvent> = serde_json::from_value(delivered[0]["events"].clone())?;
+        let breaches: Vec<(f64, &str)> = events.iter().map(|e| (e.value, e.breach.as_str())).collect();
+        assert_eq!(breaches, vec![(30.0, "above_max"), (10.0, "below_min")]);
+        assert!(events.iter().all(|e| e.sensor_id == sensor_id));
+
+        Ok(())
+    }
+    #[tokio::test]
+    async fn test_full_queue_drops_events() {
+        // Nothing is delivered before this test yields, so the queue only fills
+        let notifier = WebhookNotifier::spawn("http://127.0.0.1:9/hook".to_string(), BATCH_WINDOW, Backoff::default(), 2);
+        for reading_id in 1..=5 {
+            notifier.notify(BreachEvent {
+                sensor_id: 1,
+                reading_id,
+                timestamp: 100,
+                value: 30.0,
+                threshold_min: None,
+                threshold_max: Some(25.0),
+                breach: "above_max".to_string(),
+            });
+        }
+
+        assert_eq!(notifier.dropped.load(Ordering::Relaxed), 3);
+    }
+}
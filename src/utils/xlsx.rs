@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use rust_xlsxwriter::{Format, Workbook};
+
+/// Rows a worksheet can hold, including its header row
+pub const MAX_ROWS: usize = 1_048_576;
+
+/// Number format applied to date-time cells
+const DATE_TIME_FORMAT: &str = "yyyy-mm-dd hh:mm:ss";
+
+/// A single worksheet cell
+pub enum Cell {
+    Empty,
+    Number(f64),
+    Text(String),
+    DateTime(DateTime<Utc>),
+}
+
+impl From<Option<f64>> for Cell {
+    fn from(value: Option<f64>) -> Self {
+        value.map_or(Cell::Empty, Cell::Number)
+    }
+}
+
+impl From<Option<i64>> for Cell {
+    fn from(value: Option<i64>) -> Self {
+        value.map_or(Cell::Empty, |value| Cell::Number(value as f64))
+    }
+}
+
+impl From<Option<String>> for Cell {
+    fn from(value: Option<String>) -> Self {
+        value.map_or(Cell::Empty, Cell::Text)
+    }
+}
+
+/// A named worksheet whose first row is the column headers
+pub struct Sheet {
+    pub name: String,
+    pub rows: Vec<Vec<Cell>>,
+}
+
+impl Sheet {
+    /// Start a sheet with its header row
+    pub fn new(name: &str, headers: &[&str]) -> Self {
+        let header = headers.iter().map(|header| Cell::Text(header.to_string())).collect();
+        Self { name: name.to_string(), rows: vec![header] }
+    }
+
+    /// Append a row, failing once the sheet is full
+    pub fn push(&mut self, row: Vec<Cell>) -> Result<()> {
+        if self.rows.len() >= MAX_ROWS {
+            return Err(anyhow!("Sheet '{}' is limited to {} rows", self.name, MAX_ROWS));
+        }
+        self.rows.push(row);
+        Ok(())
+    }
+}
+
+/// Encode sheets as an xlsx workbook
+///
+/// The whole workbook is assembled in memory before it is returned;
+/// responses are gzipped on the way out for clients that accept it.
+pub fn write_workbook(sheets: &[Sheet]) -> Result<Vec<u8>> {
+    let mut workbook = Workbook::new();
+    let date_time = Format::new().set_num_format(DATE_TIME_FORMAT);
+
+    for sheet in sheets {
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(&sheet.name)?;
+
+        for (row, cells) in sheet.rows.iter().enumerate() {
+            let row = u32::try_from(row)?;
+            for (column, cell) in cells.iter().enumerate() {
+                let column = u16::try_from(column)?;
+                match cell {
+                    Cell::Empty => {}
+                    // Excel has no NaN or infinity; leave those cells blank
+                    Cell::Number(value) if !value.is_finite() => {}
+                    Cell::Number(value) => {
+                        worksheet.write_number(row, column, *value)?;
+                    }
+                    Cell::Text(text) => {
+                        worksheet.write_string(row, column, text)?;
+                    }
+                    Cell::DateTime(time) => {
+                        worksheet.write_datetime_with_format(row, column, time.naive_utc(), &date_time)?;
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(workbook.save_to_buffer()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{open_workbook_from_rs, Data, DataType, Reader, Xlsx};
+    use chrono::TimeZone;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_workbook_reads_back() -> Result<()> {
+        let noon = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let mut sheet = Sheet::new("Values", &["when", "value", "note"]);
+        sheet.push(vec![Cell::DateTime(noon), Cell::Number(1.5), Cell::Text("a<b & \"c\"".to_string())])?;
+        sheet.push(vec![Cell::Empty, Cell::Number(f64::NAN), None::<String>.into()])?;
+
+        let bytes = write_workbook(&[sheet, Sheet::new("Empty", &["only"])])?;
+        let mut workbook: Xlsx<_> = open_workbook_from_rs(Cursor::new(bytes))?;
+        assert_eq!(workbook.sheet_names(), vec!["Values", "Empty"]);
+
+        let range = workbook.worksheet_range("Values")?;
+        assert_eq!(range.get_value((0, 0)), Some(&Data::String("when".to_string())));
+        assert_eq!(range.get_value((1, 0)).and_then(|cell| cell.as_datetime()), Some(noon.naive_utc()));
+        assert_eq!(range.get_value((1, 1)), Some(&Data::Float(1.5)));
+        assert_eq!(range.get_value((1, 2)), Some(&Data::String("a<b & \"c\"".to_string())));
+        // Blank and non-finite cells are left out
+        assert!(range.get_value((2, 1)).is_none_or(|cell| cell.is_empty()));
+
+        Ok(())
+    }
+}
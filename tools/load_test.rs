@@ -1,6 +1,8 @@
-use rand::Rng;
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use reqwest::Client;
+use serde::Serialize;
 use serde_json::{json, Value};
+use std::collections::BTreeMap;
 use std::time::{Duration, Instant};
 use tokio::time::sleep;
 use tokio::task;
@@ -10,6 +12,8 @@ const NUM_SENSORS: usize = 10;
 const NUM_READINGS_PER_SENSOR: usize = 1000;
 const BATCH_SIZE: usize = 100;
 const CONCURRENT_REQUESTS: usize = 5;
+const MIX_DURATION_SECS: u64 = 10;
+const MIX_READ_RATIO: f64 = 0.8;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -67,7 +71,6 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let total_readings = NUM_SENSORS * NUM_READINGS_PER_SENSOR;
     println!("  Total readings to generate: {}", total_readings);
     
-    let mut rng = rand::thread_rng();
     let start_time = Instant::now();
     
     // Create multiple worker tasks
@@ -86,6 +89,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         let client_clone = client.clone();
         
         handles.push(task::spawn(async move {
+            let mut rng = StdRng::from_entropy();
             let mut readings_inserted = 0;
             
             for &sensor_id in &sensor_chunk {
@@ -145,89 +149,28 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("  Inserted {} readings in {:.2} seconds", total_inserted, elapsed.as_secs_f64());
     println!("  Throughput: {:.2} readings/second", throughput);
     
-    // 3. Query performance test
-    println!("\nStep 3: Testing query performance...");
-    
-    // Test 1: Get all readings for a single sensor
-    let test_sensor_id = sensor_ids[0];
-    let start_time_query = Instant::now();
-    
-    let response = client
-        .get(&format!("{}/readings", API_URL))
-        .query(&[
-            ("sensor_id", test_sensor_id.to_string()),
-            ("limit", "1000".to_string()),
-        ])
-        .send()
-        .await?;
-    
-    let query_elapsed = start_time_query.elapsed();
-    
-    if response.status().is_success() {
-        let data: Value = response.json().await?;
-        let readings_count = data["readings"].as_array().map_or(0, |arr| arr.len());
-        println!("  Query 1: Get all readings for sensor {} - {:.2} ms ({} readings)", 
-                 test_sensor_id, query_elapsed.as_millis(), readings_count);
-    }
-    
-    // Test 2: Get readings in a time range
-    let now = chrono::Utc::now().timestamp();
-    let one_hour_ago = now - 3600;
-    
-    let start_time_query = Instant::now();
-    
-    let response = client
-        .get(&format!("{}/readings", API_URL))
-        .query(&[
-            ("start_time", one_hour_ago.to_string()),
-            ("end_time", now.to_string()),
-            ("limit", "1000".to_string()),
-        ])
-        .send()
-        .await?;
-    
-    let query_elapsed = start_time_query.elapsed();
-    
-    if response.status().is_success() {
-        let data: Value = response.json().await?;
-        let readings_count = data["readings"].as_array().map_or(0, |arr| arr.len());
-        println!("  Query 2: Get readings in time range - {:.2} ms ({} readings)", 
-                 query_elapsed.as_millis(), readings_count);
-    }
-    
-    // Test 3: Get current readings for all sensors (dashboard query)
-    let start_time_query = Instant::now();
+    // 3. Mixed read/write workload
+    let config = MixConfig::from_env();
+    println!(
+        "\nStep 3: Running query mix for {} seconds ({} workers, {:.0}% reads)...",
+        config.duration.as_secs(),
+        config.concurrency,
+        config.read_ratio * 100.0
+    );
     
-    let response = client
-        .get(&format!("{}/status/current", API_URL))
-        .send()
-        .await?;
+    let report = run_query_mix(&client, API_URL, &sensor_ids, &config).await;
     
-    let query_elapsed = start_time_query.elapsed();
-    
-    if response.status().is_success() {
-        println!("  Query 3: Get current status - {:.2} ms", query_elapsed.as_millis());
+    for (kind, stats) in &report.queries {
+        println!(
+            "  {:<22} {:>6} ok {:>4} err  {:>8.1} req/s  p50 {:>7.2} ms  p95 {:>7.2} ms  p99 {:>7.2} ms",
+            kind, stats.count, stats.errors, stats.throughput, stats.p50_ms, stats.p95_ms, stats.p99_ms
+        );
     }
+    println!("  Total throughput: {:.1} requests/second", report.throughput);
     
-    // Test 4: Time-series visualization data
-    let start_time_query = Instant::now();
-    
-    let response = client
-        .get(&format!("{}/visualizations/time-series", API_URL))
-        .query(&[
-            ("sensor_ids", format!("{},{}", sensor_ids[0], sensor_ids[1])),
-            ("start_time", (now - 86400).to_string()), // Last 24 hours
-            ("end_time", now.to_string()),
-            ("interval", "hour".to_string()),
-        ])
-        .send()
-        .await?;
-    
-    let query_elapsed = start_time_query.elapsed();
-    
-    if response.status().is_success() {
-        println!("  Query 4: Time-series visualization - {:.2} ms", query_elapsed.as_millis());
-    }
+    let report_path = std::env::var("LOAD_TEST_REPORT").unwrap_or_else(|_| "load_test_report.json".to_string());
+    std::fs::write(&report_path, serde_json::to_string_pretty(&report)?)?;
+    println!("  Wrote JSON report to {}", report_path);
     
     // 4. Cleanup (optional)
     if std::env::var("KEEP_TEST_DATA").is_err() {
@@ -251,4 +194,235 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("\nLoad test completed successfully!");
     
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Shape of the mixed workload, overridable through the environment
+#[derive(Debug, Clone)]
+struct MixConfig {
+    duration: Duration,
+    concurrency: usize,
+    read_ratio: f64,  // Share of requests that are reads, 0.0-1.0
+}
+
+impl MixConfig {
+    /// Read MIX_DURATION_SECS, MIX_CONCURRENCY and MIX_READ_RATIO, falling back to defaults
+    fn from_env() -> Self {
+        let var = |name: &str| std::env::var(name).ok();
+        Self {
+            duration: Duration::from_secs(
+                var("MIX_DURATION_SECS").and_then(|v| v.parse().ok()).unwrap_or(MIX_DURATION_SECS),
+            ),
+            concurrency: var("MIX_CONCURRENCY").and_then(|v| v.parse().ok()).unwrap_or(CONCURRENT_REQUESTS),
+            read_ratio: var("MIX_READ_RATIO")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(MIX_READ_RATIO)
+                .clamp(0.0, 1.0),
+        }
+    }
+}
+
+/// Latency and throughput for one kind of request
+#[derive(Debug, Serialize)]
+struct QueryStats {
+    count: usize,
+    errors: usize,
+    throughput: f64,
+    mean_ms: f64,
+    p50_ms: f64,
+    p90_ms: f64,
+    p95_ms: f64,
+    p99_ms: f64,
+    max_ms: f64,
+}
+
+/// Results of a query mix run, serialized as the JSON report
+#[derive(Debug, Serialize)]
+struct MixReport {
+    duration_secs: f64,
+    concurrency: usize,
+    read_ratio: f64,
+    total_requests: usize,
+    throughput: f64,
+    queries: BTreeMap<String, QueryStats>,
+}
+
+/// One timed request: (kind, latency, succeeded)
+type Sample = (&'static str, Duration, bool);
+
+/// Run randomized reads and writes from several workers until the duration elapses
+async fn run_query_mix(client: &Client, api_url: &str, sensor_ids: &[i64], config: &MixConfig) -> MixReport {
+    let started = Instant::now();
+    let deadline = started + config.duration;
+    
+    let mut handles = Vec::with_capacity(config.concurrency);
+    for _ in 0..config.concurrency {
+        let client = client.clone();
+        let api_url = api_url.to_string();
+        let sensor_ids = sensor_ids.to_vec();
+        let read_ratio = config.read_ratio;
+        
+        handles.push(task::spawn(async move {
+            let mut rng = StdRng::from_entropy();
+            let mut samples: Vec<Sample> = Vec::new();
+            
+            while Instant::now() < deadline && !sensor_ids.is_empty() {
+                let sensor_id = sensor_ids[rng.gen_range(0..sensor_ids.len())];
+                let now = chrono::Utc::now().timestamp();
+                
+                let (kind, request) = if rng.gen_bool(read_ratio) {
+                    // A random window within the last day
+                    let window = rng.gen_range(300..=86400);
+                    let start = now - rng.gen_range(window..=86400);
+                    let end = (start + window).to_string();
+                    let start = start.to_string();
+                    
+                    match rng.gen_range(0..4) {
+                        0 => {
+                            let page = rng.gen_range(0..5usize);
+                            let query = [
+                                ("sensor_id", sensor_id.to_string()),
+                                ("limit", "100".to_string()),
+                                ("offset", (page * 100).to_string()),
+                            ];
+                            ("readings_page", client.get(format!("{}/readings", api_url)).query(&query))
+                        },
+                        1 => {
+                            let query = [("start_time", start), ("end_time", end), ("limit", "500".to_string())];
+                            ("readings_time_range", client.get(format!("{}/readings", api_url)).query(&query))
+                        },
+                        2 => (
+                            "current_reading",
+                            client.get(format!("{}/readings/current/{}", api_url, sensor_id)),
+                        ),
+                        _ => {
+                            let query = [
+                                ("sensor_ids", sensor_id.to_string()),
+                                ("start_time", start),
+                                ("end_time", end),
+                                ("interval", "hour".to_string()),
+                            ];
+                            ("time_series", client.get(format!("{}/visualizations/time-series", api_url)).query(&query))
+                        },
+                    }
+                } else {
+                    let reading = json!({
+                        "sensor_id": sensor_id,
+                        "timestamp": now,
+                        "value": rng.gen_range(0.0..50.0),
+                        "change_type": "periodic"
+                    });
+                    ("insert_reading", client.post(format!("{}/readings", api_url)).json(&reading))
+                };
+                
+                let sent = Instant::now();
+                let ok = match request.send().await {
+                    Ok(response) => response.status().is_success(),
+                    Err(_) => false,
+                };
+                samples.push((kind, sent.elapsed(), ok));
+            }
+            
+            samples
+        }));
+    }
+    
+    let mut samples = Vec::new();
+    for handle in handles {
+        samples.extend(handle.await.unwrap_or_default());
+    }
+    
+    build_report(samples, started.elapsed(), config)
+}
+
+/// Summarize samples per request kind
+fn build_report(samples: Vec<Sample>, elapsed: Duration, config: &MixConfig) -> MixReport {
+    let elapsed_secs = elapsed.as_secs_f64().max(f64::EPSILON);
+    let total_requests = samples.len();
+    
+    let mut by_kind: BTreeMap<&str, (Vec<f64>, usize)> = BTreeMap::new();
+    for (kind, latency, ok) in samples {
+        let entry = by_kind.entry(kind).or_default();
+        if ok {
+            entry.0.push(latency.as_secs_f64() * 1000.0);
+        } else {
+            entry.1 += 1;
+        }
+    }
+    
+    let queries = by_kind
+        .into_iter()
+        .map(|(kind, (mut latencies, errors))| {
+            latencies.sort_by(f64::total_cmp);
+            let mean_ms = if latencies.is_empty() {
+                0.0
+            } else {
+                latencies.iter().sum::<f64>() / latencies.len() as f64
+            };
+            
+            let stats = QueryStats {
+                count: latencies.len(),
+                errors,
+                throughput: latencies.len() as f64 / elapsed_secs,
+                mean_ms,
+                p50_ms: percentile(&latencies, 50.0),
+                p90_ms: percentile(&latencies, 90.0),
+                p95_ms: percentile(&latencies, 95.0),
+                p99_ms: percentile(&latencies, 99.0),
+                max_ms: latencies.last().copied().unwrap_or(0.0),
+            };
+            (kind.to_string(), stats)
+        })
+        .collect();
+    
+    MixReport {
+        duration_secs: elapsed_secs,
+        concurrency: config.concurrency,
+        read_ratio: config.read_ratio,
+        total_requests,
+        throughput: total_requests as f64 / elapsed_secs,
+        queries,
+    }
+}
+
+/// Nearest-rank percentile of ascending latencies; 0 when there are none
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, Router};
+
+    #[tokio::test]
+    async fn test_query_mix_produces_report() -> Result<(), Box<dyn std::error::Error>> {
+        // Stand-in server that accepts every request
+        let app = Router::new().fallback(|| async { StatusCode::OK });
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await?;
+        let api_url = format!("http://{}/api", listener.local_addr()?);
+        tokio::spawn(async move { axum::serve(listener, app).await });
+        
+        let config = MixConfig {
+            duration: Duration::from_secs(1),
+            concurrency: 2,
+            read_ratio: 0.5,
+        };
+        let report = run_query_mix(&Client::new(), &api_url, &[1, 2, 3], &config).await;
+        
+        assert!(report.total_requests > 0);
+        assert!(report.duration_secs >= 1.0);
+        assert!(report.queries.contains_key("insert_reading"));
+        assert!(report.queries.values().all(|stats| stats.errors == 0));
+        assert!(report.queries.values().all(|stats| stats.p50_ms <= stats.p99_ms));
+        
+        let json: Value = serde_json::from_str(&serde_json::to_string(&report)?)?;
+        assert_eq!(json["total_requests"].as_u64(), Some(report.total_requests as u64));
+        
+        Ok(())
+    }
+}